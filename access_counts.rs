@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::subscriptions::Change;
+
+/// How often a block was accessed, as returned by `access_counts`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AccessCounts {
+    pub reads: u64,  // Successful reads
+    pub writes: u64, // Inserts and updates that produced the block's data
+}
+
+impl AccessCounts {
+    /// Returns reads and writes together.
+    pub fn total(&self) -> u64 {
+        self.reads + self.writes
+    }
+}
+
+/// Simulated access latency, modelling a small cache in front of slower memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Latency {
+    pub cache: Duration,     // Delay for blocks in the cache
+    pub memory: Duration,    // Delay for every other block
+    pub cache_blocks: usize, // How many of the most recently accessed blocks the cache holds
+}
+
+/// Read and write counts of one block.
+#[derive(Debug, Default)]
+pub(crate) struct Counts {
+    reads: AtomicU64,
+    writes: AtomicU64,
+}
+
+/// Simulated latency and the cache it models.
+#[derive(Debug)]
+pub(crate) struct Tiers {
+    latency: Latency,
+    cached: Mutex<VecDeque<BlockId>>, // Cached blocks, most recently accessed first
+}
+
+impl MemoryManager {
+    /// Returns how often block `id` was read and written since it was stored,
+    /// or `None` if it does not exist.
+    ///
+    /// Counts start over when a block is renamed, as if it were newly
+    /// written, and when it is brought back by a snapshot restore or a rollback.
+    pub fn access_counts(&self, id: BlockId) -> Option<AccessCounts> {
+        if !self.exists(id) {
+            return None;
+        }
+        let counts = self.access_counts.get(&id);
+        let load = |counter: Option<&AtomicU64>| counter.map_or(0, |counter| counter.load(Ordering::Relaxed));
+        Some(AccessCounts {
+            reads: load(counts.map(|counts| &counts.reads)),
+            writes: load(counts.map(|counts| &counts.writes)),
+        })
+    }
+
+    /// Returns the `n` most accessed blocks, counting reads and writes.
+    ///
+    /// Ties are broken by ID, so the order is deterministic.
+    pub fn hot_blocks(&self, n: usize) -> Vec<BlockId> {
+        let mut ids: Vec<(u64, BlockId)> = self
+            .access_counts
+            .keys()
+            .filter_map(|&id| Some((self.access_counts(id)?.total(), id)))
+            .collect();
+        ids.sort_unstable_by_key(|&(total, id)| (std::cmp::Reverse(total), id));
+        ids.into_iter().take(n).map(|(_, id)| id).collect()
+    }
+
+    /// Sets every block's read and write counts back to zero.
+    pub fn reset_access_counts(&mut self) {
+        for counts in self.access_counts.values() {
+            counts.reads.store(0, Ordering::Relaxed);
+            counts.writes.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Turns simulated access latency on, or off with `None`.
+    ///
+    /// # Behavior
+    ///
+    /// Every read, insert, and update then sleeps for `latency.cache` if the
+    /// block is among the `latency.cache_blocks` most recently accessed, and
+    /// for `latency.memory` otherwise, after which the block is the most
+    /// recently accessed. This makes workloads with good locality run
+    /// measurably faster, as they would on real hardware. Sleeps are skipped
+    /// on wasm32, where blocking is not allowed.
+    pub fn set_latency(&mut self, latency: Option<Latency>) {
+        self.tiers = latency.map(|latency| Tiers { latency, cached: Mutex::new(VecDeque::new()) });
+    }
+
+    /// Returns the simulated latency, if it is on.
+    pub fn latency(&self) -> Option<Latency> {
+        self.tiers.as_ref().map(|tiers| tiers.latency)
+    }
+
+    /// Counts a successful read of block `id` and applies its latency.
+    pub(crate) fn count_read(&self, id: BlockId) {
+        if let Some(counts) = self.access_counts.get(&id) {
+            counts.reads.fetch_add(1, Ordering::Relaxed);
+        }
+        self.simulate_latency(id);
+    }
+
+    /// Counts the write behind `change` and applies its latency, or forgets
+    /// the counts of a deleted block.
+    pub(crate) fn count_change(&mut self, change: &Change) {
+        match *change {
+            Change::Inserted { id, .. } => {
+                self.access_counts.insert(id, Counts { reads: AtomicU64::new(0), writes: AtomicU64::new(1) });
+                self.simulate_latency(id);
+            }
+            Change::Updated { id, .. } => {
+                self.access_counts.entry(id).or_default().writes.fetch_add(1, Ordering::Relaxed);
+                self.simulate_latency(id);
+            }
+            Change::Deleted { id } => {
+                self.access_counts.remove(&id);
+            }
+        }
+    }
+
+    /// Sleeps for the latency of accessing block `id` and moves it to the
+    /// front of the simulated cache.
+    fn simulate_latency(&self, id: BlockId) {
+        let Some(tiers) = &self.tiers else {
+            return;
+        };
+        let delay = {
+            let mut cached = tiers.cached.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let hit = cached.iter().position(|&cached_id| cached_id == id);
+            if let Some(index) = hit {
+                cached.remove(index);
+            }
+            cached.push_front(id);
+            cached.truncate(tiers.latency.cache_blocks);
+            if hit.is_some() {
+                tiers.latency.cache
+            } else {
+                tiers.latency.memory
+            }
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        std::thread::sleep(delay);
+        // The browser's main thread cannot block, so delays are skipped there
+        #[cfg(target_arch = "wasm32")]
+        let _ = delay;
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::info;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::subscriptions::Change;
+
+/// Which access time to query or sort by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// The last successful `read`.
+    Read,
+    /// The last insert, update, copy, or rename that produced the block.
+    Write,
+}
+
+/// Last-read and last-write times of one block, in nanoseconds since the
+/// Unix epoch (0 = never).
+#[derive(Debug, Default)]
+pub(crate) struct AccessTimes {
+    read: AtomicU64,
+    write: AtomicU64,
+}
+
+impl AccessTimes {
+    fn get(&self, access: Access) -> Option<SystemTime> {
+        let nanos = match access {
+            Access::Read => self.read.load(Ordering::Relaxed),
+            Access::Write => self.write.load(Ordering::Relaxed),
+        };
+        (nanos > 0).then(|| UNIX_EPOCH + Duration::from_nanos(nanos))
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(1, |elapsed| elapsed.as_nanos().max(1) as u64)
+}
+
+impl MemoryManager {
+    /// Returns when block `id` was last read or written.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(time)` of the most recent access of that kind.
+    /// - `None` if the block does not exist or has not been accessed that way
+    ///   since it was stored (blocks brought back by a snapshot restore or a
+    ///   rollback start without times).
+    pub fn last_access(&self, id: BlockId, access: Access) -> Option<SystemTime> {
+        self.allocations.get(&id)?;
+        self.access_times.get(&id)?.get(access)
+    }
+
+    /// Returns the IDs of all blocks, least recently accessed first.
+    ///
+    /// Blocks never accessed that way come first. Ties are broken by ID, so
+    /// the order is deterministic.
+    pub fn ids_by_access(&self, access: Access) -> Vec<BlockId> {
+        let mut ids: Vec<(Option<SystemTime>, BlockId)> =
+            self.allocations.keys().map(|&id| (self.last_access(id, access), id)).collect();
+        ids.sort_unstable();
+        ids.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Returns the IDs of blocks accessed at or after `since`, in ID order.
+    pub fn accessed_since(&self, access: Access, since: SystemTime) -> Vec<BlockId> {
+        self.allocations
+            .keys()
+            .copied()
+            .filter(|&id| self.last_access(id, access).is_some_and(|time| time >= since))
+            .collect()
+    }
+
+    /// Logs a dump with blocks ordered from least to most recently accessed.
+    pub fn dump_by_access(&self, access: Access) {
+        info!("{}", self.render_blocks(&self.ids_by_access(access), false).trim_end());
+    }
+
+    /// Records a successful read of block `id`.
+    pub(crate) fn record_read(&self, id: BlockId) {
+        if let Some(times) = self.access_times.get(&id) {
+            times.read.store(now(), Ordering::Relaxed);
+        }
+    }
+
+    /// Records the write behind `change`, or forgets the times of a deleted block.
+    pub(crate) fn record_write(&mut self, change: &Change) {
+        match *change {
+            Change::Inserted { id, .. } => {
+                self.access_times.insert(id, AccessTimes { read: AtomicU64::new(0), write: AtomicU64::new(now()) });
+            }
+            Change::Updated { id, .. } => {
+                self.access_times.entry(id).or_default().write.store(now(), Ordering::Relaxed);
+            }
+            Change::Deleted { id } => {
+                self.access_times.remove(&id);
+            }
+        }
+    }
+}
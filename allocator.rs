@@ -0,0 +1,66 @@
+use crate::builder::Strategy;
+
+/// Decides where blocks go in memory by tracking which bytes are free.
+///
+/// `MemoryManager` asks its allocator for space on every insert and hands
+/// the space back on delete; everything else (the allocation table,
+/// checksums, encoding) is independent of the strategy. Implementations
+/// are `FreeList` (the default) and `BitmapAllocator`.
+pub trait Allocator: Send + Sync {
+    /// Returns which built-in strategy this is, so a serialized manager can
+    /// recreate its allocator. Custom allocators return `None`.
+    fn strategy(&self) -> Option<Strategy> {
+        None
+    }
+
+    /// Returns the number of bytes of memory being managed.
+    fn capacity(&self) -> usize;
+
+    /// Returns the unit space is handed out in. Blocks occupy whole units,
+    /// so up to `granularity() - 1` bytes after each block are lost to
+    /// internal fragmentation.
+    fn granularity(&self) -> usize {
+        1
+    }
+
+    /// Reserves `size` bytes starting at a multiple of `alignment`.
+    ///
+    /// # Returns
+    ///
+    /// The start of the reserved space, or `None` if nothing free can hold
+    /// it. `alignment` must be a power of two.
+    fn allocate(&mut self, size: usize, alignment: usize) -> Option<usize>;
+
+    /// Reserves exactly `start..start + size`.
+    ///
+    /// Returns `false`, leaving the allocator unchanged, unless the whole
+    /// range is free. An empty range always succeeds.
+    fn reserve(&mut self, start: usize, size: usize) -> bool;
+
+    /// Returns `start..start + size`, previously reserved, to the free space.
+    fn free(&mut self, start: usize, size: usize);
+
+    /// Forgets all reservations and marks everything outside `used` free.
+    ///
+    /// `used` may be in any order; overlapping or empty regions are tolerated.
+    fn rebuild(&mut self, used: Vec<(usize, usize)>);
+
+    /// Returns the total number of free bytes.
+    fn free_bytes(&self) -> usize;
+
+    /// Returns the size of the largest free region, or 0 if memory is full.
+    fn largest(&self) -> usize;
+
+    /// Returns the free regions as `(start, size)` pairs in address order,
+    /// with adjacent free space merged.
+    fn regions(&self) -> Vec<(usize, usize)>;
+
+    /// Returns a copy of this allocator, for snapshots.
+    fn box_clone(&self) -> Box<dyn Allocator>;
+}
+
+impl Clone for Box<dyn Allocator> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
@@ -0,0 +1,95 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::handle::Handle;
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// Commands waiting for the worker before `send` waits for room.
+const QUEUE: usize = 64;
+
+/// A command run by the worker against the manager.
+type Command = Box<dyn FnOnce(&mut MemoryManager) + Send>;
+
+/// A `MemoryManager` for async code, owned by a worker thread.
+///
+/// Every operation is sent to the worker as a command and awaited, so the
+/// manager is never locked and a slow operation (a file-backed write, a
+/// simulated latency) blocks only the worker, not the executor. Commands
+/// run one at a time in the order they were sent, so each sees and leaves
+/// a consistent state. Cloning gives another handle to the same manager;
+/// the worker exits once every handle is dropped.
+#[derive(Clone)]
+pub struct AsyncMemoryManager {
+    commands: mpsc::Sender<Command>,
+}
+
+impl AsyncMemoryManager {
+    /// Moves `manager` to a new worker thread.
+    ///
+    /// The worker is a plain thread rather than a tokio task, so this works
+    /// inside or outside a runtime, and never ties up a runtime thread.
+    pub fn new(mut manager: MemoryManager) -> Self {
+        let (commands, mut queue) = mpsc::channel::<Command>(QUEUE);
+        thread::Builder::new()
+            .name("memory-manager".to_string())
+            .spawn(move || {
+                while let Some(command) = queue.blocking_recv() {
+                    command(&mut manager);
+                }
+            })
+            .expect("spawning the memory manager worker");
+        Self { commands }
+    }
+
+    /// Runs `f` on the worker with exclusive access to the manager.
+    ///
+    /// # Panics
+    ///
+    /// If `f` panics; the panic is resumed here. The worker keeps serving
+    /// other handles, since every operation leaves the manager consistent.
+    ///
+    /// # Behavior
+    ///
+    /// Use this to group several operations so no other command can observe
+    /// the state between them.
+    pub async fn with<R: Send + 'static>(&self, f: impl FnOnce(&mut MemoryManager) -> R + Send + 'static) -> R {
+        let (reply, result) = oneshot::channel();
+        let command: Command = Box::new(move |manager: &mut MemoryManager| {
+            let _ = reply.send(panic::catch_unwind(AssertUnwindSafe(|| f(manager))));
+        });
+        if self.commands.send(command).await.is_err() {
+            panic!("the memory manager worker has stopped");
+        }
+        match result.await.expect("the worker replies to every command") {
+            Ok(value) => value,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    /// See [`MemoryManager::insert`].
+    pub async fn insert(&self, id: BlockId, data: Vec<u8>) -> Option<Handle> {
+        self.with(move |manager| manager.insert(id, data)).await
+    }
+
+    /// See [`MemoryManager::read`].
+    pub async fn read(&self, id: BlockId) -> Option<Vec<u8>> {
+        self.with(move |manager| manager.read(id)).await
+    }
+
+    /// See [`MemoryManager::update`].
+    pub async fn update(&self, id: BlockId, data: Vec<u8>) -> Option<()> {
+        self.with(move |manager| manager.update(id, data)).await
+    }
+
+    /// See [`MemoryManager::delete`].
+    pub async fn delete(&self, id: BlockId) -> Option<()> {
+        self.with(move |manager| manager.delete(id)).await
+    }
+
+    /// Returns the sequence number of the current state.
+    pub async fn sequence(&self) -> u64 {
+        self.with(|manager| manager.sequence()).await
+    }
+}
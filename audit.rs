@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::subscriptions::Change;
+
+/// A change recorded in a block's audit trail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuditAction {
+    /// The block was stored with this many bytes, by an insert, copy, rename, or split.
+    Inserted { size: usize },
+    /// The block was overwritten with this many bytes.
+    Updated { size: usize },
+    /// The block was deleted, or renamed, split, or merged away.
+    Deleted,
+}
+
+/// One entry of a block's audit trail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub time: SystemTime,      // When the change was made
+    pub sequence: u64,         // `sequence()` once the change was applied
+    pub action: AuditAction,   // What happened
+    pub actor: Option<String>, // Who made the change, as set with `set_actor`
+}
+
+/// Everything the audit trail knows about one ID, as returned by `audit`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditReport {
+    pub id: BlockId,                   // The ID the report is about
+    pub inserted_by: Option<String>,   // Actor of the most recent insert, if it had one
+    pub updates: usize,                // Updates recorded over the whole trail
+    pub reads: u64,                    // Successful reads over the whole trail
+    pub last_read: Option<SystemTime>, // Time of the most recent successful read
+    pub entries: Vec<AuditEntry>,      // Every recorded change, oldest first
+}
+
+/// The audit trail of one ID.
+#[derive(Debug, Default)]
+pub(crate) struct Trail {
+    entries: Vec<AuditEntry>,
+    reads: AtomicU64,
+    last_read: AtomicU64, // Nanoseconds since the Unix epoch (0 = never)
+}
+
+impl MemoryManager {
+    /// Creates a new `MemoryManager` that keeps an audit trail. See `set_audit`.
+    pub fn with_audit() -> Self {
+        Self { audit: Some(BTreeMap::new()), ..Self::new() }
+    }
+
+    /// Turns the audit trail on or off.
+    ///
+    /// # Behavior
+    ///
+    /// While on, every insert, update, and delete is recorded per ID with
+    /// its time, sequence number, and the current actor, and reads are
+    /// counted. Trails outlive the blocks they describe, so the full story
+    /// of an ID stays available after it is deleted, and like `metrics()`
+    /// they are not rewound by snapshot restores, rollbacks, or undo. Turning
+    /// the trail off discards everything recorded so far.
+    pub fn set_audit(&mut self, enabled: bool) {
+        if enabled {
+            self.audit.get_or_insert_with(BTreeMap::new);
+        } else {
+            self.audit = None;
+        }
+    }
+
+    /// Sets who the audit trail credits with the changes that follow, e.g. a
+    /// student or a simulation step. `None` records changes without an actor.
+    pub fn set_actor(&mut self, actor: Option<&str>) {
+        self.actor = actor.map(str::to_string);
+    }
+
+    /// Reports the audit trail of `id`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(report)` if anything was recorded for `id`, even if the block
+    ///   has since been deleted.
+    /// - `None` if the audit trail is off or `id` was never used while it was on.
+    pub fn audit(&self, id: BlockId) -> Option<AuditReport> {
+        let trail = self.audit.as_ref()?.get(&id)?;
+        let inserted = trail.entries.iter().rev().find(|entry| matches!(entry.action, AuditAction::Inserted { .. }));
+        let last_read = trail.last_read.load(Ordering::Relaxed);
+        Some(AuditReport {
+            id,
+            inserted_by: inserted.and_then(|entry| entry.actor.clone()),
+            updates: trail.entries.iter().filter(|entry| matches!(entry.action, AuditAction::Updated { .. })).count(),
+            reads: trail.reads.load(Ordering::Relaxed),
+            last_read: (last_read > 0).then(|| UNIX_EPOCH + Duration::from_nanos(last_read)),
+            entries: trail.entries.clone(),
+        })
+    }
+
+    /// Reports the audit trail of every ID recorded, in ID order.
+    pub fn audit_all(&self) -> Vec<AuditReport> {
+        let Some(trails) = &self.audit else {
+            return Vec::new();
+        };
+        trails.keys().filter_map(|&id| self.audit(id)).collect()
+    }
+
+    /// Counts a successful read of block `id` in its audit trail, if it has one.
+    pub(crate) fn audit_read(&self, id: BlockId) {
+        if let Some(trail) = self.audit.as_ref().and_then(|trails| trails.get(&id)) {
+            trail.reads.fetch_add(1, Ordering::Relaxed);
+            trail.last_read.store(nanos_since_epoch(SystemTime::now()), Ordering::Relaxed);
+        }
+    }
+
+    /// Appends `change` to the audit trail of its ID.
+    pub(crate) fn audit_change(&mut self, change: &Change) {
+        let Some(trails) = &mut self.audit else {
+            return;
+        };
+        let action = match *change {
+            Change::Inserted { size, .. } => AuditAction::Inserted { size },
+            Change::Updated { size, .. } => AuditAction::Updated { size },
+            Change::Deleted { .. } => AuditAction::Deleted,
+        };
+        let entry = AuditEntry { time: SystemTime::now(), sequence: self.sequence, action, actor: self.actor.clone() };
+        trails.entry(change.id()).or_default().entries.push(entry);
+    }
+}
+
+fn nanos_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(1, |elapsed| elapsed.as_nanos().max(1) as u64)
+}
+
+/// Formats a time as seconds since the Unix epoch, to the millisecond.
+fn seconds(time: SystemTime) -> String {
+    format!("{:.3}", nanos_since_epoch(time) as f64 / 1e9)
+}
+
+impl fmt::Display for AuditReport {
+    /// Formats the report as a summary line followed by one line per entry.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inserted_by = self.inserted_by.as_deref().unwrap_or("-");
+        write!(f, "ID {}: inserted by {}, {} updates, {} reads", self.id, inserted_by, self.updates, self.reads)?;
+        if let Some(time) = self.last_read {
+            write!(f, ", last read at {}", seconds(time))?;
+        }
+        for entry in &self.entries {
+            let action = match entry.action {
+                AuditAction::Inserted { size } => format!("inserted {} bytes", size),
+                AuditAction::Updated { size } => format!("updated to {} bytes", size),
+                AuditAction::Deleted => "deleted".to_string(),
+            };
+            let actor = entry.actor.as_deref().unwrap_or("-");
+            write!(f, "\n  seq {} at {} by {}: {}", entry.sequence, seconds(entry.time), actor, action)?;
+        }
+        Ok(())
+    }
+}
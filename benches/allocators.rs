@@ -0,0 +1,84 @@
+//! Compares allocation strategies on the same randomized workloads.
+//!
+//! Each workload from `project2::workload` runs against a fresh manager per
+//! strategy, reporting throughput, how many operations failed for lack of
+//! space, and the mean and final fragmentation of the free space. A second
+//! table follows fragmentation over time for the churn-heavy workload.
+//!
+//! Run with `cargo bench --bench allocators`.
+
+use std::time::Instant;
+
+use project2::allocator::Allocator;
+use project2::bitmap::BitmapAllocator;
+use project2::free_list::FreeList;
+use project2::memory_manager::MemoryManager;
+use project2::workload::{SizeDistribution, Workload, WorkloadReport};
+
+const CAPACITY: usize = 64 * 1024;
+const SAMPLE_EVERY: usize = 5_000;
+
+/// Creates a fresh allocator over `CAPACITY` bytes.
+type NewAllocator = fn() -> Box<dyn Allocator>;
+
+/// The strategies to compare, by name.
+fn strategies() -> Vec<(&'static str, NewAllocator)> {
+    vec![
+        ("free list", || Box::new(FreeList::new(CAPACITY))),
+        ("bitmap/8", || Box::new(BitmapAllocator::new(CAPACITY, 8))),
+        ("bitmap/64", || Box::new(BitmapAllocator::new(CAPACITY, 64))),
+    ]
+}
+
+/// Runs `workload` against a fresh manager using `allocator`.
+///
+/// # Returns
+///
+/// The report and the operations applied per second.
+fn run(workload: &Workload, allocator: Box<dyn Allocator>) -> (WorkloadReport, f64) {
+    let ops = workload.generate();
+    let mut manager = MemoryManager::with_allocator(allocator);
+    let started = Instant::now();
+    let report = manager.run_workload(&ops, SAMPLE_EVERY);
+    (report, ops.len() as f64 / started.elapsed().as_secs_f64())
+}
+
+fn main() {
+    let base = Workload { operations: 50_000, live_blocks: 300, ..Workload::default() };
+    let workloads = [
+        ("uniform", base),
+        ("fixed", Workload { sizes: SizeDistribution::Fixed(128), ..base }),
+        ("exponential", Workload { sizes: SizeDistribution::Exponential { mean: 96, max: 2048 }, ..base }),
+        ("high churn", Workload { churn: 0.45, updates: 0.05, reads: 0.0, ..base }),
+    ];
+
+    println!("{:>12} {:>10} {:>12} {:>8} {:>10} {:>10}", "workload", "strategy", "ops/s", "failed", "mean frag", "final frag");
+    for (name, workload) in &workloads {
+        for (strategy, allocator) in strategies() {
+            let (report, rate) = run(workload, allocator());
+            let last = report.samples.last().map_or(0.0, |sample| sample.fragmentation);
+            println!(
+                "{:>12} {:>10} {:>12.0} {:>8} {:>10.3} {:>10.3}",
+                name,
+                strategy,
+                rate,
+                report.failed,
+                report.mean_fragmentation(),
+                last
+            );
+        }
+    }
+
+    println!();
+    println!("fragmentation over time, high churn:");
+    let reports: Vec<(&str, WorkloadReport)> =
+        strategies().into_iter().map(|(strategy, allocator)| (strategy, run(&workloads[3].1, allocator()).0)).collect();
+    print!("{:>10}", "operation");
+    reports.iter().for_each(|(strategy, _)| print!(" {:>10}", strategy));
+    println!();
+    for (i, sample) in reports[0].1.samples.iter().enumerate() {
+        print!("{:>10}", sample.operation);
+        reports.iter().for_each(|(_, report)| print!(" {:>10.3}", report.samples[i].fragmentation));
+        println!();
+    }
+}
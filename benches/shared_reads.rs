@@ -0,0 +1,75 @@
+//! Measures how read throughput of a `SharedMemoryManager` scales with the
+//! number of reader threads.
+//!
+//! Each round fills a manager, then has every thread read random blocks for
+//! a fixed time, once through `read()`, which takes the lock shared, and
+//! once through `with()`, which takes it exclusively as every call did
+//! before reads could share it.
+//!
+//! Run with `cargo bench --bench shared_reads`.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use project2::memory_manager::{BlockId, MemoryManager};
+use project2::shared::SharedMemoryManager;
+
+const BLOCKS: BlockId = 256;
+const BLOCK_SIZE: usize = 128;
+const ROUND: Duration = Duration::from_millis(500);
+
+/// Reads random blocks from `threads` threads for one round.
+///
+/// # Returns
+///
+/// The number of reads completed per second across all threads.
+fn reads_per_second(shared: &SharedMemoryManager, threads: usize, exclusive: bool) -> f64 {
+    let stop = Arc::new(AtomicBool::new(false));
+    let total = Arc::new(AtomicU64::new(0));
+    let workers: Vec<_> = (0..threads)
+        .map(|seed| {
+            let (shared, stop, total) = (shared.clone(), stop.clone(), total.clone());
+            thread::spawn(move || {
+                // xorshift, so every thread reads its own spread of blocks
+                let mut state = seed as u64 * 0x9e37_79b9_7f4a_7c15 + 1;
+                let mut reads = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    let id = (state as usize % BLOCKS as usize) as BlockId;
+                    let data = if exclusive { shared.with(|manager| manager.read(id)) } else { shared.read(id) };
+                    assert_eq!(data.map(|data| data.len()), Some(BLOCK_SIZE));
+                    reads += 1;
+                }
+                total.fetch_add(reads, Ordering::Relaxed);
+            })
+        })
+        .collect();
+
+    let started = Instant::now();
+    thread::sleep(ROUND);
+    stop.store(true, Ordering::Relaxed);
+    workers.into_iter().for_each(|worker| worker.join().unwrap());
+    total.load(Ordering::Relaxed) as f64 / started.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let mut manager = MemoryManager::new();
+    for id in 0..BLOCKS {
+        manager.insert(id, vec![id as u8; BLOCK_SIZE]).expect("blocks fit in the default capacity");
+    }
+    let shared = SharedMemoryManager::new(manager);
+    let max = thread::available_parallelism().map_or(4, |n| n.get());
+
+    println!("{:>7} {:>16} {:>17} {:>8}", "threads", "shared reads/s", "exclusive reads/s", "ratio");
+    let mut threads = 1;
+    while threads <= max {
+        let shared_rate = reads_per_second(&shared, threads, false);
+        let exclusive_rate = reads_per_second(&shared, threads, true);
+        println!("{:>7} {:>16.0} {:>17.0} {:>7.2}x", threads, shared_rate, exclusive_rate, shared_rate / exclusive_rate);
+        threads *= 2;
+    }
+}
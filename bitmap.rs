@@ -0,0 +1,158 @@
+use crate::allocator::Allocator;
+use crate::builder::Strategy;
+
+/// Tracks free memory with one bit per fixed-size cell.
+///
+/// Every block occupies whole cells, so small blocks waste the rest of
+/// their last cell (internal fragmentation), but the bookkeeping has a
+/// fixed size no matter how fragmented memory gets. With a cell size of 1
+/// there is one bit per byte and no waste.
+#[derive(Clone, Debug)]
+pub struct BitmapAllocator {
+    capacity: usize, // Number of bytes managed
+    cell: usize,     // Bytes per cell
+    used: Vec<u64>,  // One bit per cell, set if the cell is reserved
+}
+
+impl BitmapAllocator {
+    /// Creates a bitmap covering `capacity` bytes in cells of `cell` bytes.
+    /// The last cell is shorter if `capacity` is not a multiple of `cell`.
+    ///
+    /// # Panics
+    ///
+    /// If `cell` is 0.
+    pub fn new(capacity: usize, cell: usize) -> Self {
+        assert!(cell > 0, "cell size must be at least 1 byte");
+        let cells = capacity.div_ceil(cell);
+        Self { capacity, cell, used: vec![0; cells.div_ceil(64)] }
+    }
+
+    fn cells(&self) -> usize {
+        self.capacity.div_ceil(self.cell)
+    }
+
+    fn is_used(&self, cell: usize) -> bool {
+        self.used[cell / 64] & (1 << (cell % 64)) != 0
+    }
+
+    fn set(&mut self, cells: std::ops::Range<usize>, used: bool) {
+        for cell in cells {
+            if used {
+                self.used[cell / 64] |= 1 << (cell % 64);
+            } else {
+                self.used[cell / 64] &= !(1 << (cell % 64));
+            }
+        }
+    }
+
+    /// The cells touched by `start..start + size`.
+    fn covering(&self, start: usize, size: usize) -> std::ops::Range<usize> {
+        start / self.cell..(start + size).div_ceil(self.cell)
+    }
+
+    /// Converts a run of cells to a byte range, trimming the short last cell.
+    fn bytes(&self, cells: std::ops::Range<usize>) -> (usize, usize) {
+        let start = cells.start * self.cell;
+        (start, (cells.end * self.cell).min(self.capacity) - start)
+    }
+}
+
+impl Allocator for BitmapAllocator {
+    fn strategy(&self) -> Option<Strategy> {
+        Some(Strategy::Bitmap { cell: self.cell })
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn granularity(&self) -> usize {
+        self.cell
+    }
+
+    /// Scans the bitmap for the first run of free cells that holds `size`
+    /// bytes at an aligned start. Alignment finer than the cell size is free.
+    fn allocate(&mut self, size: usize, alignment: usize) -> Option<usize> {
+        debug_assert!(alignment.is_power_of_two());
+        if size == 0 {
+            let first = (0..self.cells()).find(|&cell| !self.is_used(cell));
+            return Some(first.map_or(0, |cell| cell * self.cell));
+        }
+
+        let needed = size.div_ceil(self.cell);
+        let mut cell = 0;
+        while cell + needed <= self.cells() && cell * self.cell + size <= self.capacity {
+            if !(cell * self.cell).is_multiple_of(alignment) {
+                cell += 1;
+                continue;
+            }
+            match (cell..cell + needed).rev().find(|&c| self.is_used(c)) {
+                // Any start up to the used cell would overlap it as well
+                Some(used) => cell = used + 1,
+                None => {
+                    self.set(cell..cell + needed, true);
+                    return Some(cell * self.cell);
+                }
+            }
+        }
+        None
+    }
+
+    /// Succeeds only if every cell the range touches is free.
+    fn reserve(&mut self, start: usize, size: usize) -> bool {
+        if size == 0 {
+            return true;
+        }
+        if start + size > self.capacity {
+            return false;
+        }
+        let cells = self.covering(start, size);
+        if cells.clone().any(|cell| self.is_used(cell)) {
+            return false;
+        }
+        self.set(cells, true);
+        true
+    }
+
+    fn free(&mut self, start: usize, size: usize) {
+        if size > 0 {
+            self.set(self.covering(start, size), false);
+        }
+    }
+
+    fn rebuild(&mut self, used: Vec<(usize, usize)>) {
+        self.used.iter_mut().for_each(|word| *word = 0);
+        for (start, size) in used.into_iter().filter(|&(_, size)| size > 0) {
+            self.set(self.covering(start, size), true);
+        }
+    }
+
+    fn free_bytes(&self) -> usize {
+        self.regions().iter().map(|&(_, size)| size).sum()
+    }
+
+    fn largest(&self) -> usize {
+        self.regions().iter().map(|&(_, size)| size).max().unwrap_or(0)
+    }
+
+    fn regions(&self) -> Vec<(usize, usize)> {
+        let mut regions = Vec::new();
+        let mut run_start = None;
+        for cell in 0..=self.cells() {
+            let free = cell < self.cells() && !self.is_used(cell);
+            match (free, run_start) {
+                (true, None) => run_start = Some(cell),
+                (false, Some(start)) => {
+                    regions.push(self.bytes(start..cell));
+                    run_start = None;
+                }
+                _ => {}
+            }
+        }
+        regions
+    }
+
+    fn box_clone(&self) -> Box<dyn Allocator> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,149 @@
+use std::fs::File;
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+/// The byte storage behind a `MemoryManager`.
+///
+/// # Variants
+///
+/// - `Heap`: An ordinary in-memory buffer, lost when the manager is dropped.
+/// - `Mapped`: A shared memory mapping of a file, so every write lands in the
+///   file and the contents survive across runs.
+/// - `Static`: A caller-provided buffer that outlives the manager, e.g. a
+///   `static` pool.
+pub(crate) enum Buffer {
+    Heap(Vec<u8>),
+    Mapped(MappedFile),
+    Static(&'static mut [u8]),
+}
+
+impl Buffer {
+    /// Creates a zeroed heap buffer of `size` bytes.
+    pub(crate) fn heap(size: usize) -> Self {
+        Buffer::Heap(vec![0; size])
+    }
+}
+
+impl Deref for Buffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Buffer::Heap(bytes) => bytes,
+            Buffer::Mapped(map) => map.as_slice(),
+            Buffer::Static(bytes) => bytes,
+        }
+    }
+}
+
+impl DerefMut for Buffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            Buffer::Heap(bytes) => bytes,
+            Buffer::Mapped(map) => map.as_mut_slice(),
+            Buffer::Static(bytes) => bytes,
+        }
+    }
+}
+
+/// A file mapped into the address space with `MAP_SHARED`.
+///
+/// The file is kept open for the lifetime of the mapping. On drop the
+/// mapping is flushed with `msync` and unmapped.
+pub(crate) struct MappedFile {
+    ptr: *mut u8,
+    len: usize,
+    _file: File,
+}
+
+// SAFETY: The mapping is owned exclusively by this value and is only reached
+// through `&self`/`&mut self`, exactly like a `Vec<u8>`.
+unsafe impl Send for MappedFile {}
+unsafe impl Sync for MappedFile {}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::os::raw::{c_int, c_void};
+
+    pub const PROT_READ: c_int = 1;
+    pub const PROT_WRITE: c_int = 2;
+    pub const MAP_SHARED: c_int = 1;
+    pub const MS_SYNC: c_int = 4;
+
+    extern "C" {
+        pub fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: c_int,
+            flags: c_int,
+            fd: c_int,
+            offset: i64,
+        ) -> *mut c_void;
+        pub fn munmap(addr: *mut c_void, len: usize) -> c_int;
+        pub fn msync(addr: *mut c_void, len: usize, flags: c_int) -> c_int;
+    }
+}
+
+impl MappedFile {
+    /// Maps the first `len` bytes of `file` read/write.
+    ///
+    /// The file must already be at least `len` bytes long.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn map(file: File, len: usize) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        if len == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "cannot map an empty region"));
+        }
+
+        // SAFETY: We request a fresh shared mapping of an open file descriptor;
+        // the kernel picks the address and the result is checked below.
+        let ptr = unsafe {
+            sys::mmap(
+                std::ptr::null_mut(),
+                len,
+                sys::PROT_READ | sys::PROT_WRITE,
+                sys::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+
+        // mmap reports failure with MAP_FAILED, i.e. (void *)-1
+        if ptr as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { ptr: ptr as *mut u8, len, _file: file })
+    }
+
+    /// Memory mapping is only wired up for Linux targets.
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn map(_file: File, _len: usize) -> io::Result<Self> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "memory-mapped buffers require Linux"))
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        // SAFETY: `ptr` points to `len` mapped bytes that live as long as `self`.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        // SAFETY: As above, and `&mut self` guarantees exclusive access.
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+}
+
+impl Drop for MappedFile {
+    #[cfg(target_os = "linux")]
+    fn drop(&mut self) {
+        // SAFETY: The region was mapped in `map` and is unmapped exactly once here.
+        unsafe {
+            sys::msync(self.ptr as *mut _, self.len, sys::MS_SYNC);
+            sys::munmap(self.ptr as *mut _, self.len);
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn drop(&mut self) {}
+}
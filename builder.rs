@@ -0,0 +1,280 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::allocator::Allocator;
+use crate::bitmap::BitmapAllocator;
+use crate::erase::ErasePolicy;
+use crate::free_list::FreeList;
+use crate::journal::open_journal;
+use crate::memory_manager::MemoryManager;
+use crate::throttle::Backpressure;
+
+/// How a built manager places blocks in memory.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Strategy {
+    /// First fit over a list of free regions. See `FreeList`.
+    #[default]
+    FreeList,
+    /// First fit over a bitmap with one bit per cell of this many bytes.
+    /// See `BitmapAllocator`.
+    Bitmap { cell: usize },
+}
+
+/// Why `MemoryManagerBuilder::build` refused a configuration.
+#[derive(Debug)]
+pub enum BuildError {
+    /// The capacity is 0, so nothing could ever be stored.
+    ZeroCapacity,
+    /// The bitmap cell size is 0 or larger than the whole memory block.
+    BadCellSize { cell: usize, capacity: usize },
+    /// Backpressure is configured with a soft limit it can never reach.
+    SoftLimitTooLarge { limit: usize, capacity: usize },
+    /// Dedup was requested with encryption, which gives every block a fresh
+    /// nonce, so identical data never produces identical bytes to share.
+    DedupWithEncryption,
+    /// Canaries were requested with a backing file, whose index does not
+    /// record the guard bytes around blocks.
+    CanariesWithFile,
+    /// The backing file or the journal could not be opened.
+    Io(io::Error),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::ZeroCapacity => write!(f, "capacity must be at least 1 byte"),
+            BuildError::BadCellSize { cell, capacity } => {
+                write!(f, "bitmap cell size {} must be between 1 and the capacity ({})", cell, capacity)
+            }
+            BuildError::SoftLimitTooLarge { limit, capacity } => {
+                write!(f, "soft limit {} is above the capacity ({}) and would never apply", limit, capacity)
+            }
+            BuildError::DedupWithEncryption => write!(f, "dedup cannot find duplicates in encrypted data"),
+            BuildError::CanariesWithFile => write!(f, "canaries are not supported for file-backed memory"),
+            BuildError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Collects `MemoryManager` options and builds a manager from them.
+///
+/// Every option starts at the value `MemoryManager::new()` uses, so only the
+/// ones that differ need setting:
+///
+/// ```ignore
+/// let manager = MemoryManager::builder()
+///     .capacity(4096)
+///     .strategy(Strategy::Bitmap { cell: 16 })
+///     .compression(true)
+///     .build()?;
+/// ```
+#[derive(Clone)]
+pub struct MemoryManagerBuilder {
+    capacity: usize,                           // Size of the memory block in bytes
+    strategy: Strategy,                        // How blocks are placed
+    erase_policy: ErasePolicy,                 // How deleted blocks are erased
+    soft_limit: Option<(usize, Backpressure)>, // Soft limit and what inserts do above it
+    compression: bool,                         // Whether data is compressed before storing
+    encryption_key: Option<[u8; 32]>,          // Key for encryption at rest, if any
+    inline_threshold: usize,                   // Values up to this size are kept inline (0 = off)
+    dedup: bool,                               // Whether identical data shares one region
+    history_depth: Option<usize>,              // Undo depth, if not the default
+    version_depth: usize,                      // Previous versions kept per block (0 = off)
+    canaries: bool,                            // Whether blocks are surrounded by guard bytes
+    audit: bool,                               // Whether changes are recorded per ID
+    file: Option<PathBuf>,                     // Memory-mapped backing file, if any
+    journal: Option<PathBuf>,                  // Write-ahead journal, if any
+}
+
+impl Default for MemoryManagerBuilder {
+    fn default() -> Self {
+        Self {
+            capacity: 65535,
+            strategy: Strategy::FreeList,
+            erase_policy: ErasePolicy::Zero,
+            soft_limit: None,
+            compression: false,
+            encryption_key: None,
+            inline_threshold: 0,
+            dedup: false,
+            history_depth: None,
+            version_depth: 0,
+            canaries: false,
+            audit: false,
+            file: None,
+            journal: None,
+        }
+    }
+}
+
+impl MemoryManager {
+    /// Starts configuring a `MemoryManager`. See `MemoryManagerBuilder`.
+    pub fn builder() -> MemoryManagerBuilder {
+        MemoryManagerBuilder::default()
+    }
+}
+
+impl MemoryManagerBuilder {
+    /// Sets the size of the memory block in bytes. Defaults to 65535.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets how blocks are placed in memory. Defaults to `Strategy::FreeList`.
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Sets how deleted blocks are erased. Defaults to `ErasePolicy::Zero`.
+    pub fn erase_policy(mut self, policy: ErasePolicy) -> Self {
+        self.erase_policy = policy;
+        self
+    }
+
+    /// Applies `policy` once more than `limit` bytes are in use. See `set_soft_limit`.
+    pub fn soft_limit(mut self, limit: usize, policy: Backpressure) -> Self {
+        self.soft_limit = Some((limit, policy));
+        self
+    }
+
+    /// Compresses data before storing it. See `with_compression`.
+    pub fn compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// Encrypts block contents at rest with `key`. See `with_encryption`.
+    pub fn encryption(mut self, key: [u8; 32]) -> Self {
+        self.encryption_key = Some(key);
+        self
+    }
+
+    /// Keeps values up to `threshold` stored bytes inline. See `with_inline_threshold`.
+    pub fn inline_threshold(mut self, threshold: usize) -> Self {
+        self.inline_threshold = threshold;
+        self
+    }
+
+    /// Stores identical data only once. See `set_dedup`.
+    pub fn dedup(mut self, enabled: bool) -> Self {
+        self.dedup = enabled;
+        self
+    }
+
+    /// Sets how many operations `undo()` can revert. See `set_history_depth`.
+    pub fn history_depth(mut self, depth: usize) -> Self {
+        self.history_depth = Some(depth);
+        self
+    }
+
+    /// Keeps the previous `depth` versions of every block. See `set_version_depth`.
+    pub fn versions(mut self, depth: usize) -> Self {
+        self.version_depth = depth;
+        self
+    }
+
+    /// Surrounds every block with guard bytes. See `set_canaries`.
+    pub fn canaries(mut self, enabled: bool) -> Self {
+        self.canaries = enabled;
+        self
+    }
+
+    /// Keeps a per-ID audit trail of changes and reads. See `set_audit`.
+    pub fn audit(mut self, enabled: bool) -> Self {
+        self.audit = enabled;
+        self
+    }
+
+    /// Backs memory with the file at `path`, restoring any previous state.
+    /// See `open_mmap`.
+    pub fn file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Journals every change to `path`. See `with_journal`.
+    pub fn journal<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.journal = Some(path.into());
+        self
+    }
+
+    /// Checks the options fit together and builds the manager.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(manager)` configured as requested.
+    /// - `Err(e)` if the combination is invalid (see `BuildError`), or the
+    ///   backing file or journal cannot be opened.
+    pub fn build(self) -> Result<MemoryManager, BuildError> {
+        let capacity = self.capacity;
+        if capacity == 0 {
+            return Err(BuildError::ZeroCapacity);
+        }
+        if let Strategy::Bitmap { cell } = self.strategy {
+            if cell == 0 || cell > capacity {
+                return Err(BuildError::BadCellSize { cell, capacity });
+            }
+        }
+        if let Some((limit, policy)) = self.soft_limit {
+            if limit > capacity && policy != Backpressure::Off {
+                return Err(BuildError::SoftLimitTooLarge { limit, capacity });
+            }
+        }
+        if self.dedup && self.encryption_key.is_some() {
+            return Err(BuildError::DedupWithEncryption);
+        }
+        if self.canaries && self.file.is_some() {
+            return Err(BuildError::CanariesWithFile);
+        }
+
+        let allocator: Box<dyn Allocator> = match self.strategy {
+            Strategy::FreeList => Box::new(FreeList::new(capacity)),
+            Strategy::Bitmap { cell } => Box::new(BitmapAllocator::new(capacity, cell)),
+        };
+        let mut manager = match &self.file {
+            Some(path) => {
+                let mut manager = MemoryManager::open_mmap(path, capacity).map_err(BuildError::Io)?;
+                // Blocks restored from the file are re-registered with the chosen allocator
+                let used = manager.allocations.values().filter(|block| block.inline.is_none());
+                let used = used.map(|block| (block.start, block.size)).collect();
+                manager.allocator = allocator;
+                manager.allocator.rebuild(used);
+                manager
+            }
+            None => MemoryManager::with_allocator(allocator),
+        };
+
+        manager.erase_policy = self.erase_policy;
+        if let Some((limit, policy)) = self.soft_limit {
+            manager.set_soft_limit(limit, policy);
+        }
+        manager.compression = self.compression;
+        manager.encryption_key = self.encryption_key;
+        manager.inline_threshold = self.inline_threshold;
+        manager.dedup = self.dedup;
+        if let Some(depth) = self.history_depth {
+            manager.set_history_depth(depth);
+        }
+        manager.version_depth = self.version_depth;
+        manager.canaries = self.canaries;
+        manager.set_audit(self.audit);
+        if let Some(path) = &self.journal {
+            manager.journal = Some(open_journal(path).map_err(BuildError::Io)?);
+        }
+        Ok(manager)
+    }
+}
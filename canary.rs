@@ -0,0 +1,151 @@
+use std::fmt;
+
+use log::warn;
+
+use crate::memory_manager::{Block, BlockId, MemoryManager};
+use crate::memory_map::SegmentKind;
+
+/// Number of guard bytes on each side of a block when canaries are on.
+pub const CANARY_LEN: usize = 8;
+
+/// The value every guard byte holds, as in the "no man's land" of common
+/// debug heaps.
+pub const CANARY: u8 = 0xFD;
+
+/// Which guard of a block was overwritten.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GuardSide {
+    Before, // The guard just below the block's first byte
+    After,  // The guard just past the block's last byte
+}
+
+/// A guard found overwritten by `check_integrity()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SmashedGuard {
+    pub owner: SegmentKind, // The block the guard belongs to
+    pub side: GuardSide,    // Which of its guards was hit
+    pub address: usize,     // Index of the first overwritten guard byte
+}
+
+impl MemoryManager {
+    /// Creates a new `MemoryManager` that surrounds every block with guard
+    /// bytes. See `set_canaries`.
+    pub fn with_canaries() -> Self {
+        Self { canaries: true, ..Self::new() }
+    }
+
+    /// Turns guard bytes around blocks on or off.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the setting was applied.
+    /// - `None` if blocks already occupy memory, since they were laid out
+    ///   for the current setting, or the manager is file-backed, since the
+    ///   index file does not record the layout.
+    ///
+    /// # Behavior
+    ///
+    /// With canaries on, every block that occupies memory is placed with
+    /// `CANARY_LEN` bytes of `CANARY` on each side, so a write that runs
+    /// past either end of a block lands in a guard instead of a neighbour.
+    /// Reads and updates of a block whose guards were overwritten fail, and
+    /// deletes log a warning but go ahead. `check_integrity()` sweeps every
+    /// block at once.
+    ///
+    /// Guard bytes belong to no block and to no free region, so they count
+    /// as neither used nor free in `stats()`. Aligned inserts are limited
+    /// to an alignment of `CANARY_LEN`, and blocks cannot be split or merged,
+    /// since either would need guards inside a block.
+    pub fn set_canaries(&mut self, enabled: bool) -> Option<()> {
+        let occupied = self.allocations.values().chain(self.content.values().map(|entry| &entry.block));
+        if occupied.filter(|block| block.inline.is_none()).any(|block| block.size > 0) || self.index_path.is_some() {
+            return None;
+        }
+        self.canaries = enabled;
+        Some(())
+    }
+
+    /// Returns `true` if blocks are surrounded by guard bytes.
+    pub fn canaries(&self) -> bool {
+        self.canaries
+    }
+
+    /// Checks the guards of every block in memory.
+    ///
+    /// # Returns
+    ///
+    /// Every overwritten guard, ordered by the address of its block. Blocks
+    /// sharing a region through dedup each report the region's guards.
+    /// Empty with canaries off.
+    pub fn check_integrity(&self) -> Vec<SmashedGuard> {
+        let mut smashed = Vec::new();
+        for segment in self.memory_map() {
+            if segment.kind == SegmentKind::Free {
+                continue;
+            }
+            for (side, address) in self.smashed_guards(segment.start, segment.size) {
+                smashed.push(SmashedGuard { owner: segment.kind, side, address });
+            }
+        }
+        smashed
+    }
+
+    /// Returns `true` unless a guard of block `id` was overwritten, in which
+    /// case the damage is logged as a warning.
+    pub(crate) fn guards_intact(&self, id: BlockId) -> bool {
+        self.allocations.get(&id).is_none_or(|block| self.block_guards_intact(block, &format_args!("block {}", id)))
+    }
+
+    /// Returns `true` unless a guard of `block` was overwritten, in which
+    /// case the damage is logged as a warning naming the block `name`.
+    pub(crate) fn block_guards_intact(&self, block: &Block, name: &dyn fmt::Display) -> bool {
+        if block.inline.is_some() {
+            return true;
+        }
+        let smashed = self.smashed_guards(block.start, block.size);
+        for (side, address) in &smashed {
+            let side = match side {
+                GuardSide::Before => "before",
+                GuardSide::After => "after",
+            };
+            warn!("guard {} {} was overwritten at address {}", side, name, address);
+        }
+        smashed.is_empty()
+    }
+
+    /// Returns the number of guard bytes on each side of a block of `size` bytes.
+    pub(crate) fn guard_len(&self, size: usize) -> usize {
+        if self.canaries && size > 0 {
+            CANARY_LEN
+        } else {
+            0
+        }
+    }
+
+    /// Returns the `(start, size)` of everything a block at `start` reserves,
+    /// its guards included.
+    pub(crate) fn extent(&self, start: usize, size: usize) -> (usize, usize) {
+        let guard = self.guard_len(size);
+        (start - guard, size + 2 * guard)
+    }
+
+    /// Fills the guards around the block at `start..start + size`.
+    pub(crate) fn write_guards(&mut self, start: usize, size: usize) {
+        let guard = self.guard_len(size);
+        self.memory[start - guard..start].fill(CANARY);
+        self.memory[start + size..start + size + guard].fill(CANARY);
+    }
+
+    /// Returns the side and first overwritten address of each damaged guard
+    /// around the block at `start..start + size`.
+    fn smashed_guards(&self, start: usize, size: usize) -> Vec<(GuardSide, usize)> {
+        let guard = self.guard_len(size);
+        [(GuardSide::Before, start - guard), (GuardSide::After, start + size)]
+            .into_iter()
+            .filter_map(|(side, from)| {
+                let offset = self.memory[from..from + guard].iter().position(|&byte| byte != CANARY)?;
+                Some((side, from + offset))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,90 @@
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// Computes the CRC32 (IEEE 802.3) checksum of `data`.
+///
+/// This is the same polynomial used by zip and PNG, computed bit by bit so
+/// no lookup table is needed.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            // Shift right and apply the reversed polynomial when the low bit was set
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Computes the 64-bit FNV-1a hash of `data`.
+pub fn fnv1a(data: &[u8]) -> u64 {
+    fnv1a_extend(0xcbf2_9ce4_8422_2325, data)
+}
+
+/// Widens a block ID to 64 bits, the same way at either ID width.
+pub(crate) fn widen_id(id: BlockId) -> u64 {
+    let mut bytes = [0; 8];
+    bytes[..std::mem::size_of::<BlockId>()].copy_from_slice(&id.to_le_bytes());
+    u64::from_le_bytes(bytes)
+}
+
+/// Continues an FNV-1a hash from `hash` over more `data`.
+fn fnv1a_extend(mut hash: u64, data: &[u8]) -> u64 {
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+impl MemoryManager {
+    /// Checks every allocation against its stored checksum.
+    ///
+    /// # Returns
+    ///
+    /// The IDs of all blocks whose bytes no longer match their checksum,
+    /// in ascending order. An empty vector means memory is intact.
+    pub fn verify_all(&self) -> Vec<BlockId> {
+        self.allocations
+            .iter()
+            .filter(|(_, block)| crc32(self.stored(block)) != block.checksum)
+            .map(|(&id, _)| id)
+            .collect()
+    }
+
+    /// Flips a single bit of raw memory without updating any checksum.
+    ///
+    /// # Parameters
+    ///
+    /// - `address`: The memory index of the byte to corrupt.
+    /// - `bit`: Which bit of that byte to flip (0-7).
+    ///
+    /// # Behavior
+    ///
+    /// Simulates a hardware bit-flip so integrity checks can be exercised.
+    /// Addresses outside memory are ignored.
+    pub fn flip_bit(&mut self, address: usize, bit: u8) {
+        if let Some(byte) = self.memory.get_mut(address) {
+            *byte ^= 1 << (bit % 8);
+        }
+    }
+
+    /// Hashes the complete observable state of the manager.
+    ///
+    /// # Returns
+    ///
+    /// A 64-bit FNV-1a hash over every allocation's ID, start address, size,
+    /// and stored bytes, visited in ID order. Two managers that went through
+    /// the same operations with the same allocator produce the same hash.
+    pub fn state_hash(&self) -> u64 {
+        let mut hash = fnv1a(&[]);
+        for (id, block) in &self.allocations {
+            hash = fnv1a_extend(hash, &widen_id(*id).to_le_bytes());
+            hash = fnv1a_extend(hash, &(block.start as u64).to_le_bytes());
+            hash = fnv1a_extend(hash, &(block.size as u64).to_le_bytes());
+            hash = fnv1a_extend(hash, self.stored(block));
+        }
+        hash
+    }
+}
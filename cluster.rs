@@ -0,0 +1,226 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+
+use crate::checksum::widen_id;
+use crate::erase::splitmix64;
+use crate::error::MemoryError;
+use crate::handle::Handle;
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::stats::Stats;
+
+/// Identifies a node of a `Cluster`, assigned by `add_node`.
+pub type NodeId = u32;
+
+/// Why a cluster refused to add or remove a node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ClusterError {
+    /// No node has this ID.
+    NoSuchNode { node: NodeId },
+    /// The node still holds blocks and no other node is left to take them.
+    LastNode { node: NodeId },
+    /// A block could not be moved to the node it now belongs to.
+    Rebalance { id: BlockId, node: NodeId, reason: MemoryError },
+}
+
+impl fmt::Display for ClusterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClusterError::NoSuchNode { node } => write!(f, "no node {}", node),
+            ClusterError::LastNode { node } => write!(f, "node {} is the last node and still holds blocks", node),
+            ClusterError::Rebalance { id, node, reason } => write!(f, "cannot move block {} to node {}: {}", id, node, reason),
+        }
+    }
+}
+
+impl Error for ClusterError {}
+
+/// Several `MemoryManager`s ("nodes") behind one ID space, simulating
+/// distributed memory.
+///
+/// Each node is placed at `virtual_nodes` points on a hash ring, and a
+/// block lives on the node owning the first point at or after the hash of
+/// its ID. Adding or removing a node only changes the owner of the IDs
+/// next to its points, so `add_node` and `remove_node` move about
+/// `1 / nodes` of the blocks rather than all of them. Blocks move as data,
+/// like `export` and `import`: tags, versions, and seals stay behind.
+pub struct Cluster {
+    nodes: BTreeMap<NodeId, MemoryManager>, // Node ID -> its manager
+    ring: BTreeMap<u64, NodeId>,            // Ring point -> node owning it
+    virtual_nodes: usize,                   // Ring points per node
+    next_node: NodeId,                      // ID of the next node added
+}
+
+impl Cluster {
+    /// Creates a cluster without nodes, placing each node at
+    /// `virtual_nodes` points on the ring. More points spread blocks more
+    /// evenly; 64 to 256 is typical.
+    ///
+    /// # Panics
+    ///
+    /// If `virtual_nodes` is 0.
+    pub fn new(virtual_nodes: usize) -> Self {
+        assert!(virtual_nodes > 0, "every node needs at least one ring point");
+        Self { nodes: BTreeMap::new(), ring: BTreeMap::new(), virtual_nodes, next_node: 0 }
+    }
+
+    /// Adds `manager` as a new node and moves to it every block it now owns.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(node)` with the new node's ID.
+    /// - `Err(ClusterError::Rebalance)` if a block does not fit on the new
+    ///   node. The node is not added then, and no block has moved.
+    ///
+    /// # Behavior
+    ///
+    /// Blocks already in `manager` stay where they are, so it should start
+    /// out empty; otherwise they may be unreachable through the cluster.
+    pub fn add_node(&mut self, manager: MemoryManager) -> Result<NodeId, ClusterError> {
+        let node = self.next_node;
+        self.next_node += 1;
+        self.nodes.insert(node, manager);
+        for point in points(node, self.virtual_nodes) {
+            self.ring.insert(point, node);
+        }
+
+        if let Err(e) = self.rebalance(self.nodes.keys().copied().filter(|&other| other != node).collect()) {
+            self.ring.retain(|_, owner| *owner != node);
+            self.nodes.remove(&node);
+            return Err(e);
+        }
+        Ok(node)
+    }
+
+    /// Removes node `node`, moving its blocks to the nodes that now own them.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(manager)` with the removed node's manager, now without the
+    ///   blocks that moved.
+    /// - `Err(ClusterError::NoSuchNode)` if the node does not exist.
+    /// - `Err(ClusterError::LastNode)` if it is the only node and holds blocks.
+    /// - `Err(ClusterError::Rebalance)` if a block does not fit on its new
+    ///   node. The node stays then, and no block has moved.
+    pub fn remove_node(&mut self, node: NodeId) -> Result<MemoryManager, ClusterError> {
+        if !self.nodes.contains_key(&node) {
+            return Err(ClusterError::NoSuchNode { node });
+        }
+        if self.nodes.len() == 1 && !block_ids(&self.nodes[&node]).is_empty() {
+            return Err(ClusterError::LastNode { node });
+        }
+
+        self.ring.retain(|_, owner| *owner != node);
+        if let Err(e) = self.rebalance(vec![node]) {
+            for point in points(node, self.virtual_nodes) {
+                self.ring.insert(point, node);
+            }
+            return Err(e);
+        }
+        Ok(self.nodes.remove(&node).expect("checked above"))
+    }
+
+    /// Returns the node that owns block `id`, or `None` without nodes.
+    pub fn node_of(&self, id: BlockId) -> Option<NodeId> {
+        let hash = hash_id(id);
+        self.ring.range(hash..).next().or_else(|| self.ring.iter().next()).map(|(_, node)| *node)
+    }
+
+    /// Returns the manager of node `node`.
+    pub fn node(&self, node: NodeId) -> Option<&MemoryManager> {
+        self.nodes.get(&node)
+    }
+
+    /// Returns the IDs of every node, in ascending order.
+    pub fn nodes(&self) -> Vec<NodeId> {
+        self.nodes.keys().copied().collect()
+    }
+
+    /// Returns the usage statistics of every node.
+    ///
+    /// Compare `allocations` across nodes to see how evenly IDs are spread.
+    pub fn node_stats(&self) -> BTreeMap<NodeId, Stats> {
+        self.nodes.iter().map(|(&node, manager)| (node, manager.stats())).collect()
+    }
+
+    /// Stores `data` under `id` on the node that owns it. See [`MemoryManager::insert`].
+    pub fn insert(&mut self, id: BlockId, data: Vec<u8>) -> Option<Handle> {
+        self.owner_mut(id)?.insert(id, data)
+    }
+
+    /// Reads block `id` from the node that owns it. See [`MemoryManager::read`].
+    pub fn read(&self, id: BlockId) -> Option<Vec<u8>> {
+        self.nodes.get(&self.node_of(id)?)?.read(id)
+    }
+
+    /// Updates block `id` on the node that owns it. See [`MemoryManager::update`].
+    pub fn update(&mut self, id: BlockId, data: Vec<u8>) -> Option<()> {
+        self.owner_mut(id)?.update(id, data)
+    }
+
+    /// Deletes block `id` from the node that owns it. See [`MemoryManager::delete`].
+    pub fn delete(&mut self, id: BlockId) -> Option<()> {
+        self.owner_mut(id)?.delete(id)
+    }
+
+    fn owner_mut(&mut self, id: BlockId) -> Option<&mut MemoryManager> {
+        let node = self.node_of(id)?;
+        self.nodes.get_mut(&node)
+    }
+
+    /// Moves every block on `sources` that belongs to another node there.
+    ///
+    /// Blocks are copied first and only deleted from their old node once
+    /// every copy succeeded, so a failure can be undone by deleting the
+    /// copies.
+    fn rebalance(&mut self, sources: Vec<NodeId>) -> Result<(), ClusterError> {
+        let mut moves = Vec::new();
+        for source in sources {
+            for id in block_ids(&self.nodes[&source]) {
+                let target = self.node_of(id).expect("rebalancing keeps at least one node");
+                if target != source {
+                    moves.push((id, source, target));
+                }
+            }
+        }
+
+        for (done, &(id, source, target)) in moves.iter().enumerate() {
+            let copied = match self.nodes[&source].peek(id) {
+                Some(data) => {
+                    let size = data.len();
+                    let target_manager = self.nodes.get_mut(&target).expect("target is on the ring");
+                    target_manager.insert(id, data).map(|_| ()).ok_or_else(|| target_manager.insert_failure(id, size))
+                }
+                None => Err(self.nodes[&source].read_failure(id)),
+            };
+            if let Err(reason) = copied {
+                for &(id, _, target) in &moves[..done] {
+                    self.nodes.get_mut(&target).expect("target is on the ring").delete(id);
+                }
+                return Err(ClusterError::Rebalance { id, node: target, reason });
+            }
+        }
+        for (id, source, _) in moves {
+            self.nodes.get_mut(&source).expect("source is a node").delete(id);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the IDs of every block in `manager`, including swapped ones.
+fn block_ids(manager: &MemoryManager) -> BTreeSet<BlockId> {
+    manager.allocations.keys().copied().chain(manager.swapped_ids()).collect()
+}
+
+/// Returns the ring points of node `node`.
+fn points(node: NodeId, virtual_nodes: usize) -> impl Iterator<Item = u64> {
+    let mut state = (node as u64) << 32;
+    (0..virtual_nodes).map(move |_| splitmix64(&mut state))
+}
+
+/// Returns the ring position of block `id`.
+fn hash_id(id: BlockId) -> u64 {
+    // Offset so IDs and node numbers never share a generator state
+    let mut state = widen_id(id) ^ 0x5bd1_e995_0000_0000;
+    splitmix64(&mut state)
+}
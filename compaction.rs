@@ -0,0 +1,158 @@
+use std::collections::BTreeMap;
+
+use crate::erase::erase;
+use crate::events::Event;
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::operation::Operation;
+
+/// What a call to `compact()` achieved.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CompactReport {
+    pub moved: usize,        // Number of blocks moved to a lower address
+    pub bytes_moved: usize,  // Total size of the moved regions
+    pub largest_free: usize, // Largest free region afterwards
+    pub stranded: usize,     // Free bytes left in gaps below pinned blocks
+}
+
+impl MemoryManager {
+    /// Pins a block so `compact()` leaves it at its current address.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the block is now pinned.
+    /// - `None` if no block has this ID.
+    ///
+    /// # Behavior
+    ///
+    /// Use this for blocks whose address must not change, e.g. to simulate
+    /// DMA buffers. Deleting the block unpins it; renaming keeps the pin.
+    /// Pins are journaled, since they decide where compaction leaves blocks.
+    pub fn pin(&mut self, id: BlockId) -> Option<()> {
+        if !self.exists(id) || !self.journal_operation(|| Operation::Pin { id }) {
+            return None;
+        }
+        if self.is_swapped(id) {
+            self.swap_in(id)?;
+        }
+        self.pinned.insert(id);
+        self.trace_change();
+        Some(())
+    }
+
+    /// Unpins a block. Returns `None` if it was not pinned.
+    pub fn unpin(&mut self, id: BlockId) -> Option<()> {
+        if !self.pinned.contains(&id) || !self.journal_operation(|| Operation::Unpin { id }) {
+            return None;
+        }
+        self.pinned.remove(&id);
+        self.trace_change();
+        Some(())
+    }
+
+    /// Returns `true` if the block is pinned.
+    pub fn is_pinned(&self, id: BlockId) -> bool {
+        self.pinned.contains(&id)
+    }
+
+    /// Moves blocks towards the start of memory so free space is merged.
+    ///
+    /// # Returns
+    ///
+    /// A `CompactReport`. `stranded` is the free space compaction could not
+    /// reclaim because pinned blocks are in the way.
+    ///
+    /// # Behavior
+    ///
+    /// Blocks keep their relative order. Each unpinned block slides down to
+    /// the end of the block before it; pinned blocks stay put, so the gaps
+    /// just below them remain. A region shared by deduplicated blocks moves
+    /// as one, and stays put if any of them is pinned. Content-addressed
+    /// blocks are moved too, and inline blocks take no memory and are left
+    /// alone. Bytes are moved as stored, so checksums, compression, and
+    /// encryption are unaffected. Freed space is erased according to the
+    /// erase policy. Emits `Event::Compacted`. Compaction is journaled and
+    /// traced as `COMPACT`; if the journal cannot be written, nothing moves.
+    pub fn compact(&mut self) -> CompactReport {
+        if !self.journal_operation(|| Operation::Compact) {
+            return CompactReport { largest_free: self.allocator.largest(), ..CompactReport::default() };
+        }
+
+        // start -> (size, keys) for every region in memory; deduplicated blocks share one
+        let mut regions: BTreeMap<usize, (usize, Vec<Slot>)> = BTreeMap::new();
+        let blocks = self
+            .allocations
+            .iter()
+            .filter(|(_, block)| block.inline.is_none())
+            .map(|(&id, block)| (block.start, block.size, Slot::Id(id)))
+            .chain(self.content.iter().map(|(&hash, entry)| (entry.block.start, entry.block.size, Slot::Content(hash))))
+            .filter(|&(_, size, _)| size > 0);
+        for (start, size, slot) in blocks {
+            regions.entry(start).or_insert((size, Vec::new())).1.push(slot);
+        }
+
+        let mut report = CompactReport::default();
+        // Regions start on the allocator's unit boundaries, so they never share a unit
+        let unit = self.allocator.granularity();
+        let mut cursor = 0;
+        for (start, (size, slots)) in regions {
+            let pinned = slots.iter().any(|slot| matches!(slot, Slot::Id(id) if self.pinned.contains(id)));
+            // Guard bytes, if any, move with the block
+            let (from, extent) = self.extent(start, size);
+            if !pinned && from > cursor {
+                self.memory.copy_within(from..from + extent, cursor);
+                let moved_to = cursor + (start - from);
+                for &slot in &slots {
+                    let block = match slot {
+                        Slot::Id(id) => self.allocations.get_mut(&id),
+                        Slot::Content(hash) => self.content.get_mut(&hash).map(|entry| &mut entry.block),
+                    };
+                    if let Some(block) = block {
+                        block.start = moved_to;
+                    }
+                }
+                report.moved += slots.len();
+                report.bytes_moved += extent;
+                cursor = (cursor + extent).next_multiple_of(unit);
+            } else {
+                cursor = (from + extent).next_multiple_of(unit);
+            }
+        }
+
+        let used: Vec<(usize, usize)> = self
+            .allocations
+            .values()
+            .chain(self.content.values().map(|entry| &entry.block))
+            .filter(|block| block.inline.is_none())
+            .map(|block| self.extent(block.start, block.size))
+            .collect();
+        self.allocator.rebuild(used);
+        self.rebuild_refcounts();
+        for (start, size) in self.allocator.regions() {
+            erase(&mut self.memory[start..start + size], self.erase_policy);
+        }
+
+        // Everything free except the region running to the end of memory is stuck below a pin
+        let tail = match self.allocator.regions().last() {
+            Some(&(start, size)) if start + size == self.memory.len() => size,
+            _ => 0,
+        };
+        report.largest_free = self.allocator.largest();
+        report.stranded = self.free_bytes() - tail;
+        self.counters.compacted(report.bytes_moved);
+        if report.moved > 0 {
+            self.sequence += 1;
+            self.save_index();
+        }
+        self.emit(Event::Compacted { moved: report.moved, largest_free: report.largest_free });
+        self.check_watches(None);
+        self.trace_change();
+        report
+    }
+}
+
+/// Identifies a block in either allocation table.
+#[derive(Clone, Copy)]
+enum Slot {
+    Id(BlockId),
+    Content(u64),
+}
@@ -0,0 +1,35 @@
+/// Compresses `data` with run-length encoding.
+///
+/// # Format
+///
+/// The output is a sequence of `(count, byte)` pairs, where `count` is in
+/// 1..=255. A pair with a count of zero decodes to nothing, so the zero
+/// padding left behind by a shrinking update is harmless.
+pub fn rle_compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut iter = data.iter().peekable();
+
+    while let Some(&byte) = iter.next() {
+        // Count how many times this byte repeats, up to the largest count a pair can hold
+        let mut count: u8 = 1;
+        while count < u8::MAX && iter.peek() == Some(&&byte) {
+            iter.next();
+            count += 1;
+        }
+        out.push(count);
+        out.push(byte);
+    }
+
+    out
+}
+
+/// Expands run-length encoded `data` produced by [`rle_compress`].
+///
+/// A trailing unpaired byte is ignored.
+pub fn rle_decompress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for pair in data.chunks_exact(2) {
+        out.extend(std::iter::repeat_n(pair[1], pair[0] as usize));
+    }
+    out
+}
@@ -0,0 +1,118 @@
+use std::borrow::Cow;
+
+use crate::checksum::{crc32, fnv1a};
+use crate::memory_manager::{Block, MemoryManager};
+use crate::operation::Operation;
+
+/// A content-addressed block and the number of times it has been inserted.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct ContentEntry {
+    pub(crate) block: Block,
+    pub(crate) refs: usize,
+}
+
+impl MemoryManager {
+    /// Stores `data` under the hash of its contents.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(hash)`: The 64-bit FNV-1a hash of `data`, which is its key.
+    /// - `None` if there is not enough space, a different payload already
+    ///   has the same hash, or the manager is file-backed, since its index
+    ///   only records ID-keyed blocks.
+    ///
+    /// # Behavior
+    ///
+    /// Identical content is only stored once. Inserting it again returns the
+    /// same hash and bumps a reference count instead of using more memory.
+    /// Content-addressed blocks live alongside, but separate from, ID-keyed ones.
+    /// Inserts are journaled as `INSERT_CONTENT`.
+    pub fn insert_content(&mut self, data: Vec<u8>) -> Option<u64> {
+        if self.index_path.is_some() {
+            return None;
+        }
+        let hash = fnv1a(&data);
+
+        if let Some(entry) = self.content.get(&hash) {
+            // Same hash must mean same bytes, otherwise refuse rather than alias
+            if *self.decode(&entry.block)? != *data {
+                return None;
+            }
+            if !self.journal_operation(|| Operation::InsertContent { data }) {
+                return None;
+            }
+            self.content.get_mut(&hash)?.refs += 1;
+            self.sequence += 1;
+            self.trace_change();
+            return Some(hash);
+        }
+        if !self.journal_operation(|| Operation::InsertContent { data: data.clone() }) {
+            return None;
+        }
+
+        let raw_size = data.len();
+        let (data, compressed, nonce) = self.encode(data);
+        let size = data.len();
+        let start = self.allocate(size)?;
+        self.memory[start..start + size].copy_from_slice(&data);
+
+        let block = Block {
+            start,
+            size,
+            checksum: crc32(&data),
+            raw_size,
+            stored_size: size,
+            compressed,
+            nonce,
+            inline: None,
+        };
+        self.content.insert(hash, ContentEntry { block, refs: 1 });
+        self.sequence += 1;
+        self.trace_change();
+
+        Some(hash)
+    }
+
+    /// Reads content previously stored with `insert_content`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(data)` if content with this hash exists and is intact.
+    /// - `None` otherwise.
+    pub fn read_content(&self, hash: u64) -> Option<Vec<u8>> {
+        let block = &self.content.get(&hash)?.block;
+        if crc32(self.stored(block)) != block.checksum {
+            return None;
+        }
+        self.decode(block).map(Cow::into_owned)
+    }
+
+    /// Drops one reference to content stored with `insert_content`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(remaining)`: How many references are left. At zero the bytes
+    ///   are erased like a deleted block's, and the hash no longer resolves.
+    /// - `None` if no content has this hash.
+    ///
+    /// Deletes are journaled as `DELETE_CONTENT`.
+    pub fn delete_content(&mut self, hash: u64) -> Option<usize> {
+        if !self.content.contains_key(&hash) || !self.journal_operation(|| Operation::DeleteContent { hash }) {
+            return None;
+        }
+        self.trace_change();
+        let entry = self.content.get_mut(&hash)?;
+        entry.refs -= 1;
+        self.sequence += 1;
+        if entry.refs > 0 {
+            return Some(entry.refs);
+        }
+
+        // A smashed guard is reported, but the content is still deleted
+        let block = self.content.remove(&hash)?.block;
+        self.block_guards_intact(&block, &format_args!("content {:016x}", hash));
+        self.release_region(block.start, block.size, self.erase_policy);
+        Some(0)
+    }
+}
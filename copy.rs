@@ -0,0 +1,116 @@
+use crate::history::HistoryEntry;
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::operation::Operation;
+use crate::subscriptions::Change;
+
+impl MemoryManager {
+    /// Copies the block stored under `src` into a new block under `dst`.
+    ///
+    /// # Parameters
+    ///
+    /// - `src`: The ID of the block to copy.
+    /// - `dst`: The ID for the new block. Must not already exist.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the copy was made.
+    /// - `None` if `src` does not exist, `dst` already exists, or there is
+    ///   not enough space.
+    ///
+    /// # Behavior
+    ///
+    /// The stored bytes are copied directly within memory, so compressed or
+    /// encrypted blocks are duplicated without being decoded. Tags are copied too.
+    /// With dedup on, the new block shares the source's region instead.
+    pub fn copy(&mut self, src: BlockId, dst: BlockId) -> Option<()> {
+        self.copy_block(src, dst, self.dedup)
+    }
+
+    /// Copies block `src` to `dst`, sharing its region instead of copying
+    /// the bytes if `share` is set. See `copy` and `share`.
+    pub(crate) fn copy_block(&mut self, src: BlockId, dst: BlockId, share: bool) -> Option<()> {
+        if self.is_swapped(src) && !self.exists(dst) {
+            self.swap_in(src)?;
+        }
+        if !self.allocations.contains_key(&src) || self.exists(dst) {
+            return None;
+        }
+        if !self.journal_operation(|| Operation::Copy { src, dst }) {
+            return None;
+        }
+
+        let mut block = self.allocations.get(&src)?.clone();
+        if share && block.inline.is_none() && block.size > 0 {
+            self.share_region(block.start);
+        } else if block.inline.is_none() {
+            // Copy the bytes into a fresh region of the same size
+            let start = self.allocate(block.size)?;
+            self.memory.copy_within(block.start..block.start + block.size, start);
+            block.start = start;
+        }
+
+        let raw_size = block.raw_size;
+        self.allocations.insert(dst, block);
+        if let Some(tags) = self.tags.get(&src).cloned() {
+            self.tags.insert(dst, tags);
+        }
+        self.save_index();
+        if self.history.recording() {
+            if let Some(data) = self.peek(dst) {
+                self.history.record(HistoryEntry::Inserted { id: dst, data });
+            }
+        }
+        self.sequence += 1;
+        self.notify(Change::Inserted { id: dst, size: raw_size });
+
+        Some(())
+    }
+
+    /// Moves the block stored under `old_id` to `new_id`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the block was renamed.
+    /// - `None` if `old_id` does not exist or `new_id` already exists.
+    ///
+    /// # Behavior
+    ///
+    /// Only the allocation table changes; the bytes stay where they are.
+    /// Tags, seals, pins, and previous versions move with the block; handles to the old ID become stale.
+    /// Subscribers see the old ID deleted and the new ID inserted.
+    pub fn rename(&mut self, old_id: BlockId, new_id: BlockId) -> Option<()> {
+        if self.is_swapped(old_id) && !self.exists(new_id) {
+            self.swap_in(old_id)?;
+        }
+        if !self.allocations.contains_key(&old_id) || self.exists(new_id) {
+            return None;
+        }
+        if !self.journal_operation(|| Operation::Rename { from: old_id, to: new_id }) {
+            return None;
+        }
+
+        let block = self.allocations.remove(&old_id)?;
+        let raw_size = block.raw_size;
+        self.allocations.insert(new_id, block);
+        if let Some(seal) = self.seals.remove(&old_id) {
+            self.seals.insert(new_id, seal);
+        }
+        if self.pinned.remove(&old_id) {
+            self.pinned.insert(new_id);
+        }
+        self.retire_handles(old_id);
+        self.save_index();
+        self.history.record(HistoryEntry::Renamed { from: old_id, to: new_id });
+        self.sequence += 1;
+        self.notify(Change::Deleted { id: old_id });
+        if let Some(tags) = self.tags.remove(&old_id) {
+            self.tags.insert(new_id, tags);
+        }
+        if let Some(versions) = self.versions.remove(&old_id) {
+            self.versions.insert(new_id, versions);
+        }
+        self.notify(Change::Inserted { id: new_id, size: raw_size });
+
+        Some(())
+    }
+}
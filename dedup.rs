@@ -0,0 +1,174 @@
+use crate::erase::{erase, ErasePolicy};
+use crate::memory_manager::{BlockId, MemoryManager};
+
+impl MemoryManager {
+    /// Creates a new `MemoryManager` that stores identical data only once.
+    ///
+    /// # Behavior
+    ///
+    /// See `set_dedup`.
+    pub fn with_dedup() -> Self {
+        Self { dedup: true, ..Self::new() }
+    }
+
+    /// Turns deduplication of new blocks on or off.
+    ///
+    /// # Behavior
+    ///
+    /// While on, inserting data whose stored bytes match an existing block
+    /// makes the new block share that block's region instead of taking a
+    /// second copy, and `copy()` shares the source region. Shared regions are
+    /// reference counted: deleting one of the blocks only drops a reference,
+    /// and updating one first gives it a private copy (copy-on-write), so the
+    /// other blocks keep their data. Encrypted data never matches, since
+    /// every encryption uses a fresh nonce. Inline blocks and blocks placed
+    /// with `insert_at` or `reserve` are never shared. Turning dedup off
+    /// keeps existing sharing intact.
+    pub fn set_dedup(&mut self, enabled: bool) {
+        self.dedup = enabled;
+    }
+
+    /// Creates an alias of block `id`: a new block sharing its bytes.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(alias)` with the new block's ID, the lowest unused one.
+    /// - `None` if `id` does not exist or every ID is taken.
+    ///
+    /// # Behavior
+    ///
+    /// See `share_into`.
+    pub fn share(&mut self, id: BlockId) -> Option<BlockId> {
+        let alias = (0..=BlockId::MAX).find(|&candidate| !self.exists(candidate))?;
+        self.share_into(id, alias)?;
+        Some(alias)
+    }
+
+    /// Creates an alias of block `id` under `alias`, which must not exist.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the alias was created.
+    /// - `None` if `id` does not exist or `alias` already exists.
+    ///
+    /// # Behavior
+    ///
+    /// Works like `copy`, with or without dedup, except that the new block
+    /// references the same region instead of a copy of it, and `refcount`
+    /// counts it. Deleting either block only drops a reference; the bytes
+    /// are freed with the last one. Updating either gives it a private copy
+    /// first, so the other keeps its data. Inline and empty blocks have no
+    /// region to share, so their alias gets its own copy of the value.
+    /// The journal records a plain copy, so replaying it only shares with
+    /// dedup on.
+    pub fn share_into(&mut self, id: BlockId, alias: BlockId) -> Option<()> {
+        self.copy_block(id, alias, true)
+    }
+
+    /// Returns the number of blocks referencing the region of block `id`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(1)` for a block with a region of its own, or an inline block.
+    /// - `Some(n)` if `n` blocks share the region.
+    /// - `None` if no block has this ID.
+    pub fn refcount(&self, id: BlockId) -> Option<usize> {
+        let block = self.allocations.get(&id)?;
+        if block.inline.is_some() || block.size == 0 {
+            return Some(1);
+        }
+        Some(self.refcounts.get(&block.start).copied().unwrap_or(1))
+    }
+
+    /// Returns the number of bytes dedup avoids storing: the size of every
+    /// shared region times the number of extra references to it.
+    pub fn dedup_saved_bytes(&self) -> usize {
+        self.refcounts
+            .iter()
+            .map(|(start, count)| self.region_size(*start) * (count - 1))
+            .sum()
+    }
+
+    /// Finds a region already holding exactly `stored`, unencrypted and
+    /// encoded the same way, starting at a multiple of `alignment`.
+    pub(crate) fn find_duplicate(&self, stored: &[u8], checksum: u32, compressed: bool, alignment: usize) -> Option<usize> {
+        self.allocations
+            .values()
+            .find(|block| {
+                block.inline.is_none()
+                    && !stored.is_empty()
+                    && block.nonce.is_none()
+                    && block.compressed == compressed
+                    && block.checksum == checksum
+                    && block.size == stored.len()
+                    && block.start % alignment == 0
+                    && &self.memory[block.start..block.start + block.size] == stored
+            })
+            .map(|block| block.start)
+    }
+
+    /// Adds a reference to the region starting at `start`.
+    pub(crate) fn share_region(&mut self, start: usize) {
+        *self.refcounts.entry(start).or_insert(1) += 1;
+    }
+
+    /// Drops a reference to the region of `size` bytes at `start`.
+    ///
+    /// The last reference erases the region with `policy` and returns it to
+    /// the free list; earlier ones leave the bytes for the remaining blocks.
+    pub(crate) fn release_region(&mut self, start: usize, size: usize, policy: ErasePolicy) {
+        if let Some(count) = self.refcounts.get_mut(&start).filter(|_| size > 0) {
+            *count -= 1;
+            if *count == 1 {
+                self.refcounts.remove(&start);
+            }
+            return;
+        }
+        erase(&mut self.memory[start..start + size], policy);
+        self.free_region(start, size);
+    }
+
+    /// Gives block `id` a private copy of its region if the region is shared.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the block now has a region of its own.
+    /// - `None` if there is not enough space for the copy.
+    pub(crate) fn unshare(&mut self, id: BlockId) -> Option<()> {
+        let block = self.allocations.get(&id)?;
+        let (start, size) = (block.start, block.size);
+        if block.inline.is_some() || size == 0 || !self.refcounts.contains_key(&start) {
+            return Some(());
+        }
+
+        let copy = self.allocate(size)?;
+        self.memory.copy_within(start..start + size, copy);
+        self.release_region(start, size, ErasePolicy::None);
+        if let Some(block) = self.allocations.get_mut(&id) {
+            block.start = copy;
+        }
+        Some(())
+    }
+
+    /// Recounts shared regions from the allocation table, e.g. after it was
+    /// loaded from an index file or compaction moved regions.
+    pub(crate) fn rebuild_refcounts(&mut self) {
+        self.refcounts.clear();
+        for block in self.allocations.values().filter(|block| block.inline.is_none() && block.size > 0) {
+            *self.refcounts.entry(block.start).or_insert(0) += 1;
+        }
+        self.refcounts.retain(|_, count| *count > 1);
+    }
+
+    /// Returns `true` if more than one block references the region of block `id`.
+    pub(crate) fn is_shared(&self, id: BlockId) -> bool {
+        self.refcount(id).is_some_and(|count| count > 1)
+    }
+
+    fn region_size(&self, start: usize) -> usize {
+        self.allocations
+            .values()
+            .find(|block| block.inline.is_none() && block.size > 0 && block.start == start)
+            .map_or(0, |block| block.size)
+    }
+}
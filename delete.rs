@@ -1,25 +1,30 @@
-use crate::memory_manager::MemoryManager;
+use log::{info, warn};
+
+use crate::error::MemoryError;
+use crate::memory_manager::{BlockId, MemoryManager};
 
 /// Attempts to delete a memory allocation by its ID.
 ///
 /// # Parameters
 ///
 /// - `manager`: A mutable reference to the `MemoryManager` instance.
-/// - `id`: The `usize` identifier of the memory block to delete. Internally cast to `u16`.
+/// - `id`: The identifier of the memory block to delete.
 ///
-/// # Behavior
+/// # Returns
 ///
-/// - If the specified ID exists, the corresponding memory block is cleared
-///   and a success message is printed.
-/// - If the ID is not found, an error message is printed.
-pub fn delete(manager: &mut MemoryManager, id: usize) {
+/// - `Ok(())` if the specified ID existed; the corresponding memory block is
+///   cleared and a success message is logged at info level.
+/// - `Err(reason)` otherwise, logged as a warning.
+pub fn delete(manager: &mut MemoryManager, id: BlockId) -> Result<(), MemoryError> {
     // Attempt to delete the memory associated with the provided ID.
-    // The ID is cast to u16 as the MemoryManager uses u16 for IDs.
-    if manager.delete(id as u16).is_some() {
-        // If deletion is successful, print a success message
-        println!("Delete successful for ID {}", id);
+    if manager.delete(id).is_some() {
+        // If deletion is successful, log a success message
+        info!("Delete successful for ID {}", id);
+        Ok(())
     } else {
-        // If the ID is not found, print a failure message
-        println!("Delete failed for ID {}: ID not found", id);
+        // Otherwise log a failure message with the reason
+        let reason = manager.delete_failure(id);
+        warn!("Delete failed for ID {}: {}", id, reason);
+        Err(reason)
     }
 }
@@ -6,10 +6,10 @@ use crate::memory_manager::MemoryManager;
 /// - `manager`: A reference to the `MemoryManager` instance.
 ///
 /// # Behavior
-/// This function prints the current state of memory allocations managed by the `MemoryManager`.
-/// It calls the `dump` method, which outputs the details of all memory blocks, including their ID,
-/// starting address, size, and data.
+/// This function logs the current state of memory allocations managed by the `MemoryManager`.
+/// It calls the `dump` method, which logs the details of all memory blocks, including their ID,
+/// starting address, size, and data, at info level.
 pub fn dump(manager: &MemoryManager) {
-    // Call the dump method on the MemoryManager to display memory contents
+    // Call the dump method on the MemoryManager to log memory contents
     manager.dump();
 }
@@ -0,0 +1,102 @@
+/// The ChaCha20 quarter round, applied to four words of the state.
+fn quarter_round(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(12);
+    state[a] = state[a].wrapping_add(state[b]);
+    state[d] = (state[d] ^ state[a]).rotate_left(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_left(7);
+}
+
+/// Runs the 20 ChaCha rounds (10 column/diagonal double rounds) over `state`.
+fn rounds(state: &mut [u32; 16]) {
+    for _ in 0..10 {
+        quarter_round(state, 0, 4, 8, 12);
+        quarter_round(state, 1, 5, 9, 13);
+        quarter_round(state, 2, 6, 10, 14);
+        quarter_round(state, 3, 7, 11, 15);
+        quarter_round(state, 0, 5, 10, 15);
+        quarter_round(state, 1, 6, 11, 12);
+        quarter_round(state, 2, 7, 8, 13);
+        quarter_round(state, 3, 4, 9, 14);
+    }
+}
+
+/// Builds the initial state from the constants, key, and the four trailing words.
+fn initial_state(key: &[u8; 32], tail: [u32; 4]) -> [u32; 16] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&[0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574]); // "expand 32-byte k"
+    for (i, word) in key.chunks_exact(4).enumerate() {
+        state[4 + i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    state[12..].copy_from_slice(&tail);
+    state
+}
+
+fn le_words<const N: usize>(bytes: &[u8]) -> [u32; N] {
+    let mut words = [0u32; N];
+    for (i, word) in bytes.chunks_exact(4).take(N).enumerate() {
+        words[i] = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+    }
+    words
+}
+
+/// Produces one 64-byte ChaCha20 keystream block (RFC 8439).
+pub fn chacha20_block(key: &[u8; 32], counter: u32, nonce: &[u8; 12]) -> [u8; 64] {
+    let [n0, n1, n2] = le_words::<3>(nonce);
+    let initial = initial_state(key, [counter, n0, n1, n2]);
+    let mut state = initial;
+    rounds(&mut state);
+
+    let mut out = [0u8; 64];
+    for i in 0..16 {
+        let word = state[i].wrapping_add(initial[i]);
+        out[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+    out
+}
+
+/// Derives an XChaCha20 subkey from `key` and the first 16 bytes of the nonce.
+pub fn hchacha20(key: &[u8; 32], nonce: &[u8; 16]) -> [u8; 32] {
+    let mut state = initial_state(key, le_words::<4>(nonce));
+    rounds(&mut state);
+
+    // The subkey is the first and last rows, without the feed-forward addition
+    let mut out = [0u8; 32];
+    for (i, &word) in state[..4].iter().chain(&state[12..]).enumerate() {
+        out[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+    out
+}
+
+/// Encrypts or decrypts `data` in place with XChaCha20.
+///
+/// # Parameters
+///
+/// - `key`: The 256-bit secret key.
+/// - `nonce`: A 192-bit nonce that must never be reused with the same key.
+/// - `data`: The bytes to transform. Applying the function twice restores them.
+pub fn xchacha20_xor(key: &[u8; 32], nonce: &[u8; 24], data: &mut [u8]) {
+    let mut prefix = [0u8; 16];
+    prefix.copy_from_slice(&nonce[..16]);
+    let subkey = hchacha20(key, &prefix);
+
+    let mut inner = [0u8; 12];
+    inner[4..].copy_from_slice(&nonce[16..]);
+
+    for (counter, chunk) in data.chunks_mut(64).enumerate() {
+        let keystream = chacha20_block(&subkey, counter as u32, &inner);
+        for (byte, key_byte) in chunk.iter_mut().zip(keystream.iter()) {
+            *byte ^= key_byte;
+        }
+    }
+}
+
+/// Expands a per-block nonce counter into a full XChaCha20 nonce.
+pub(crate) fn nonce_from_counter(counter: u64) -> [u8; 24] {
+    let mut nonce = [0u8; 24];
+    nonce[..8].copy_from_slice(&counter.to_le_bytes());
+    nonce
+}
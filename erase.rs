@@ -0,0 +1,58 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::memory_manager::MemoryManager;
+
+/// What happens to a block's bytes when it is deleted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErasePolicy {
+    /// Leave the bytes in place. Fastest, but deleted data stays readable in
+    /// memory (and in the file, when memory-mapped) until overwritten.
+    None,
+    /// Overwrite the bytes with zeros.
+    #[default]
+    Zero,
+    /// Overwrite the bytes with random data this many times.
+    Random(u32),
+}
+
+impl MemoryManager {
+    /// Creates a new `MemoryManager` that erases deleted blocks according to `policy`.
+    pub fn with_erase_policy(policy: ErasePolicy) -> Self {
+        Self { erase_policy: policy, ..Self::new() }
+    }
+
+    /// Sets how `delete` and `delete_content` erase the bytes they release.
+    pub fn set_erase_policy(&mut self, policy: ErasePolicy) {
+        self.erase_policy = policy;
+    }
+}
+
+/// Erases `bytes` according to `policy`.
+pub(crate) fn erase(bytes: &mut [u8], policy: ErasePolicy) {
+    match policy {
+        ErasePolicy::None => {}
+        ErasePolicy::Zero => bytes.fill(0),
+        ErasePolicy::Random(passes) => {
+            // RandomState is seeded by the OS and needs no clock, so this also works on wasm32
+            let mut state = RandomState::new().build_hasher().finish();
+            for _ in 0..passes {
+                for chunk in bytes.chunks_mut(8) {
+                    let random = splitmix64(&mut state).to_le_bytes();
+                    chunk.copy_from_slice(&random[..chunk.len()]);
+                }
+            }
+        }
+    }
+}
+
+/// Advances a SplitMix64 generator. Not cryptographically secure, but each
+/// pass is unpredictable enough to defeat reading back the original bytes.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,127 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::checksum::crc32;
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// Why an operation on a `MemoryManager` failed, with the figures needed to act on it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MemoryError {
+    /// No block has this ID.
+    NotFound { id: BlockId },
+    /// A block with this ID already exists.
+    DuplicateId { id: BlockId },
+    /// No free region is large enough for the data.
+    OutOfSpace { requested: usize, free: usize, largest_free: usize },
+    /// Part of the requested address range is in use or outside memory.
+    Occupied { start: usize, size: usize },
+    /// The new data does not fit in the block being updated.
+    TooLarge { requested: usize, capacity: usize },
+    /// The block's stored bytes no longer match its checksum.
+    Corrupted { id: BlockId },
+    /// Something wrote over the guard bytes around the block. See `set_canaries`.
+    GuardSmashed { id: BlockId },
+    /// The manager refused the operation for another reason, e.g. the
+    /// journal could not be written or the data could not be decrypted.
+    Rejected,
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryError::NotFound { .. } => write!(f, "ID not found"),
+            MemoryError::DuplicateId { .. } => write!(f, "ID already exists"),
+            MemoryError::OutOfSpace { requested, free, largest_free } => write!(
+                f,
+                "not enough space: requested {} bytes, {} bytes free, largest free region {} bytes",
+                requested, free, largest_free
+            ),
+            MemoryError::Occupied { start, size } => {
+                write!(f, "bytes {}..{} are not all free", start, start + size)
+            }
+            MemoryError::TooLarge { requested, capacity } => {
+                write!(f, "new data is {} bytes but the block holds at most {} bytes", requested, capacity)
+            }
+            MemoryError::Corrupted { .. } => write!(f, "stored data does not match its checksum"),
+            MemoryError::GuardSmashed { .. } => write!(f, "guard bytes around the block were overwritten"),
+            MemoryError::Rejected => write!(f, "rejected (journal write failed or data could not be decoded)"),
+        }
+    }
+}
+
+impl Error for MemoryError {}
+
+impl MemoryManager {
+    /// Explains why inserting `requested` bytes under `id` failed.
+    ///
+    /// Call this right after `insert` returns `None`, before anything else
+    /// changes the manager.
+    pub fn insert_failure(&self, id: BlockId, requested: usize) -> MemoryError {
+        if self.exists(id) {
+            return MemoryError::DuplicateId { id };
+        }
+        let largest_free = self.allocator.largest();
+        if requested + 2 * self.guard_len(requested) > largest_free {
+            return MemoryError::OutOfSpace { requested, free: self.free_bytes(), largest_free };
+        }
+        MemoryError::Rejected
+    }
+
+    /// Explains why placing `requested` bytes at `start` under `id` failed.
+    pub fn insert_at_failure(&self, id: BlockId, start: usize, requested: usize) -> MemoryError {
+        if self.exists(id) {
+            return MemoryError::DuplicateId { id };
+        }
+        let free = start + requested <= self.memory.len()
+            && self.allocator.regions().iter().any(|&(s, len)| s <= start && start + requested <= s + len);
+        if requested > 0 && !free {
+            return MemoryError::Occupied { start, size: requested };
+        }
+        MemoryError::Rejected
+    }
+
+    /// Explains why reading `id` failed.
+    pub fn read_failure(&self, id: BlockId) -> MemoryError {
+        match self.allocations.get(&id) {
+            None if self.is_swapped(id) => MemoryError::Corrupted { id },
+            None => MemoryError::NotFound { id },
+            Some(block) if crc32(self.stored(block)) != block.checksum => MemoryError::Corrupted { id },
+            Some(_) if !self.guards_intact(id) => MemoryError::GuardSmashed { id },
+            Some(_) => MemoryError::Rejected,
+        }
+    }
+
+    /// Explains why updating `id` with `requested` bytes failed.
+    pub fn update_failure(&self, id: BlockId, requested: usize) -> MemoryError {
+        let Some(block) = self.allocations.get(&id) else {
+            if let Some(block) = self.swapped_block(id) {
+                // There was no room to bring the block back into memory
+                let largest_free = self.allocator.largest();
+                return MemoryError::OutOfSpace { requested: block.size, free: self.free_bytes(), largest_free };
+            }
+            return MemoryError::NotFound { id };
+        };
+        let capacity = if block.inline.is_some() { self.inline_threshold } else { block.size };
+        if requested > capacity {
+            return MemoryError::TooLarge { requested, capacity };
+        }
+        if !self.guards_intact(id) {
+            return MemoryError::GuardSmashed { id };
+        }
+        // A shared region needs room for a private copy first
+        let largest_free = self.allocator.largest();
+        if self.is_shared(id) && block.size + 2 * self.guard_len(block.size) > largest_free {
+            return MemoryError::OutOfSpace { requested: block.size, free: self.free_bytes(), largest_free };
+        }
+        MemoryError::Rejected
+    }
+
+    /// Explains why deleting `id` failed.
+    pub fn delete_failure(&self, id: BlockId) -> MemoryError {
+        if self.exists(id) {
+            MemoryError::Rejected
+        } else {
+            MemoryError::NotFound { id }
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::sync::Mutex;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::pressure::Pressure;
+use crate::subscriptions::Change;
+use crate::watch::Watchpoint;
+
+/// Something that happened inside a `MemoryManager`, passed to `on_event` callbacks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// A block was stored at `start`, reserving `size` bytes (0 if inline).
+    Inserted { id: BlockId, start: usize, size: usize },
+    /// A block's data was replaced in place.
+    Updated { id: BlockId, start: usize, size: usize },
+    /// A block was removed and its memory released.
+    Deleted { id: BlockId },
+    /// `compact()` moved blocks together to merge free space.
+    Compacted { moved: usize, largest_free: usize },
+    /// A read of block `id` touched a watchpoint. `data` is what the
+    /// watchpoint covers: the block's data, or the bytes of the range.
+    WatchRead { point: Watchpoint, id: BlockId, data: Vec<u8> },
+    /// What a watchpoint covers changed. `id` is the block whose change
+    /// caused it, or `None` for a compaction. `old` and `new` are `None`
+    /// while a watched block does not exist.
+    WatchWrite { point: Watchpoint, id: Option<BlockId>, old: Option<Vec<u8>>, new: Option<Vec<u8>> },
+    /// A change moved memory pressure from one level to another (see `pressure`).
+    PressureChanged { from: Pressure, to: Pressure },
+}
+
+/// A registered `on_event` callback. The mutex lets callbacks mutate their
+/// own state while the manager stays shareable between threads.
+pub(crate) type Listener = Mutex<Box<dyn FnMut(&Event) + Send>>;
+
+impl MemoryManager {
+    /// Registers `callback` to be called after every insert, update, delete,
+    /// and compaction, whenever a watchpoint is hit (see `watch`), and when
+    /// memory pressure changes level (see `pressure`).
+    ///
+    /// # Behavior
+    ///
+    /// Callbacks run synchronously, in registration order, before the
+    /// operation returns. Unlike `subscribe`, events carry addresses and
+    /// sizes, so visualizers and tests can follow the allocator itself.
+    pub fn on_event<F: FnMut(&Event) + Send + 'static>(&mut self, callback: F) {
+        self.listeners.push(Mutex::new(Box::new(callback)));
+    }
+
+    /// Calls every registered callback with `event`.
+    pub(crate) fn emit(&self, event: Event) {
+        for listener in &self.listeners {
+            let mut callback = listener.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            callback(&event);
+        }
+    }
+
+    /// Emits the event matching `change`, looking up where the block now lives.
+    pub(crate) fn emit_change(&self, change: &Change) {
+        if self.listeners.is_empty() {
+            return;
+        }
+        let block = self.allocations.get(&change.id());
+        let (start, size) = block.map_or((0, 0), |block| (block.start, block.size));
+        let event = match *change {
+            Change::Inserted { id, .. } => Event::Inserted { id, start, size },
+            Change::Updated { id, .. } => Event::Updated { id, start, size },
+            Change::Deleted { id } => Event::Deleted { id },
+        };
+        self.emit(event);
+    }
+}
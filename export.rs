@@ -0,0 +1,237 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl MemoryManager {
+    /// Exports the allocation table and summary statistics as JSON.
+    ///
+    /// # Returns
+    ///
+    /// A JSON object with the `sequence` number, a `stats` object (the
+    /// fields of `Stats` plus `free_bytes`), and an `allocations` array in
+    /// ID order. Each allocation has `id`, `start`, `size`, `inline`, `tags`,
+    /// and `data`: the bytes a read would return, base64-encoded, or `null`
+    /// if the block cannot be read.
+    pub fn export_json(&self) -> String {
+        let stats = self.stats();
+        let mut out = String::from("{\n");
+        out.push_str(&format!("  \"sequence\": {},\n", self.sequence));
+        out.push_str(&format!(
+            "  \"stats\": {{\"allocations\": {}, \"capacity\": {}, \"used_bytes\": {}, \"raw_bytes\": {}, \
+             \"stored_bytes\": {}, \"free_bytes\": {}}},\n",
+            stats.allocations,
+            stats.capacity,
+            stats.used_bytes,
+            stats.raw_bytes,
+            stats.stored_bytes,
+            self.free_bytes()
+        ));
+        out.push_str("  \"allocations\": [");
+        for (i, (id, block)) in self.allocations.iter().enumerate() {
+            let tags: Vec<String> = self
+                .tags(*id)
+                .iter()
+                .map(|(key, value)| format!("{}: {}", json_string(key), json_string(value)))
+                .collect();
+            let data = self.peek(*id).map_or("null".to_string(), |data| json_string(&encode_base64(&data)));
+            out.push_str(&format!(
+                "{}\n    {{\"id\": {}, \"start\": {}, \"size\": {}, \"inline\": {}, \"tags\": {{{}}}, \"data\": {}}}",
+                if i == 0 { "" } else { "," },
+                id,
+                block.start,
+                block.size,
+                block.inline.is_some(),
+                tags.join(", "),
+                data
+            ));
+        }
+        out.push_str(if self.allocations.is_empty() { "]\n}\n" } else { "\n  ]\n}\n" });
+        out
+    }
+
+    /// Exports the allocation table as CSV, one row per block in ID order.
+    ///
+    /// # Returns
+    ///
+    /// CSV text with the columns `id,start,size,inline,tags,data`, where
+    /// `tags` is `key=value` pairs joined by `;` and `data` is base64 (empty
+    /// if the block cannot be read). Summary statistics come first as lines
+    /// starting with `#`, which e.g. `pandas.read_csv(..., comment="#")` skips.
+    pub fn export_csv(&self) -> String {
+        let stats = self.stats();
+        let mut out = String::new();
+        out.push_str(&format!("# sequence={}\n", self.sequence));
+        out.push_str(&format!(
+            "# allocations={} capacity={} used_bytes={} raw_bytes={} stored_bytes={} free_bytes={}\n",
+            stats.allocations,
+            stats.capacity,
+            stats.used_bytes,
+            stats.raw_bytes,
+            stats.stored_bytes,
+            self.free_bytes()
+        ));
+        out.push_str("id,start,size,inline,tags,data\n");
+        for (id, block) in &self.allocations {
+            let tags: Vec<String> = self.tags(*id).iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+            let data = self.peek(*id).map(|data| encode_base64(&data)).unwrap_or_default();
+            out.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                id,
+                block.start,
+                block.size,
+                block.inline.is_some(),
+                csv_field(&tags.join(";")),
+                data
+            ));
+        }
+        out
+    }
+
+    /// Writes every block to `path` as `id<TAB>base64data` lines, in ID order.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(count)` with the number of blocks written.
+    /// - `Err` if a block cannot be read, in which case nothing is written,
+    ///   or if the file cannot be written.
+    ///
+    /// # Behavior
+    ///
+    /// The data is what `read` returns, so `import` on a fresh manager
+    /// recreates the same blocks. Swapped-out blocks are included; tags,
+    /// content-addressed blocks, and placement are not.
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> io::Result<usize> {
+        let ids: BTreeSet<BlockId> = self.allocations.keys().copied().chain(self.swapped_ids()).collect();
+        let mut out = String::new();
+        for &id in &ids {
+            let data = self
+                .peek(id)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("block {}: {}", id, self.read_failure(id))))?;
+            out.push_str(&format!("{}\t{}\n", id, encode_base64(&data)));
+        }
+        fs::write(path, out)?;
+        Ok(ids.len())
+    }
+
+    /// Inserts every block listed in `path`, in the format `export` writes.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(count)` with the number of blocks inserted.
+    /// - `Err` with the line number if a line is malformed or repeats an ID,
+    ///   or with the reason if an insert fails. Nothing is inserted then,
+    ///   unless a transaction was already open, in which case the blocks
+    ///   inserted before the failure stay until it is rolled back.
+    ///
+    /// # Behavior
+    ///
+    /// Blank lines and lines starting with `#` are skipped. The file is
+    /// checked completely before anything is inserted, and the inserts run
+    /// in a transaction of their own unless one is already open.
+    pub fn import<P: AsRef<Path>>(&mut self, path: P) -> io::Result<usize> {
+        let contents = fs::read_to_string(path)?;
+        let mut blocks = Vec::new();
+        let mut seen = BTreeSet::new();
+        for (number, line) in contents.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |reason: &str| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", number + 1, reason));
+            let (id, data) = line.trim_end_matches('\r').split_once('\t').ok_or_else(|| invalid("expected id<TAB>base64"))?;
+            let id: BlockId = id.trim().parse().map_err(|_| invalid("bad ID"))?;
+            let data = decode_base64(data.trim()).ok_or_else(|| invalid("bad base64"))?;
+            if !seen.insert(id) {
+                return Err(invalid("duplicate ID"));
+            }
+            blocks.push((id, data));
+        }
+
+        let own_transaction = self.begin_transaction().is_some();
+        for (id, data) in &blocks {
+            if self.insert(*id, data.clone()).is_none() {
+                let reason = self.insert_failure(*id, data.len());
+                if own_transaction {
+                    self.rollback();
+                }
+                return Err(io::Error::other(format!("block {}: {}", id, reason)));
+            }
+        }
+        if own_transaction {
+            self.commit();
+        }
+        Ok(blocks.len())
+    }
+}
+
+/// Encodes bytes as standard padded base64.
+pub fn encode_base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(group >> (18 - 6 * i)) as usize & 0x3f] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes standard base64, with or without padding.
+///
+/// Returns `None` if `text` contains anything but base64 characters and
+/// trailing padding, or has an impossible length.
+pub fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    if text.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        let mut group = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = BASE64.iter().position(|&b| b == c)? as u32;
+            group |= value << (18 - 6 * i);
+        }
+        let bytes = group.to_be_bytes();
+        out.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    Some(out)
+}
+
+/// Quotes `text` as a JSON string.
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                out.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Quotes a CSV field if it contains a separator, quote, or line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
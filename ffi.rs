@@ -0,0 +1,124 @@
+//! C interface to `MemoryManager`.
+//!
+//! The header `include/memory_manager.h` is generated from this file with
+//! `cbindgen --config cbindgen.toml --output include/memory_manager.h`, and
+//! the library to link against is built with
+//! `cargo rustc --lib --release --crate-type cdylib`. Functions returning `int` use 0 for success and -1 for failure. Define
+//! `MM_WIDE_IDS` when the library is built with the `wide-ids` feature, so
+//! `BlockId` matches.
+
+use std::ptr;
+use std::slice;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// Creates a new manager. Release it with `mm_free`.
+#[no_mangle]
+pub extern "C" fn mm_new() -> *mut MemoryManager {
+    Box::into_raw(Box::new(MemoryManager::new()))
+}
+
+/// Releases a manager created by `mm_new`. Passing NULL does nothing.
+///
+/// # Safety
+///
+/// `mm` must be NULL or a pointer returned by `mm_new` that has not been freed.
+#[no_mangle]
+pub unsafe extern "C" fn mm_free(mm: *mut MemoryManager) {
+    if !mm.is_null() {
+        drop(Box::from_raw(mm));
+    }
+}
+
+/// Inserts `len` bytes from `data` under `id`.
+///
+/// Returns 0 on success, or -1 if the ID exists, there is not enough space,
+/// or `mm` is NULL.
+///
+/// # Safety
+///
+/// `mm` must come from `mm_new`, and `data` must point to `len` readable
+/// bytes (it may be NULL when `len` is 0).
+#[no_mangle]
+pub unsafe extern "C" fn mm_insert(mm: *mut MemoryManager, id: BlockId, data: *const u8, len: usize) -> i32 {
+    let Some(manager) = mm.as_mut() else {
+        return -1;
+    };
+    status(manager.insert(id, bytes(data, len)).map(|_| ()))
+}
+
+/// Reads the data stored under `id` into `out`.
+///
+/// Returns the length of the data, or -1 if the ID does not exist, the data
+/// is corrupted, or `mm` is NULL. At most `capacity` bytes are copied, so
+/// call with `out` NULL and `capacity` 0 first to learn the length.
+///
+/// # Safety
+///
+/// `mm` must come from `mm_new`, and `out` must point to `capacity` writable
+/// bytes (it may be NULL when `capacity` is 0).
+#[no_mangle]
+pub unsafe extern "C" fn mm_read(mm: *const MemoryManager, id: BlockId, out: *mut u8, capacity: usize) -> isize {
+    let Some(data) = mm.as_ref().and_then(|manager| manager.read_cow(id)) else {
+        return -1;
+    };
+    if !out.is_null() {
+        ptr::copy_nonoverlapping(data.as_ptr(), out, data.len().min(capacity));
+    }
+    data.len() as isize
+}
+
+/// Replaces the data stored under `id` with `len` bytes from `data`.
+///
+/// Returns 0 on success, or -1 if the ID does not exist, the data does not
+/// fit in the block, or `mm` is NULL.
+///
+/// # Safety
+///
+/// As for `mm_insert`.
+#[no_mangle]
+pub unsafe extern "C" fn mm_update(mm: *mut MemoryManager, id: BlockId, data: *const u8, len: usize) -> i32 {
+    let Some(manager) = mm.as_mut() else {
+        return -1;
+    };
+    status(manager.update(id, bytes(data, len)))
+}
+
+/// Deletes the data stored under `id`.
+///
+/// Returns 0 on success, or -1 if the ID does not exist or `mm` is NULL.
+///
+/// # Safety
+///
+/// `mm` must come from `mm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn mm_delete(mm: *mut MemoryManager, id: BlockId) -> i32 {
+    let Some(manager) = mm.as_mut() else {
+        return -1;
+    };
+    status(manager.delete(id))
+}
+
+/// Prints the contents of memory to standard output, in the format of `MemoryManager::dump`.
+///
+/// # Safety
+///
+/// `mm` must be NULL or come from `mm_new`.
+#[no_mangle]
+pub unsafe extern "C" fn mm_dump(mm: *const MemoryManager) {
+    if let Some(manager) = mm.as_ref() {
+        print!("{}", manager.dump_text());
+    }
+}
+
+/// Copies `len` bytes from a C buffer, treating NULL as empty.
+unsafe fn bytes(data: *const u8, len: usize) -> Vec<u8> {
+    if data.is_null() || len == 0 {
+        return Vec::new();
+    }
+    slice::from_raw_parts(data, len).to_vec()
+}
+
+fn status(result: Option<()>) -> i32 {
+    if result.is_some() { 0 } else { -1 }
+}
@@ -0,0 +1,102 @@
+use crate::checksum::crc32;
+use crate::history::HistoryEntry;
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::subscriptions::Change;
+
+impl MemoryManager {
+    /// Sets every byte of block `id` to `byte`, like `memset`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the block was filled.
+    /// - `None` if it does not exist, or `update` would refuse the result.
+    ///
+    /// # Behavior
+    ///
+    /// Covers every byte `read` returns, including the zero padding left
+    /// by a shrinking update. See `modify` for when bytes are changed in place.
+    pub fn fill(&mut self, id: BlockId, byte: u8) -> Option<()> {
+        self.modify(id, |data| {
+            data.fill(byte);
+            Some(())
+        })
+    }
+
+    /// Sets `len` bytes of block `id`, starting at `offset`, to `byte`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the range was filled.
+    /// - `None` if the block does not exist, the range runs past its end,
+    ///   or `update` would refuse the result. Nothing is changed then.
+    pub fn fill_range(&mut self, id: BlockId, offset: usize, len: usize, byte: u8) -> Option<()> {
+        self.modify(id, |data| {
+            data.get_mut(offset..offset.checked_add(len)?)?.fill(byte);
+            Some(())
+        })
+    }
+
+    /// Copies `len` bytes of block `id` from `src_offset` to `dst_offset`,
+    /// like `memmove`. The ranges may overlap.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the bytes were copied.
+    /// - `None` if the block does not exist, either range runs past its
+    ///   end, or `update` would refuse the result. Nothing is changed then.
+    pub fn copy_within(&mut self, id: BlockId, src_offset: usize, dst_offset: usize, len: usize) -> Option<()> {
+        self.modify(id, |data| {
+            if src_offset.max(dst_offset).checked_add(len)? > data.len() {
+                return None;
+            }
+            data.copy_within(src_offset..src_offset + len, dst_offset);
+            Some(())
+        })
+    }
+
+    /// Changes the data of block `id` with `edit`, which returns `None`
+    /// without touching the data to refuse the change.
+    ///
+    /// # Behavior
+    ///
+    /// Plain blocks with a region of their own are edited directly in
+    /// memory; their data is only copied for the undo history and versions.
+    /// Anything else goes through `peek` and `update`: inline, compressed,
+    /// encrypted, and shared blocks, since their stored bytes are not the
+    /// data, and every block while a journal or trace must record the new
+    /// data before it is applied.
+    fn modify(&mut self, id: BlockId, edit: impl FnOnce(&mut [u8]) -> Option<()>) -> Option<()> {
+        if self.is_swapped(id) {
+            self.swap_in(id)?;
+        }
+        let block = self.allocations.get(&id)?;
+        let plain = block.inline.is_none() && !block.compressed && block.nonce.is_none();
+        let logged = self.journal.is_some() || self.trace.is_some();
+
+        if !plain || logged || self.is_shared(id) {
+            let mut data = self.peek(id)?;
+            edit(&mut data)?;
+            return self.update(id, data);
+        }
+
+        let (start, size, raw_size) = (block.start, block.size, block.raw_size);
+        if !self.guards_intact(id) {
+            return None; // Something already wrote past this block
+        }
+        let before = (self.history.recording() || self.version_depth > 0).then(|| self.memory[start..start + size].to_vec());
+        edit(&mut self.memory[start..start + size])?;
+        let checksum = crc32(&self.memory[start..start + size]);
+        self.allocations.get_mut(&id)?.checksum = checksum;
+        self.save_index();
+        if let Some(before) = before {
+            if self.history.recording() {
+                let after = self.memory[start..start + size].to_vec();
+                self.history.record(HistoryEntry::Updated { id, before: before.clone(), after });
+            }
+            self.keep_version(id, before);
+        }
+        self.sequence += 1;
+        self.notify(Change::Updated { id, size: raw_size });
+        Some(())
+    }
+}
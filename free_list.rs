@@ -0,0 +1,287 @@
+#[cfg(feature = "std")]
+use crate::allocator::Allocator;
+#[cfg(feature = "std")]
+use crate::builder::Strategy;
+
+/// Where a `FreeList` keeps its free regions: a `Vec`, or a `FixedRegions`
+/// array where there is no heap.
+pub trait Regions {
+    /// Returns the regions in address order.
+    fn as_slice(&self) -> &[(usize, usize)];
+
+    /// Returns the regions in address order, for resizing them in place.
+    fn as_mut_slice(&mut self) -> &mut [(usize, usize)];
+
+    /// Inserts `region` at `index`, shifting the ones after it.
+    fn insert(&mut self, index: usize, region: (usize, usize));
+
+    /// Removes and returns the region at `index`.
+    fn remove(&mut self, index: usize) -> (usize, usize);
+
+    /// Removes every region.
+    fn clear(&mut self);
+}
+
+#[cfg(feature = "std")]
+impl Regions for Vec<(usize, usize)> {
+    fn as_slice(&self) -> &[(usize, usize)] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [(usize, usize)] {
+        self
+    }
+
+    fn insert(&mut self, index: usize, region: (usize, usize)) {
+        Vec::insert(self, index, region);
+    }
+
+    fn remove(&mut self, index: usize) -> (usize, usize) {
+        Vec::remove(self, index)
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+}
+
+/// Room for up to `N` free regions without a heap.
+///
+/// Coalescing keeps at most one more region than there are blocks, and
+/// neither `allocate` without alignment nor `free` ever needs more than
+/// that, so `N` equal to the most blocks stored at once is enough.
+#[derive(Clone, Debug)]
+pub struct FixedRegions<const N: usize> {
+    regions: [(usize, usize); N],
+    len: usize,
+}
+
+impl<const N: usize> FixedRegions<N> {
+    /// Creates storage without regions.
+    pub fn new() -> Self {
+        Self { regions: [(0, 0); N], len: 0 }
+    }
+}
+
+impl<const N: usize> Default for FixedRegions<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Regions for FixedRegions<N> {
+    fn as_slice(&self) -> &[(usize, usize)] {
+        &self.regions[..self.len]
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [(usize, usize)] {
+        &mut self.regions[..self.len]
+    }
+
+    /// # Panics
+    ///
+    /// If all `N` entries are in use.
+    fn insert(&mut self, index: usize, region: (usize, usize)) {
+        assert!(self.len < N, "more than {} free regions", N);
+        self.regions.copy_within(index..self.len, index + 1);
+        self.regions[index] = region;
+        self.len += 1;
+    }
+
+    fn remove(&mut self, index: usize) -> (usize, usize) {
+        let region = self.as_slice()[index];
+        self.regions.copy_within(index + 1..self.len, index);
+        self.len -= 1;
+        region
+    }
+
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+/// The free regions of memory, kept sorted by address and fully coalesced.
+///
+/// Allocation is first fit at byte granularity, so there is no internal
+/// fragmentation, but holes left by deletes can be too small to reuse.
+/// `MemoryManager` keeps the regions in a `Vec`; `pool::Pool` runs the same
+/// code over `FixedRegions`.
+#[derive(Clone, Debug)]
+pub struct FreeList<R> {
+    capacity: usize, // Number of bytes managed
+    regions: R,      // (start index, size), sorted by start
+}
+
+#[cfg(feature = "std")]
+impl FreeList<Vec<(usize, usize)>> {
+    /// Creates a free list covering `capacity` bytes starting at address 0.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_regions(capacity, Vec::new())
+    }
+}
+
+impl<R: Regions> FreeList<R> {
+    /// Creates a free list covering `capacity` bytes starting at address 0,
+    /// keeping its regions in `regions`, whose contents are discarded.
+    pub fn with_regions(capacity: usize, mut regions: R) -> Self {
+        regions.clear();
+        if capacity > 0 {
+            regions.insert(0, (0, capacity));
+        }
+        Self { capacity, regions }
+    }
+
+    /// Returns the number of bytes being managed.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Reserves `size` bytes at a multiple of `alignment` in the first region
+    /// that fits. Bytes skipped to reach the aligned start, and bytes left
+    /// after the block, stay on the free list.
+    pub fn allocate(&mut self, size: usize, alignment: usize) -> Option<usize> {
+        debug_assert!(alignment.is_power_of_two());
+        if size == 0 {
+            return Some(self.regions.as_slice().first().map_or(0, |&(start, _)| start));
+        }
+
+        for i in 0..self.regions.as_slice().len() {
+            let (start, len) = self.regions.as_slice()[i];
+            let aligned = start.next_multiple_of(alignment);
+            let padding = aligned - start;
+            if padding + size > len {
+                continue;
+            }
+
+            // Replace the region with whatever is left on either side of the block
+            let after = (aligned + size, len - padding - size);
+            self.regions.remove(i);
+            if after.1 > 0 {
+                self.regions.insert(i, after);
+            }
+            if padding > 0 {
+                self.regions.insert(i, (start, padding));
+            }
+            return Some(aligned);
+        }
+
+        None
+    }
+
+    /// Reserves exactly `start..start + size`, or returns `false` unless the
+    /// whole range is free.
+    pub fn reserve(&mut self, start: usize, size: usize) -> bool {
+        if size == 0 {
+            return true;
+        }
+
+        // The only region that can contain the range is the last one starting at or before it
+        let i = self.regions.as_slice().partition_point(|&(s, _)| s <= start);
+        if i == 0 {
+            return false;
+        }
+        let (region_start, len) = self.regions.as_slice()[i - 1];
+        if start + size > region_start + len {
+            return false;
+        }
+
+        let before = (region_start, start - region_start);
+        let after = (start + size, region_start + len - start - size);
+        self.regions.remove(i - 1);
+        if after.1 > 0 {
+            self.regions.insert(i - 1, after);
+        }
+        if before.1 > 0 {
+            self.regions.insert(i - 1, before);
+        }
+        true
+    }
+
+    /// Returns `start..start + size` to the free space, merging it with any
+    /// adjacent free regions.
+    pub fn free(&mut self, start: usize, size: usize) {
+        if size == 0 {
+            return;
+        }
+
+        // Merge before inserting, so the list never holds more regions than it ends up with
+        let regions = self.regions.as_slice();
+        let i = regions.partition_point(|&(s, _)| s < start);
+        let next = regions.get(i).filter(|&&(s, _)| s == start + size).map(|&(_, len)| len);
+        let joins_previous = i > 0 && regions[i - 1].0 + regions[i - 1].1 == start;
+        match (joins_previous, next) {
+            (true, Some(len)) => {
+                self.regions.remove(i);
+                self.regions.as_mut_slice()[i - 1].1 += size + len;
+            }
+            (true, None) => self.regions.as_mut_slice()[i - 1].1 += size,
+            (false, Some(len)) => self.regions.as_mut_slice()[i] = (start, size + len),
+            (false, None) => self.regions.insert(i, (start, size)),
+        }
+    }
+
+    /// Returns the total number of free bytes.
+    pub fn free_bytes(&self) -> usize {
+        self.regions.as_slice().iter().map(|&(_, size)| size).sum()
+    }
+
+    /// Returns the size of the largest free region, or 0 if memory is full.
+    pub fn largest(&self) -> usize {
+        self.regions.as_slice().iter().map(|&(_, size)| size).max().unwrap_or(0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Allocator for FreeList<Vec<(usize, usize)>> {
+    fn strategy(&self) -> Option<Strategy> {
+        Some(Strategy::FreeList)
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn allocate(&mut self, size: usize, alignment: usize) -> Option<usize> {
+        FreeList::allocate(self, size, alignment)
+    }
+
+    fn reserve(&mut self, start: usize, size: usize) -> bool {
+        FreeList::reserve(self, start, size)
+    }
+
+    fn free(&mut self, start: usize, size: usize) {
+        FreeList::free(self, start, size)
+    }
+
+    fn rebuild(&mut self, mut used: Vec<(usize, usize)>) {
+        used.sort_unstable();
+        self.regions.clear();
+        let mut cursor = 0;
+        // Empty blocks reserve nothing, and would split the free space they sit in
+        for (start, size) in used.into_iter().filter(|&(_, size)| size > 0) {
+            if start > cursor {
+                self.regions.push((cursor, start - cursor));
+            }
+            cursor = cursor.max(start + size);
+        }
+        if self.capacity > cursor {
+            self.regions.push((cursor, self.capacity - cursor));
+        }
+    }
+
+    fn free_bytes(&self) -> usize {
+        FreeList::free_bytes(self)
+    }
+
+    fn largest(&self) -> usize {
+        FreeList::largest(self)
+    }
+
+    fn regions(&self) -> Vec<(usize, usize)> {
+        self.regions.clone()
+    }
+
+    fn box_clone(&self) -> Box<dyn Allocator> {
+        Box::new(self.clone())
+    }
+}
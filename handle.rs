@@ -0,0 +1,80 @@
+use std::fmt;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// An opaque reference to a block, returned by `insert`.
+///
+/// Unlike a raw address, a handle stays valid when the block moves (e.g.
+/// during `compact()`): it is resolved to the block's current location on
+/// every use. It becomes stale once the block is deleted or renamed, even if
+/// a new block is later stored under the same ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Handle {
+    id: BlockId,
+    generation: u32, // Incremented every time the ID is deleted or renamed away
+}
+
+impl Handle {
+    /// Returns the ID of the block this handle refers to.
+    pub fn id(&self) -> BlockId {
+        self.id
+    }
+}
+
+impl fmt::Display for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}#{}", self.id, self.generation)
+    }
+}
+
+impl MemoryManager {
+    /// Returns a handle to the block currently stored under `id`, if any.
+    pub fn handle(&self, id: BlockId) -> Option<Handle> {
+        if !self.exists(id) {
+            return None;
+        }
+        Some(Handle { id, generation: self.generation(id) })
+    }
+
+    /// Returns `true` if `handle` still refers to a live block.
+    pub fn is_valid(&self, handle: Handle) -> bool {
+        self.exists(handle.id) && self.generation(handle.id) == handle.generation
+    }
+
+    /// Returns the block's current start address, or `None` if the handle
+    /// is stale. Inline blocks have no address and also return `None`.
+    pub fn resolve(&self, handle: Handle) -> Option<usize> {
+        let block = self.allocations.get(&self.live(handle)?)?;
+        block.inline.is_none().then_some(block.start)
+    }
+
+    /// Reads the block `handle` refers to. See `read`.
+    pub fn read_handle(&self, handle: Handle) -> Option<Vec<u8>> {
+        self.read(self.live(handle)?)
+    }
+
+    /// Updates the block `handle` refers to. See `update`.
+    pub fn update_handle(&mut self, handle: Handle, data: Vec<u8>) -> Option<()> {
+        self.update(self.live(handle)?, data)
+    }
+
+    /// Deletes the block `handle` refers to, making the handle stale. See `delete`.
+    pub fn delete_handle(&mut self, handle: Handle) -> Option<()> {
+        self.delete(self.live(handle)?)
+    }
+
+    /// Returns the ID behind `handle` if it is still valid.
+    fn live(&self, handle: Handle) -> Option<BlockId> {
+        self.is_valid(handle).then_some(handle.id)
+    }
+
+    fn generation(&self, id: BlockId) -> u32 {
+        self.generations.get(&id).copied().unwrap_or(0)
+    }
+
+    /// Invalidates every handle to `id`. Called when the ID stops naming its block.
+    pub(crate) fn retire_handles(&mut self, id: BlockId) {
+        let generation = self.generations.entry(id).or_insert(0);
+        *generation = generation.wrapping_add(1);
+    }
+}
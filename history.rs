@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// A recorded mutation, with enough data to apply it in either direction.
+#[derive(Clone, Debug)]
+pub(crate) enum HistoryEntry {
+    Inserted { id: BlockId, data: Vec<u8> },
+    Updated { id: BlockId, before: Vec<u8>, after: Vec<u8> },
+    Deleted { id: BlockId, data: Vec<u8> },
+    Renamed { from: BlockId, to: BlockId },
+    Split { id: BlockId, offset: usize, first: BlockId, second: BlockId },
+    Merged { first: BlockId, second: BlockId, into: BlockId, offset: usize },
+}
+
+/// Undo and redo stacks for a `MemoryManager`.
+pub(crate) struct History {
+    undo: VecDeque<HistoryEntry>,
+    redo: Vec<HistoryEntry>,
+    pub(crate) depth: usize, // Maximum number of undoable operations kept
+    paused: bool,            // Set while undo/redo re-applies operations
+}
+
+impl History {
+    pub(crate) fn new(depth: usize) -> Self {
+        Self { undo: VecDeque::new(), redo: Vec::new(), depth, paused: false }
+    }
+
+    /// Returns `true` if new operations should be recorded.
+    pub(crate) fn recording(&self) -> bool {
+        self.depth > 0 && !self.paused
+    }
+
+    /// Records a new operation, discarding the oldest one past the depth
+    /// limit. Any redoable operations are forgotten.
+    pub(crate) fn record(&mut self, entry: HistoryEntry) {
+        if !self.recording() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push_back(entry);
+        while self.undo.len() > self.depth {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Forgets all recorded operations.
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+impl MemoryManager {
+    /// Reverts the most recent insert, update, delete, copy, rename, split, or merge.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if an operation was undone.
+    /// - `None` if there is nothing to undo, or the inverse operation failed
+    ///   (e.g. there is no longer room to restore a deleted block).
+    ///
+    /// # Behavior
+    ///
+    /// Undoing a delete restores the data under the same ID, though not
+    /// necessarily at the same address. History is off by default, since it
+    /// keeps a copy of the data of every recorded operation; turn it on with
+    /// `set_history_depth`.
+    pub fn undo(&mut self) -> Option<()> {
+        let entry = self.history.undo.pop_back()?;
+        let inverse = match &entry {
+            HistoryEntry::Inserted { id, data } => HistoryEntry::Deleted { id: *id, data: data.clone() },
+            HistoryEntry::Updated { id, before, after } => {
+                HistoryEntry::Updated { id: *id, before: after.clone(), after: before.clone() }
+            }
+            HistoryEntry::Deleted { id, data } => HistoryEntry::Inserted { id: *id, data: data.clone() },
+            HistoryEntry::Renamed { from, to } => HistoryEntry::Renamed { from: *to, to: *from },
+            HistoryEntry::Split { id, offset, first, second } => {
+                HistoryEntry::Merged { first: *first, second: *second, into: *id, offset: *offset }
+            }
+            HistoryEntry::Merged { first, second, into, offset } => {
+                HistoryEntry::Split { id: *into, offset: *offset, first: *first, second: *second }
+            }
+        };
+
+        if self.replay_entry(&inverse).is_some() {
+            self.history.redo.push(entry);
+            Some(())
+        } else {
+            self.history.undo.push_back(entry);
+            None
+        }
+    }
+
+    /// Re-applies the most recently undone operation.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if an operation was redone.
+    /// - `None` if there is nothing to redo or it can no longer be applied.
+    pub fn redo(&mut self) -> Option<()> {
+        let entry = self.history.redo.pop()?;
+        if self.replay_entry(&entry).is_some() {
+            self.history.undo.push_back(entry);
+            Some(())
+        } else {
+            self.history.redo.push(entry);
+            None
+        }
+    }
+
+    /// Sets how many operations `undo()` can revert. Zero, the default,
+    /// disables history.
+    ///
+    /// Shrinking the depth discards the oldest recorded operations.
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history.depth = depth;
+        while self.history.undo.len() > depth {
+            self.history.undo.pop_front();
+        }
+        if depth == 0 {
+            self.history.clear();
+        }
+    }
+
+    /// Applies a history entry in its forward direction without recording it.
+    fn replay_entry(&mut self, entry: &HistoryEntry) -> Option<()> {
+        self.history.paused = true;
+        let result = match entry {
+            HistoryEntry::Inserted { id, data } => self.insert(*id, data.clone()).map(|_| ()),
+            HistoryEntry::Updated { id, after, .. } => self.update(*id, after.clone()),
+            HistoryEntry::Deleted { id, .. } => self.delete(*id),
+            HistoryEntry::Renamed { from, to } => self.rename(*from, *to),
+            HistoryEntry::Split { id, offset, first, second } => self.split_into(*id, *offset, *first, *second),
+            HistoryEntry::Merged { first, second, into, .. } => self.merge_into(*first, *second, *into),
+        };
+        self.history.paused = false;
+        result
+    }
+}
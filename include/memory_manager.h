@@ -0,0 +1,106 @@
+#ifndef MEMORY_MANAGER_H
+#define MEMORY_MANAGER_H
+
+/* Generated by cbindgen from ffi.rs. Do not edit by hand. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+typedef struct MemoryManager MemoryManager;
+
+#if !defined(MM_WIDE_IDS)
+/**
+ * The type of block IDs: 32 bits wide, or 64 bits with the `wide-ids` feature.
+ */
+typedef uint32_t BlockId;
+#endif
+
+#if defined(MM_WIDE_IDS)
+/**
+ * The type of block IDs: 32 bits wide, or 64 bits with the `wide-ids` feature.
+ */
+typedef uint64_t BlockId;
+#endif
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a new manager. Release it with `mm_free`.
+ */
+MemoryManager *mm_new(void);
+
+/**
+ * Releases a manager created by `mm_new`. Passing NULL does nothing.
+ *
+ * # Safety
+ *
+ * `mm` must be NULL or a pointer returned by `mm_new` that has not been freed.
+ */
+void mm_free(MemoryManager *mm);
+
+/**
+ * Inserts `len` bytes from `data` under `id`.
+ *
+ * Returns 0 on success, or -1 if the ID exists, there is not enough space,
+ * or `mm` is NULL.
+ *
+ * # Safety
+ *
+ * `mm` must come from `mm_new`, and `data` must point to `len` readable
+ * bytes (it may be NULL when `len` is 0).
+ */
+int32_t mm_insert(MemoryManager *mm, BlockId id, const uint8_t *data, size_t len);
+
+/**
+ * Reads the data stored under `id` into `out`.
+ *
+ * Returns the length of the data, or -1 if the ID does not exist, the data
+ * is corrupted, or `mm` is NULL. At most `capacity` bytes are copied, so
+ * call with `out` NULL and `capacity` 0 first to learn the length.
+ *
+ * # Safety
+ *
+ * `mm` must come from `mm_new`, and `out` must point to `capacity` writable
+ * bytes (it may be NULL when `capacity` is 0).
+ */
+ptrdiff_t mm_read(const MemoryManager *mm, BlockId id, uint8_t *out, size_t capacity);
+
+/**
+ * Replaces the data stored under `id` with `len` bytes from `data`.
+ *
+ * Returns 0 on success, or -1 if the ID does not exist, the data does not
+ * fit in the block, or `mm` is NULL.
+ *
+ * # Safety
+ *
+ * As for `mm_insert`.
+ */
+int32_t mm_update(MemoryManager *mm, BlockId id, const uint8_t *data, size_t len);
+
+/**
+ * Deletes the data stored under `id`.
+ *
+ * Returns 0 on success, or -1 if the ID does not exist or `mm` is NULL.
+ *
+ * # Safety
+ *
+ * `mm` must come from `mm_new`.
+ */
+int32_t mm_delete(MemoryManager *mm, BlockId id);
+
+/**
+ * Prints the contents of memory to standard output, in the format of `MemoryManager::dump`.
+ *
+ * # Safety
+ *
+ * `mm` must be NULL or come from `mm_new`.
+ */
+void mm_dump(const MemoryManager *mm);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* MEMORY_MANAGER_H */
@@ -1,9 +1,26 @@
+use log::{info, warn};
 
-use crate::memory_manager::MemoryManager;
+use crate::error::MemoryError;
+use crate::handle::Handle;
+use crate::memory_manager::{BlockId, MemoryManager};
 
-pub fn insert(manager: &mut MemoryManager, id: u16, data: Vec<u8>) {
+/// Inserts `data` under `id` and logs the outcome.
+///
+/// # Returns
+///
+/// - `Ok(handle)` for the new block, logged at info level.
+/// - `Err(reason)` if the insert failed, logged as a warning.
+pub fn insert(manager: &mut MemoryManager, id: BlockId, data: Vec<u8>) -> Result<Handle, MemoryError> {
+    let requested = data.len();
     match manager.insert(id, data) {
-        Some(_) => println!("Data inserted with ID {}", id),
-        None => println!("Failed to insert data with ID {}", id),
+        Some(handle) => {
+            info!("Data inserted with ID {}", id);
+            Ok(handle)
+        }
+        None => {
+            let reason = manager.insert_failure(id, requested);
+            warn!("Failed to insert data with ID {}: {}", id, reason);
+            Err(reason)
+        }
     }
 }
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use crate::checksum::crc32;
+use crate::memory_manager::MemoryManager;
+
+impl MemoryManager {
+    /// Checks the internal consistency of the allocation tables.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if every invariant holds.
+    /// - `Err(message)` describing the first violation found.
+    ///
+    /// # Invariants
+    ///
+    /// - Every block lies inside memory, and so do its guard bytes.
+    /// - No two blocks (ID-keyed or content-addressed) overlap, and no block
+    ///   overlaps a free region. Deduplicated blocks may share a region
+    ///   exactly, and the region's reference count matches how many do.
+    /// - Free regions are sorted, non-empty, and fully coalesced, and together
+    ///   with the blocks (with any guard bytes, rounded out to whole allocator
+    ///   units) they account for every byte of memory.
+    /// - Stored data fits in its block, and inline blocks use no memory.
+    /// - Every block's checksum matches its stored bytes.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let content = self.content.iter().map(|(hash, entry)| (format!("content {:016x}", hash), &entry.block));
+        let blocks = self.allocations.iter().map(|(id, block)| (format!("ID {}", id), block)).chain(content);
+
+        // Blocks occupy whole allocator units, including any slack after the data
+        let unit = self.allocator.granularity();
+        let mut regions = Vec::new();
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for (name, block) in blocks {
+            if let Some(inline) = &block.inline {
+                if block.size != 0 || inline.len() != block.stored_size {
+                    return Err(format!("{} is inline but has size {} and stored size {}", name, block.size, block.stored_size));
+                }
+            } else {
+                if block.start + block.size > self.memory.len() {
+                    return Err(format!("{} ends at {}, past the end of memory", name, block.start + block.size));
+                }
+                if block.stored_size > block.size {
+                    return Err(format!("{} stores {} bytes in {} bytes", name, block.stored_size, block.size));
+                }
+                let guard = self.guard_len(block.size);
+                if block.start < guard || block.start + block.size + guard > self.memory.len() {
+                    return Err(format!("{} has no room for its guard bytes", name));
+                }
+                if block.size > 0 {
+                    *shared.entry(block.start).or_insert(0) += 1;
+                    let (start, size) = self.extent(block.start, block.size);
+                    let end = (start + size).next_multiple_of(unit).min(self.memory.len());
+                    regions.push((start / unit * unit, end, name.clone(), Some(block.start)));
+                }
+            }
+
+            if crc32(self.stored(block)) != block.checksum {
+                return Err(format!("{} does not match its checksum", name));
+            }
+        }
+
+        let free = self.allocator.regions();
+        for pair in free.windows(2) {
+            if pair[0].0 + pair[0].1 >= pair[1].0 {
+                return Err(format!("free regions at {} and {} are out of order or not coalesced", pair[0].0, pair[1].0));
+            }
+        }
+        for &(start, size) in &free {
+            if size == 0 || start + size > self.memory.len() {
+                return Err(format!("free region at {} of {} bytes is empty or past the end of memory", start, size));
+            }
+            regions.push((start, start + size, format!("free region at {}", start), None));
+        }
+
+        shared.retain(|_, count| *count > 1);
+        if shared != self.refcounts {
+            return Err(format!("shared regions {:?} do not match reference counts {:?}", shared, self.refcounts));
+        }
+
+        regions.sort();
+        // Blocks sharing a region count once. Reference counts are keyed by the
+        // block's own start, not the unit-rounded one the region begins at.
+        regions.dedup_by(|next, first| {
+            (next.0, next.1) == (first.0, first.1)
+                && next.3 == first.3
+                && first.3.is_some_and(|start| self.refcounts.contains_key(&start))
+        });
+        for pair in regions.windows(2) {
+            if pair[0].1 > pair[1].0 {
+                return Err(format!("{} overlaps {}", pair[0].2, pair[1].2));
+            }
+        }
+
+        let accounted: usize = regions.iter().map(|(start, end, _, _)| end - start).sum();
+        if accounted != self.memory.len() {
+            return Err(format!("blocks and free regions cover {} of {} bytes", accounted, self.memory.len()));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::memory_manager::MemoryManager;
+use crate::operation::Operation;
+
+impl MemoryManager {
+    /// Creates a new `MemoryManager` that journals every change to `path`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(manager)` with an empty memory block.
+    /// - `Err(e)` if the journal cannot be opened for appending.
+    ///
+    /// # Behavior
+    ///
+    /// Every insert, update, and delete is appended to the journal, one line
+    /// in the `Operation` text format, and synced to disk before it is
+    /// applied. Transactions are journaled as `BEGIN`, `COMMIT`, and
+    /// `ROLLBACK` lines. Use `recover` to rebuild the state after a crash.
+    pub fn with_journal<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut manager = Self::new();
+        manager.journal = Some(open_journal(path.as_ref())?);
+        Ok(manager)
+    }
+
+    /// Rebuilds a manager by replaying the journal at `path`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(manager)` holding the recovered state, still journaling to `path`.
+    /// - `Err(e)` if the journal cannot be read or contains a malformed line.
+    ///
+    /// # Behavior
+    ///
+    /// Operations are re-applied in order. A transaction without a matching
+    /// `COMMIT` (e.g. interrupted by a crash) is rolled back.
+    pub fn recover<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let contents = if path.exists() { fs::read_to_string(path)? } else { String::new() };
+
+        let mut manager = Self::new();
+        for line in contents.lines() {
+            match line.trim() {
+                "BEGIN" => {
+                    manager.begin_transaction();
+                }
+                "COMMIT" => {
+                    manager.commit();
+                }
+                "ROLLBACK" => {
+                    manager.rollback();
+                }
+                _ => {
+                    if let Some(op) = Operation::parse(line)? {
+                        manager.apply_one(&op);
+                    }
+                }
+            }
+        }
+        let interrupted = manager.rollback().is_some();
+
+        // Close the interrupted transaction so later recoveries keep what follows
+        manager.journal = Some(open_journal(path)?);
+        if interrupted && !manager.journal_append("ROLLBACK") {
+            return Err(io::Error::other("failed to close interrupted transaction in journal"));
+        }
+        Ok(manager)
+    }
+
+    /// Journals the operation built by `op`, if journaling is enabled.
+    ///
+    /// The operation is only built when there is a journal or a trace to write it to.
+    /// Returns the same as `journal_append`.
+    pub(crate) fn journal_operation<F: FnOnce() -> Operation>(&mut self, op: F) -> bool {
+        if self.journal.is_none() && self.trace.is_none() {
+            return true;
+        }
+        let line = op().to_string();
+        self.journal_append(&line)
+    }
+
+    /// Appends one line to the journal, if there is one, and syncs it.
+    /// Once it is durable, the line is also added to the trace, if one is
+    /// being recorded.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the line is durable (or there is no journal), `false` if
+    /// writing failed and the change must not be applied. The error is kept
+    /// for `take_io_error()`.
+    pub(crate) fn journal_append(&mut self, line: &str) -> bool {
+        if let Some(journal) = self.journal.as_mut() {
+            if let Err(e) = writeln!(journal, "{}", line).and_then(|_| journal.sync_data()) {
+                self.io_error = Some(io::Error::new(e.kind(), format!("failed to write journal: {}", e)));
+                return false;
+            }
+        }
+        self.trace_line(line);
+        true
+    }
+}
+
+pub(crate) fn open_journal(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
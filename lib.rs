@@ -1,5 +1,84 @@
-pub mod memory_manager;
-pub mod insert;
-pub mod delete;
-pub mod read;
-pub mod update;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+// The parts that need neither `std` nor a heap
+pub mod free_list;
+pub mod pool;
+
+/// Declares modules that need `std`, which is everything built on
+/// `MemoryManager`.
+macro_rules! with_std {
+    ($($module:item)*) => {
+        $(
+            #[cfg(feature = "std")]
+            $module
+        )*
+    };
+}
+
+with_std! {
+    pub mod access_counts;
+    #[cfg(feature = "access-times")]
+    pub mod access_times;
+    pub mod allocator;
+    #[cfg(feature = "tokio")]
+    pub mod async_manager;
+    pub mod audit;
+    pub mod bitmap;
+    pub mod builder;
+    mod buffer;
+    pub mod canary;
+    pub mod checksum;
+    pub mod cluster;
+    pub mod compaction;
+    pub mod compression;
+    pub mod content;
+    pub mod copy;
+    pub mod dedup;
+    pub mod memory_manager;
+    pub mod memory_map;
+    pub mod insert;
+    pub mod invariants;
+    pub mod delete;
+    pub mod read;
+    pub mod update;
+    pub mod dump;
+    pub mod operation;
+    pub mod paging;
+    pub mod pressure;
+    #[cfg(feature = "python")]
+    pub mod python;
+    pub mod replay;
+    pub mod snapshot;
+    pub mod encryption;
+    pub mod erase;
+    pub mod error;
+    pub mod events;
+    pub mod export;
+    pub mod ffi;
+    pub mod fill;
+    pub mod handle;
+    pub mod history;
+    pub mod journal;
+    pub mod metrics;
+    pub mod seal;
+    pub mod segmentation;
+    pub mod selftest;
+    #[cfg(feature = "serde")]
+    pub mod serialize;
+    pub mod server;
+    pub mod shared;
+    pub mod split;
+    pub mod stats;
+    pub mod subscriptions;
+    pub mod swap;
+    pub mod tags;
+    pub mod throttle;
+    pub mod trace;
+    pub mod transaction;
+    pub mod versions;
+    pub mod virtual_memory;
+    pub mod watch;
+    pub mod workload;
+    #[cfg(feature = "wasm")]
+    pub mod wasm;
+}
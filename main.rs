@@ -1,24 +1,216 @@
-mod insert;
-mod memory_manager;
-mod dump;
+use project2::memory_manager::MemoryManager;
+use project2::insert::insert;
+use project2::dump::dump;
+use project2::operation::Operation;
+use project2::replay::{replay, verify_replay};
+use project2::selftest::run_selftest;
+use project2::server::serve;
+use project2::shared::SharedMemoryManager;
 
-use memory_manager::MemoryManager;
-use insert::insert;
-use dump::dump;
+use std::env;
+use std::fs;
+use std::process;
+
+use env_logger::Env;
 
 fn main() {
+    // Library messages go through the `log` facade; show info and above unless RUST_LOG says otherwise
+    env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
+
+    let args: Vec<String> = env::args().collect();
+
+    match args.get(1).map(String::as_str) {
+        Some("verify-replay") => verify_replay_command(&args[2..]),
+        Some("import") => import_command(&args[2..]),
+        // `export json` and `export csv` export a replayed log; any other file gets the import format
+        Some("export") if !matches!(args.get(2).map(String::as_str), Some("json" | "csv")) => {
+            export_tsv_command(&args[2..])
+        }
+        Some("export") => export_command(&args[2..]),
+        Some("selftest") => selftest_command(),
+        Some("serve") => serve_command(&args[2..]),
+        _ => demo(),
+    }
+}
+
+/// Replays an operation log and checks the final state hash.
+///
+/// Usage: `verify-replay <log> [expected-hash]`
+///
+/// Without an expected hash, the replayed hash is printed so it can be
+/// recorded next to the log.
+fn verify_replay_command(args: &[String]) {
+    let Some(log) = args.first() else {
+        eprintln!("Usage: verify-replay <log> [expected-hash]");
+        process::exit(2);
+    };
+
+    let expected = match args.get(1).map(|hash| u64::from_str_radix(hash.trim_start_matches("0x"), 16)) {
+        Some(Ok(hash)) => Some(hash),
+        Some(Err(_)) => {
+            eprintln!("Expected hash must be hexadecimal");
+            process::exit(2);
+        }
+        None => None,
+    };
+
+    match verify_replay(log, expected.unwrap_or(0)) {
+        Ok(report) if expected.is_none() => {
+            println!("Replayed {} operations, state hash {:016x}", report.operations, report.actual);
+        }
+        Ok(report) if report.matches() => {
+            println!("PASS: {} operations, state hash {:016x}", report.operations, report.actual);
+        }
+        Ok(report) => {
+            println!("FAIL: expected state hash {:016x}, got {:016x}", report.expected, report.actual);
+            process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Failed to replay {}: {}", log, e);
+            process::exit(2);
+        }
+    }
+}
+
+/// Replays an operation log and exports the resulting state.
+///
+/// Usage: `export <json|csv> <log> [out]`
+///
+/// The export is written to `out`, or printed if no output file is given.
+fn export_command(args: &[String]) {
+    let (Some(format), Some(log)) = (args.first(), args.get(1)) else {
+        eprintln!("Usage: export <json|csv> <log> [out]");
+        process::exit(2);
+    };
+
+    let ops = match fs::read_to_string(log).and_then(|contents| Operation::parse_all(&contents)) {
+        Ok(ops) => ops,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", log, e);
+            process::exit(2);
+        }
+    };
+    let manager = replay(&ops);
+    let export = match format.to_ascii_lowercase().as_str() {
+        "json" => manager.export_json(),
+        "csv" => manager.export_csv(),
+        _ => {
+            eprintln!("Unknown export format {}, expected json or csv", format);
+            process::exit(2);
+        }
+    };
+
+    match args.get(2) {
+        Some(out) => {
+            if let Err(e) = fs::write(out, export) {
+                eprintln!("Failed to write {}: {}", out, e);
+                process::exit(2);
+            }
+        }
+        None => print!("{}", export),
+    }
+}
+
+/// Imports an `id<TAB>base64` file into a memory-mapped manager.
+///
+/// Usage: `import <file> [memory]`, with the manager kept in `memory.bin`
+/// unless another memory file is given. Nothing is imported if any line is
+/// malformed or any ID is already taken.
+fn import_command(args: &[String]) {
+    let Some(file) = args.first() else {
+        eprintln!("Usage: import <file> [memory]");
+        process::exit(2);
+    };
+    let mut manager = open_memory(args.get(1));
+    match manager.import(file) {
+        Ok(count) => println!("Imported {} blocks from {}", count, file),
+        Err(e) => {
+            eprintln!("Failed to import {}: {}", file, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Exports the blocks of a memory-mapped manager as an `id<TAB>base64` file.
+///
+/// Usage: `export <file> [memory]`, reading the manager from `memory.bin`
+/// unless another memory file is given.
+fn export_tsv_command(args: &[String]) {
+    let Some(file) = args.first() else {
+        eprintln!("Usage: export <file> [memory]");
+        process::exit(2);
+    };
+    let manager = open_memory(args.get(1));
+    match manager.export(file) {
+        Ok(count) => println!("Exported {} blocks to {}", count, file),
+        Err(e) => {
+            eprintln!("Failed to export {}: {}", file, e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Opens the memory-mapped manager the `import` and `export` commands share.
+fn open_memory(path: Option<&String>) -> MemoryManager {
+    let path = path.map_or("memory.bin", String::as_str);
+    match MemoryManager::open_mmap(path, 65535) {
+        Ok(manager) => manager,
+        Err(e) => {
+            eprintln!("Failed to open {}: {}", path, e);
+            process::exit(2);
+        }
+    }
+}
+
+/// Runs the built-in self-test and reports each stage.
+///
+/// Exits with status 1 if any stage failed.
+fn selftest_command() {
+    let results = run_selftest();
+    for stage in &results {
+        match &stage.result {
+            Ok(()) => println!("PASS {}", stage.stage),
+            Err(e) => println!("FAIL {}: {}", stage.stage, e),
+        }
+    }
+
+    let failed = results.iter().filter(|stage| !stage.passed()).count();
+    if failed > 0 {
+        println!("{} of {} stages failed", failed, results.len());
+        process::exit(1);
+    }
+    println!("All {} stages passed", results.len());
+}
+
+/// Serves a fresh manager over TCP until the process is killed.
+///
+/// Usage: `serve [address]`, listening on 127.0.0.1:7878 by default.
+/// Clients can `UNDO` the last 64 changes, e.g. a mistaken `DELETE`.
+fn serve_command(args: &[String]) {
+    let addr = args.first().map_or("127.0.0.1:7878", String::as_str);
+    let mut manager = MemoryManager::new();
+    manager.set_history_depth(64);
+    println!("Listening on {}", addr);
+    if let Err(e) = serve(addr, SharedMemoryManager::new(manager)) {
+        eprintln!("Failed to serve on {}: {}", addr, e);
+        process::exit(2);
+    }
+}
+
+/// Runs a short walkthrough of insert, read, update, delete, dump, and the memory map.
+fn demo() {
     // Create a new instance of the memory manager
     let mut manager = MemoryManager::new();
 
     // Insert data with ID 1
     let id1 = 1;
     let data1 = vec![72, 101, 108, 108, 111]; // "Hello"
-    insert(&mut manager, id1, data1);
+    let _ = insert(&mut manager, id1, data1); // Failures are logged
 
     // Insert data with ID 2
     let id2 = 2;
     let data2 = vec![82, 117, 115, 116]; // "Rust"
-    insert(&mut manager, id2, data2);
+    let _ = insert(&mut manager, id2, data2);
 
     // Read and print data with ID 1
     match manager.read(id1) {
@@ -40,5 +232,8 @@ fn main() {
     manager.delete(id1);
 
     // Dump memory content
-    dump(&manager);  // This will print out the memory contents
+    dump(&manager);  // This will log the memory contents
+
+    // Show where the remaining blocks sit in memory
+    print!("{}", manager.visualize());
 }
@@ -1,9 +1,110 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+
+use log::info;
+
+use crate::access_counts::{Counts, Tiers};
+#[cfg(feature = "access-times")]
+use crate::access_times::AccessTimes;
+use crate::buffer::{Buffer, MappedFile};
+use crate::checksum::crc32;
+use crate::compression::{rle_compress, rle_decompress};
+use crate::content::ContentEntry;
+use crate::encryption::{nonce_from_counter, xchacha20_xor};
+use crate::erase::{erase, ErasePolicy};
+use crate::events::Listener;
+use crate::allocator::Allocator;
+use crate::audit::Trail;
+use crate::free_list::FreeList;
+use crate::handle::Handle;
+use crate::history::{History, HistoryEntry};
+use crate::metrics::Counters;
+use crate::operation::{decode_hex, encode_hex, Operation};
+use crate::snapshot::Snapshot;
+use crate::pressure::PressureWatch;
+use crate::subscriptions::{Change, Subscription};
+use crate::swap::Swap;
+use crate::throttle::Backpressure;
+use crate::trace::Trace;
+use crate::watch::Watch;
+
+// Defined next to `Pool`, so builds without `std` have it too
+pub use crate::pool::BlockId;
+
+/// The order in which `dump_sorted_by` lists blocks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Id,      // Ascending ID, the same order as `dump()`
+    Address, // Ascending start address, inline blocks last
+    Size,    // Ascending size as shown in the dump
+}
+
+/// Where `insert_placed` puts a new block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Placement {
+    Aligned(usize), // First free region that fits, at a multiple of this alignment
+    At(usize),      // Exactly this start index
+}
+
+/// A single allocation tracked by the `MemoryManager`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Block {
+    pub(crate) start: usize, // Start index in memory
+    pub(crate) size: usize, // Number of bytes reserved
+    pub(crate) checksum: u32, // CRC32 of the bytes in memory
+    pub(crate) raw_size: usize, // Length of the data as the caller provided it
+    pub(crate) stored_size: usize, // Length of the data as written to memory
+    pub(crate) compressed: bool, // Whether the stored bytes are run-length encoded
+    pub(crate) nonce: Option<u64>, // Nonce counter the stored bytes were encrypted with, if any
+    pub(crate) inline: Option<Vec<u8>>, // Stored bytes kept in the table instead of memory
+}
 
 pub struct MemoryManager {
-    memory: [u8; 65535], // The memory block, 65535 bytes in size
-    allocations: HashMap<u16, (usize, usize)>, // id -> (start index, size)
-    next_free: usize, // The next available free index in memory
+    pub(crate) memory: Buffer, // The memory block, 65535 bytes in size unless file-backed
+    pub(crate) allocations: BTreeMap<BlockId, Block>, // id -> allocated block, ordered by ID
+    pub(crate) allocator: Box<dyn Allocator>, // Tracks which bytes of memory are not reserved by any block
+    pub(crate) index_path: Option<PathBuf>, // Where the allocation table is saved when file-backed
+    pub(crate) compression: bool, // Whether new data is compressed before being stored
+    pub(crate) subscribers: Vec<(Subscription, Sender<Change>)>, // Listeners for changes
+    pub(crate) encryption_key: Option<[u8; 32]>, // Key used to encrypt block contents at rest
+    pub(crate) next_nonce: u64, // Counter handing out a fresh nonce for every encryption
+    pub(crate) transaction: Option<Snapshot>, // State to return to if the open transaction rolls back
+    pub(crate) inline_threshold: usize, // Values up to this many stored bytes are kept inline (0 = off)
+    pub(crate) history: History, // Undo/redo stacks
+    pub(crate) content: HashMap<u64, ContentEntry>, // content hash -> content-addressed block
+    pub(crate) journal: Option<File>, // Write-ahead log of changes, if journaling
+    pub(crate) sequence: u64, // Number of changes applied so far
+    pub(crate) snapshots: BTreeMap<String, Snapshot>, // Named snapshots saved with `snapshot()`
+    pub(crate) soft_limit: usize, // Used bytes above which backpressure applies
+    pub(crate) backpressure: Backpressure, // What inserts do above the soft limit
+    pub(crate) seals: HashMap<BlockId, Vec<u8>>, // id -> detached signature over the block's data
+    pub(crate) tags: HashMap<BlockId, BTreeMap<String, String>>, // id -> key/value tags
+    pub(crate) versions: HashMap<BlockId, VecDeque<Vec<u8>>>, // id -> previous data, newest first
+    pub(crate) version_depth: usize, // Previous versions kept per block (0 = off)
+    #[cfg(feature = "access-times")]
+    pub(crate) access_times: HashMap<BlockId, AccessTimes>, // id -> last-read and last-write times
+    pub(crate) erase_policy: ErasePolicy, // How deleted blocks are erased
+    pub(crate) io_error: Option<io::Error>, // Most recent journal or index write failure
+    pub(crate) listeners: Vec<Listener>, // Callbacks registered with `on_event`
+    pub(crate) pinned: HashSet<BlockId>, // IDs that compaction must not move
+    pub(crate) generations: HashMap<BlockId, u32>, // id -> generation of handles that are currently valid
+    pub(crate) dedup: bool, // Whether new blocks share regions holding identical bytes
+    pub(crate) refcounts: HashMap<usize, usize>, // start -> number of blocks sharing the region, if more than one
+    pub(crate) counters: Counters, // Operation counts reported by `metrics()`
+    pub(crate) canaries: bool, // Whether blocks in memory are surrounded by guard bytes
+    pub(crate) audit: Option<BTreeMap<BlockId, Trail>>, // id -> audit trail, if auditing
+    pub(crate) actor: Option<String>, // Who the audit trail credits with changes
+    pub(crate) swap: Option<Swap>, // Swap file for cold blocks, if swapping
+    pub(crate) access_counts: HashMap<BlockId, Counts>, // id -> read and write counts
+    pub(crate) tiers: Option<Tiers>, // Simulated access latency, if on
+    pub(crate) watches: Vec<Watch>, // Watchpoints set with `watch` or `watch_range`
+    pub(crate) trace: Option<Trace>, // Trace being recorded, if any
+    pub(crate) pressure_watch: PressureWatch, // Pressure levels and `on_threshold` callbacks
 }
 
 impl MemoryManager {
@@ -11,9 +112,225 @@ impl MemoryManager {
     /// The memory block size is set to 65535 bytes.
     pub fn new() -> Self {
         Self {
-            memory: [0; 65535],
-            allocations: HashMap::new(),
-            next_free: 0,
+            memory: Buffer::heap(65535),
+            allocations: BTreeMap::new(),
+            allocator: Box::new(FreeList::new(65535)),
+            index_path: None,
+            compression: false,
+            subscribers: Vec::new(),
+            encryption_key: None,
+            next_nonce: 0,
+            transaction: None,
+            inline_threshold: 0,
+            history: History::new(0),
+            content: HashMap::new(),
+            journal: None,
+            sequence: 0,
+            snapshots: BTreeMap::new(),
+            soft_limit: usize::MAX,
+            backpressure: Backpressure::Off,
+            seals: HashMap::new(),
+            tags: HashMap::new(),
+            versions: HashMap::new(),
+            version_depth: 0,
+            #[cfg(feature = "access-times")]
+            access_times: HashMap::new(),
+            erase_policy: ErasePolicy::Zero,
+            io_error: None,
+            listeners: Vec::new(),
+            pinned: HashSet::new(),
+            generations: HashMap::new(),
+            dedup: false,
+            refcounts: HashMap::new(),
+            counters: Counters::default(),
+            canaries: false,
+            audit: None,
+            actor: None,
+            swap: None,
+            access_counts: HashMap::new(),
+            tiers: None,
+            watches: Vec::new(),
+            trace: None,
+            pressure_watch: PressureWatch::default(),
+        }
+    }
+
+    /// Creates a new `MemoryManager` with a heap memory block of `capacity` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_allocator(Box::new(FreeList::new(capacity)))
+    }
+
+    /// Creates a new `MemoryManager` that places blocks with `allocator`.
+    ///
+    /// # Parameters:
+    /// - `allocator`: A fresh allocator, e.g. `BitmapAllocator::new(4096, 16)`.
+    ///   The heap memory block is `allocator.capacity()` bytes.
+    ///
+    /// # Behavior:
+    /// - Lets the same workload run against different allocation strategies
+    ///   to compare their speed and fragmentation.
+    pub fn with_allocator(allocator: Box<dyn Allocator>) -> Self {
+        Self { memory: Buffer::heap(allocator.capacity()), allocator, ..Self::new() }
+    }
+
+    /// Creates a new `MemoryManager` whose memory block is `pool`.
+    ///
+    /// # Behavior:
+    /// - Blocks are stored in the caller's buffer, e.g. a `static` array at a
+    ///   known address, instead of a heap allocation. It is cleared first.
+    /// - The allocation table still lives on the heap. Without `std`, use
+    ///   `pool::Pool`, whose table has a fixed size and which places blocks
+    ///   with the same `FreeList`.
+    pub fn from_slice(pool: &'static mut [u8]) -> Self {
+        pool.fill(0);
+        Self { allocator: Box::new(FreeList::new(pool.len())), memory: Buffer::Static(pool), ..Self::new() }
+    }
+
+    /// Creates a new `MemoryManager` that compresses data before storing it.
+    ///
+    /// # Behavior:
+    /// - Inserted and updated data is run-length encoded when that makes it smaller.
+    /// - Reads transparently decompress, so callers always see the original bytes.
+    /// - `stats()` reports both the raw and the stored sizes.
+    pub fn with_compression() -> Self {
+        Self { compression: true, ..Self::new() }
+    }
+
+    /// Creates a new `MemoryManager` that encrypts block contents at rest.
+    ///
+    /// # Parameters:
+    /// - `key`: The 256-bit XChaCha20 key used for every block.
+    ///
+    /// # Behavior:
+    /// - Inserted and updated data is encrypted with a fresh nonce before it is written.
+    /// - Reads transparently decrypt, while `dump()` shows the ciphertext.
+    ///   Use `dump_decrypted()` to see the plaintext.
+    pub fn with_encryption(key: [u8; 32]) -> Self {
+        Self { encryption_key: Some(key), ..Self::new() }
+    }
+
+    /// Creates a new `MemoryManager` that keeps small values out of memory.
+    ///
+    /// # Parameters:
+    /// - `threshold`: Values whose stored size is at most this many bytes are
+    ///   kept directly in the allocation table.
+    ///
+    /// # Behavior:
+    /// - Inline values consume no memory, so workloads dominated by tiny
+    ///   records fit many more of them.
+    /// - Reads, updates, and deletes work the same as for any other block.
+    pub fn with_inline_threshold(threshold: usize) -> Self {
+        Self { inline_threshold: threshold, ..Self::new() }
+    }
+
+    /// Opens a `MemoryManager` whose memory block is a memory-mapped file.
+    ///
+    /// # Parameters:
+    /// - `path`: The file holding the memory contents. Created if missing.
+    /// - `size`: The size of the memory block in bytes.
+    ///
+    /// # Returns:
+    /// - `Ok(manager)` with the previous state restored if the file was used before.
+    /// - `Err(e)` if the file cannot be created, resized, or mapped.
+    ///
+    /// # Behavior:
+    /// - Every write to memory goes straight to the mapped file.
+    /// - The allocation table is kept next to it in `<path>.idx` and rewritten
+    ///   after each insert, update, and delete, so the whole state survives restarts.
+    pub fn open_mmap<P: AsRef<Path>>(path: P, size: usize) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        if file.metadata()?.len() < size as u64 {
+            file.set_len(size as u64)?;
+        }
+
+        let mut index_path = path.as_os_str().to_owned();
+        index_path.push(".idx");
+        let index_path = PathBuf::from(index_path);
+
+        let mut manager = Self {
+            memory: Buffer::Mapped(MappedFile::map(file, size)?),
+            allocator: Box::new(FreeList::new(size)),
+            index_path: Some(index_path.clone()),
+            ..Self::new()
+        };
+
+        if index_path.exists() {
+            manager.load_index(&fs::read_to_string(&index_path)?)?;
+        }
+
+        Ok(manager)
+    }
+
+    /// Restores the allocation table from the
+    /// `id start size checksum raw_size stored_size compressed nonce inline` lines of an index file.
+    /// The nonce field is `-` for unencrypted blocks, and the inline field is
+    /// either `-` or the hex-encoded inline bytes.
+    fn load_index(&mut self, contents: &str) -> io::Result<()> {
+        let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("bad index line: {}", line));
+
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 9 {
+                return Err(invalid(line));
+            }
+            let id: BlockId = fields[0].parse().map_err(|_| invalid(line))?;
+            let start: usize = fields[1].parse().map_err(|_| invalid(line))?;
+            let size: usize = fields[2].parse().map_err(|_| invalid(line))?;
+            let checksum: u32 = fields[3].parse().map_err(|_| invalid(line))?;
+            let raw_size: usize = fields[4].parse().map_err(|_| invalid(line))?;
+            let stored_size: usize = fields[5].parse().map_err(|_| invalid(line))?;
+            let compressed = fields[6] == "1";
+            let nonce: Option<u64> = match fields[7] {
+                "-" => None,
+                value => Some(value.parse().map_err(|_| invalid(line))?),
+            };
+            let inline = match fields[8] {
+                "-" => None,
+                hex => Some(decode_hex(hex).ok_or_else(|| invalid(line))?),
+            };
+            if start + size > self.memory.len() {
+                return Err(invalid(line));
+            }
+
+            self.allocations
+                .insert(id, Block { start, size, checksum, raw_size, stored_size, compressed, nonce, inline });
+            if let Some(nonce) = nonce {
+                self.next_nonce = self.next_nonce.max(nonce + 1);
+            }
+        }
+
+        let used = self.allocations.values().filter(|block| block.inline.is_none()).map(|block| (block.start, block.size));
+        self.allocator.rebuild(used.collect());
+        self.rebuild_refcounts();
+        Ok(())
+    }
+
+    /// Rewrites the index file for a file-backed manager. Does nothing otherwise.
+    ///
+    /// A failure is kept for `take_io_error()` rather than failing the change,
+    /// since memory itself has already been updated.
+    pub(crate) fn save_index(&mut self) {
+        if let Some(path) = &self.index_path {
+            let mut contents = String::new();
+            for (id, block) in &self.allocations {
+                contents.push_str(&format!(
+                    "{} {} {} {} {} {} {} {} {}\n",
+                    id,
+                    block.start,
+                    block.size,
+                    block.checksum,
+                    block.raw_size,
+                    block.stored_size,
+                    block.compressed as u8,
+                    block.nonce.map_or("-".to_string(), |nonce| nonce.to_string()),
+                    block.inline.as_deref().map_or("-".to_string(), encode_hex)
+                ));
+            }
+            if let Err(e) = fs::write(path, contents) {
+                let message = format!("failed to save allocation index {}: {}", path.display(), e);
+                self.io_error = Some(io::Error::new(e.kind(), message));
+            }
         }
     }
 
@@ -24,35 +341,158 @@ impl MemoryManager {
     /// - `data`: The byte vector to insert into memory.
     ///
     /// # Returns:
-    /// - `Some(())` if the data is inserted successfully.
+    /// - `Some(handle)` if the data is inserted successfully. The handle keeps
+    ///   referring to the block even if it is moved.
     /// - `None` if the ID already exists or there is not enough space.
     ///
     /// # Behavior:
     /// - Checks for duplicate IDs.
     /// - Ensures there is enough space in memory.
     /// - Copies the data into the memory and tracks the allocation.
-    pub fn insert(&mut self, id: u16, data: Vec<u8>) -> Option<()> {
-        let size = data.len();
+    /// - Values at or below the inline threshold are kept in the table instead.
+    /// - Above the soft limit, the backpressure policy may delay or shrink the insert.
+    pub fn insert(&mut self, id: BlockId, data: Vec<u8>) -> Option<Handle> {
+        self.insert_aligned(id, data, 1)
+    }
+
+    /// Inserts data into memory with a given `id`, starting at a multiple of `alignment`.
+    ///
+    /// # Parameters:
+    /// - `id`: Unique identifier for the data.
+    /// - `data`: The byte vector to insert into memory.
+    /// - `alignment`: Required alignment of the start index; must be a power of two.
+    ///
+    /// # Returns:
+    /// - `Some(handle)` if the data is inserted successfully. The handle keeps
+    ///   referring to the block even if it is moved.
+    /// - `None` if the alignment is invalid, the ID already exists, or there is
+    ///   no free region large enough once padding is added.
+    ///
+    /// # Behavior:
+    /// - Works like `insert`, except the block is placed at an aligned address.
+    /// - Bytes skipped to reach that address remain free and can hold later blocks.
+    /// - Aligned values are never kept inline, since inline values have no address.
+    pub fn insert_aligned(&mut self, id: BlockId, data: Vec<u8>, alignment: usize) -> Option<Handle> {
+        if !alignment.is_power_of_two() {
+            return None;
+        }
+        self.insert_placed(id, data, Placement::Aligned(alignment))
+    }
+
+    /// Inserts data with a given `id` at the explicit address `start`.
+    ///
+    /// # Parameters:
+    /// - `id`: Unique identifier for the data.
+    /// - `start`: The index in memory where the block must begin.
+    /// - `data`: The byte vector to insert into memory.
+    ///
+    /// # Returns:
+    /// - `Some(handle)` if the data is inserted successfully. The handle keeps
+    ///   referring to the block even if it is moved.
+    /// - `None` if the ID already exists, or the block would overlap another
+    ///   block or run past the end of memory.
+    ///
+    /// # Behavior:
+    /// - Lets tests build a specific memory layout, e.g. a fragmentation scenario.
+    /// - Placed values are never kept inline.
+    pub fn insert_at(&mut self, id: BlockId, start: usize, data: Vec<u8>) -> Option<Handle> {
+        self.insert_placed(id, data, Placement::At(start))
+    }
+
+    /// Claims `size` bytes for `id` without providing data yet.
+    ///
+    /// # Returns:
+    /// - `Some(handle)` if the region was reserved.
+    /// - `None` if the ID already exists or there is not enough space.
+    ///
+    /// # Behavior:
+    /// - The block reads as `size` zero bytes until it is updated.
+    /// - It is stored as-is, never compressed, encrypted, or kept inline, so
+    ///   `update` can fill it with up to `size` bytes in place.
+    pub fn reserve(&mut self, id: BlockId, size: usize) -> Option<Handle> {
+        if self.exists(id) || !self.journal_operation(|| Operation::Reserve { id, size }) {
+            return None;
+        }
+
+        let start = self.allocate_swapping(size, 1)?;
+        self.memory[start..start + size].fill(0);
+        let checksum = crc32(&self.memory[start..start + size]);
+        let block = Block { start, size, checksum, raw_size: size, stored_size: size, compressed: false, nonce: None, inline: None };
+
+        self.allocations.insert(id, block);
+        self.save_index();
+        if self.history.recording() {
+            self.history.record(HistoryEntry::Inserted { id, data: vec![0; size] });
+        }
+        self.sequence += 1;
+        self.notify(Change::Inserted { id, size });
+
+        self.handle(id)
+    }
 
+    /// Stores a new block, placing it in memory according to `placement`.
+    fn insert_placed(&mut self, id: BlockId, data: Vec<u8>, placement: Placement) -> Option<Handle> {
         // Reject duplicate ID
-        if self.allocations.contains_key(&id) {
+        if self.exists(id) {
             return None;
         }
 
-        // Not enough space
-        if self.next_free + size > self.memory.len() {
+        // Whether there is room is only known once placed; replay refuses the same inserts
+        let data = self.throttle(data);
+        let logged = |data: &Vec<u8>| match placement {
+            Placement::At(start) => Operation::InsertAt { id, start, data: data.clone() },
+            Placement::Aligned(1) => Operation::Insert { id, data: data.clone() },
+            Placement::Aligned(alignment) => Operation::InsertAligned { id, alignment, data: data.clone() },
+        };
+        if !self.journal_operation(|| logged(&data)) {
             return None;
         }
 
-        // Copy data into memory
-        let start = self.next_free;
-        self.memory[start..start + size].copy_from_slice(&data);
+        let recorded = self.history.recording().then(|| data.clone());
+        let raw_size = data.len();
+        let (data, compressed, nonce) = self.encode(data);
+        let size = data.len();
+
+        let checksum = crc32(&data);
+        let block = if placement == Placement::Aligned(1) && self.inline_threshold > 0 && size <= self.inline_threshold {
+            // Small values live in the allocation table and take no memory
+            Block { start: 0, size: 0, checksum, raw_size, stored_size: size, compressed, nonce, inline: Some(data) }
+        } else {
+            let duplicate = match placement {
+                Placement::Aligned(alignment) if self.dedup && nonce.is_none() => {
+                    self.find_duplicate(&data, checksum, compressed, alignment)
+                }
+                _ => None,
+            };
+            let start = if let Some(start) = duplicate {
+                // Identical bytes are already stored; share them
+                self.share_region(start);
+                start
+            } else {
+                // Not enough space, or the requested address is taken
+                let start = match placement {
+                    Placement::Aligned(alignment) => self.allocate_swapping(size, alignment)?,
+                    Placement::At(start) => self.allocate_at(start, size)?,
+                };
+
+                // Copy data into memory
+                self.memory[start..start + size].copy_from_slice(&data);
+                start
+            };
+
+            Block { start, size, checksum, raw_size, stored_size: size, compressed, nonce, inline: None }
+        };
 
         // Track allocation
-        self.allocations.insert(id, (start, size));
-        self.next_free += size;
+        self.allocations.insert(id, block);
+        self.save_index();
+        if let Some(data) = recorded {
+            self.history.record(HistoryEntry::Inserted { id, data });
+        }
+        self.sequence += 1;
+        self.notify(Change::Inserted { id, size: raw_size });
 
-        Some(())
+        self.handle(id)
     }
 
     /// Reads data from memory using the provided `id`.
@@ -62,13 +502,74 @@ impl MemoryManager {
     ///
     /// # Returns:
     /// - `Some(data)` if the data is found.
-    /// - `None` if no data is found for the given ID.
-    pub fn read(&self, id: u16) -> Option<Vec<u8>> {
-        if let Some(&(start, size)) = self.allocations.get(&id) {
-            Some(self.memory[start..start + size].to_vec())
-        } else {
-            None
+    /// - `None` if no data is found for the given ID, or if the stored bytes
+    ///   no longer match the block's checksum.
+    ///
+    /// # Behavior:
+    /// - Metrics, access counts and times, and audit trails record the read
+    ///   in atomics, so it needs no exclusive access.
+    pub fn read(&self, id: BlockId) -> Option<Vec<u8>> {
+        self.read_cow(id).map(Cow::into_owned)
+    }
+
+    /// Reads data like `read`, copying it only when it has to be decoded.
+    ///
+    /// # Returns:
+    /// - `Some(Cow::Borrowed(data))` pointing into memory for plain blocks.
+    /// - `Some(Cow::Owned(data))` for compressed or encrypted blocks, which
+    ///   must be decoded into a new buffer.
+    /// - `None` in the same cases as `read`.
+    pub fn read_cow(&self, id: BlockId) -> Option<Cow<'_, [u8]>> {
+        let Some(data) = self.load(id) else {
+            self.trace_read(id, None);
+            return None;
+        };
+        #[cfg(feature = "access-times")]
+        self.record_read(id);
+        self.counters.read();
+        self.audit_read(id);
+        self.swap_touch(id);
+        self.count_read(id);
+        self.watch_read(id, &data);
+        self.trace_read(id, Some(&data));
+        Some(data)
+    }
+
+    /// Borrows the data stored under `id` without copying it.
+    ///
+    /// # Returns:
+    /// - `Some(data)` pointing into memory, as `read` would return it.
+    /// - `None` if `read` would return `None`, or if the block is compressed
+    ///   or encrypted and so has no plain bytes to borrow. Use `read_cow` to
+    ///   handle every block.
+    pub fn read_ref(&self, id: BlockId) -> Option<&[u8]> {
+        let block = self.allocations.get(&id)?;
+        if block.compressed || block.nonce.is_some() {
+            return None;
+        }
+        match self.read_cow(id)? {
+            Cow::Borrowed(data) => Some(data),
+            Cow::Owned(_) => None,
+        }
+    }
+
+    /// Reads block `id` like `read`, without counting it as an access.
+    /// Used when the manager itself needs a block's data, e.g. for undo history.
+    pub(crate) fn peek(&self, id: BlockId) -> Option<Vec<u8>> {
+        self.load(id).map(Cow::into_owned)
+    }
+
+    fn load(&self, id: BlockId) -> Option<Cow<'_, [u8]>> {
+        let Some(block) = self.allocations.get(&id) else {
+            return self.read_swapped(id).map(Cow::Owned);
+        };
+
+        // Refuse to hand out corrupted data
+        if crc32(self.stored(block)) != block.checksum || !self.guards_intact(id) {
+            return None;
         }
+
+        self.decode(block)
     }
 
     /// Updates the data for the specified ID.
@@ -84,21 +585,75 @@ impl MemoryManager {
     /// # Behavior:
     /// - Updates the data in memory and ensures the data does not grow larger than the existing allocation.
     /// - If the new data is smaller, it pads the remaining space with zeros.
-    pub fn update(&mut self, id: u16, data: Vec<u8>) -> Option<()> {
-        if let Some(&(start, size)) = self.allocations.get(&id) {
-            if data.len() > size {
-                return None; // Don't allow expanding
+    /// - With compression enabled, the size limit applies to the compressed data.
+    /// - Inline values may grow up to the inline threshold.
+    /// - With versioning on, the previous data is kept for `read_version`.
+    /// - With canaries on, fails if the guards around the block were overwritten.
+    /// - A swapped-out block is moved back into memory first.
+    pub fn update(&mut self, id: BlockId, data: Vec<u8>) -> Option<()> {
+        if self.is_swapped(id) {
+            self.swap_in(id)?;
+        }
+        if !self.journal_operation(|| Operation::Update { id, data: data.clone() }) {
+            return None;
+        }
+
+        let before = if self.history.recording() || self.version_depth > 0 { self.peek(id) } else { None };
+        let after = (before.is_some() && self.history.recording()).then(|| data.clone());
+
+        if let Some(block) = self.allocations.get(&id) {
+            let (size, is_inline) = (block.size, block.inline.is_some());
+            if !self.guards_intact(id) {
+                return None; // Something already wrote past this block
             }
+            let raw_size = data.len();
+            let (data, compressed, nonce) = self.encode(data);
+            let stored_size = data.len();
+
+            let (checksum, inline) = if is_inline {
+                if stored_size > self.inline_threshold {
+                    return None; // Inline values cannot outgrow the table
+                }
+                (crc32(&data), Some(data))
+            } else {
+                if stored_size > size {
+                    return None; // Don't allow expanding
+                }
+
+                // Other blocks sharing the region keep the old bytes
+                self.unshare(id)?;
+                let start = self.allocations.get(&id)?.start;
+
+                // Overwrite the existing allocation
+                self.memory[start..start + stored_size].copy_from_slice(&data);
+
+                // If data is shorter, pad the rest with zeros
+                if stored_size < size {
+                    for i in start + stored_size..start + size {
+                        self.memory[i] = 0;
+                    }
+                }
 
-            // Overwrite the existing allocation
-            self.memory[start..start + data.len()].copy_from_slice(&data);
+                (crc32(&self.memory[start..start + size]), None)
+            };
 
-            // If data is shorter, pad the rest with zeros
-            if data.len() < size {
-                for i in start + data.len()..start + size {
-                    self.memory[i] = 0;
+            if let Some(block) = self.allocations.get_mut(&id) {
+                block.checksum = checksum;
+                block.raw_size = raw_size;
+                block.stored_size = stored_size;
+                block.compressed = compressed;
+                block.nonce = nonce;
+                block.inline = inline;
+            }
+            self.save_index();
+            if let Some(before) = before {
+                if let Some(after) = after {
+                    self.history.record(HistoryEntry::Updated { id, before: before.clone(), after });
                 }
+                self.keep_version(id, before);
             }
+            self.sequence += 1;
+            self.notify(Change::Updated { id, size: raw_size });
 
             Some(())
         } else {
@@ -106,6 +661,123 @@ impl MemoryManager {
         }
     }
 
+    /// Converts caller data into the bytes that will be written to memory.
+    ///
+    /// Returns the stored bytes, whether they are compressed, and the nonce
+    /// they were encrypted with. Compression is only kept when it actually
+    /// makes the data smaller; encryption is applied after compression.
+    pub(crate) fn encode(&mut self, mut data: Vec<u8>) -> (Vec<u8>, bool, Option<u64>) {
+        let mut compressed = false;
+        if self.compression {
+            let packed = rle_compress(&data);
+            if packed.len() < data.len() {
+                data = packed;
+                compressed = true;
+            }
+        }
+
+        let nonce = self.encryption_key.map(|key| {
+            let nonce = self.next_nonce;
+            self.next_nonce += 1;
+            xchacha20_xor(&key, &nonce_from_counter(nonce), &mut data);
+            nonce
+        });
+
+        (data, compressed, nonce)
+    }
+
+    /// Recovers the caller's data from a block's stored bytes.
+    ///
+    /// Plain blocks return their whole region, including any zero padding
+    /// left by a shrinking update. Compressed or encrypted blocks return
+    /// exactly the data last written. Returns `None` if the block is
+    /// encrypted and the manager has no key.
+    /// Plain blocks are borrowed; the others are decoded into a new buffer.
+    pub(crate) fn decode<'a>(&'a self, block: &'a Block) -> Option<Cow<'a, [u8]>> {
+        self.decode_region(block, self.stored(block))
+    }
+
+    /// Recovers the caller's data from `region`, the stored bytes of `block`,
+    /// wherever they were read from. See `decode`.
+    pub(crate) fn decode_region<'a>(&self, block: &Block, region: &'a [u8]) -> Option<Cow<'a, [u8]>> {
+        if !block.compressed && block.nonce.is_none() {
+            return Some(Cow::Borrowed(region));
+        }
+
+        let mut data = region[..block.stored_size].to_vec();
+        if let Some(nonce) = block.nonce {
+            xchacha20_xor(self.encryption_key.as_ref()?, &nonce_from_counter(nonce), &mut data);
+        }
+        if block.compressed {
+            data = rle_decompress(&data);
+        }
+        Some(Cow::Owned(data))
+    }
+
+    /// Returns how many bytes of memory are still available for new blocks.
+    ///
+    /// Free space may be split into several regions, so a single block of
+    /// this size is not guaranteed to fit.
+    pub fn free_bytes(&self) -> usize {
+        self.allocator.free_bytes()
+    }
+
+    /// Reserves `size` bytes of memory.
+    ///
+    /// Returns the start index of the reserved region, or `None` if there is
+    /// not enough space left.
+    pub(crate) fn allocate(&mut self, size: usize) -> Option<usize> {
+        self.allocate_aligned(size, 1)
+    }
+
+    /// Reserves `size` bytes of memory starting at a multiple of `alignment`.
+    ///
+    /// Any padding skipped to reach the aligned start stays free.
+    /// With canaries on, the guards around the block are reserved and
+    /// filled too, and alignment is limited to `CANARY_LEN`.
+    pub(crate) fn allocate_aligned(&mut self, size: usize, alignment: usize) -> Option<usize> {
+        let guard = self.guard_len(size);
+        let start = if guard > 0 && alignment > guard {
+            None
+        } else {
+            self.allocator.allocate(size + 2 * guard, alignment).map(|region| region + guard)
+        };
+        self.counters.allocation(size, start.is_some());
+        if let Some(start) = start {
+            self.write_guards(start, size);
+        }
+        start
+    }
+
+    /// Reserves exactly `start..start + size`, if all of it is free memory.
+    /// With canaries on, the guards on either side must be free as well.
+    pub(crate) fn allocate_at(&mut self, start: usize, size: usize) -> Option<usize> {
+        let guard = self.guard_len(size);
+        let reserved = start >= guard
+            && start.checked_add(size + guard).is_some_and(|end| end <= self.memory.len())
+            && self.allocator.reserve(start - guard, size + 2 * guard);
+        self.counters.allocation(size, reserved);
+        if reserved {
+            self.write_guards(start, size);
+        }
+        reserved.then_some(start)
+    }
+
+    /// Returns the memory reserved for the block at `start..start + size`,
+    /// guards included, to the allocator.
+    pub(crate) fn free_region(&mut self, start: usize, size: usize) {
+        let (start, size) = self.extent(start, size);
+        self.allocator.free(start, size);
+    }
+
+    /// Returns the bytes a block currently holds, wherever they are stored.
+    pub(crate) fn stored<'a>(&'a self, block: &'a Block) -> &'a [u8] {
+        match &block.inline {
+            Some(bytes) => bytes,
+            None => &self.memory[block.start..block.start + block.size],
+        }
+    }
+
     /// Deletes the data associated with the specified ID.
     /// 
     /// # Parameters:
@@ -116,12 +788,47 @@ impl MemoryManager {
     /// - `None` if the ID does not exist.
     ///
     /// # Behavior:
-    /// - Removes the data from memory and clears the memory block.
-    pub fn delete(&mut self, id: u16) -> Option<()> {
-        if let Some((start, size)) = self.allocations.remove(&id) {
-            for i in start..start + size {
-                self.memory[i] = 0;
+    /// - Removes the data from memory and erases the memory block according
+    ///   to the manager's erase policy (zeroing it by default).
+    pub fn delete(&mut self, id: BlockId) -> Option<()> {
+        self.secure_delete(id, self.erase_policy)
+    }
+
+    /// Deletes the data associated with the specified ID, erasing it with `policy`.
+    ///
+    /// # Parameters:
+    /// - `id`: The unique identifier of the data to delete.
+    /// - `policy`: How to erase the block's bytes, overriding the manager's policy.
+    ///
+    /// # Returns:
+    /// - `Some(())` if the deletion is successful.
+    /// - `None` if the ID does not exist.
+    pub fn secure_delete(&mut self, id: BlockId, policy: ErasePolicy) -> Option<()> {
+        if !self.journal_operation(|| Operation::Delete { id }) {
+            return None;
+        }
+
+        let recorded = if self.history.recording() { self.peek(id) } else { None };
+        // A smashed guard is reported, but the block is still deleted
+        self.guards_intact(id);
+
+        // A swapped-out block comes back with its bytes inline, to be erased like one
+        if let Some(Block { start, size, inline, .. }) = self.allocations.remove(&id).or_else(|| self.take_swapped(id)) {
+            match inline {
+                Some(mut bytes) => erase(&mut bytes, policy),
+                None => self.release_region(start, size, policy),
+            }
+            self.seals.remove(&id);
+            self.pinned.remove(&id);
+            self.retire_handles(id);
+            self.save_index();
+            if let Some(data) = recorded {
+                self.history.record(HistoryEntry::Deleted { id, data });
             }
+            self.sequence += 1;
+            self.notify(Change::Deleted { id });
+            self.tags.remove(&id);
+            self.versions.remove(&id);
             Some(())
         } else {
             None
@@ -131,15 +838,114 @@ impl MemoryManager {
     /// Dumps the contents of memory along with the allocated data.
     /// 
     /// # Behavior:
-    /// - Prints out the allocated memory blocks with their IDs, start positions, sizes, and the stored data.
+    /// - Logs the allocated memory blocks with their IDs, start positions, sizes, and the stored data
+    ///   at info level. Use `dump_text()` to get the text instead.
+    /// - Blocks are listed in ascending ID order, so the output is the same on every run.
+    /// - Encrypted blocks are shown as ciphertext.
+    /// - Tagged blocks list their tags.
+    /// - The header shows the sequence number of the state being dumped.
     pub fn dump(&self) {
-        println!("--- Memory Dump ---");
-        for (id, (start, size)) in &self.allocations {
-            let data = &self.memory[*start..*start + *size];
-            let display_data = String::from_utf8_lossy(data);
-            println!("ID {} -> Start: {}, Size: {}, Data: {}", id, start, size, display_data);
+        info!("{}", self.render_dump(false).trim_end());
+    }
+
+    /// Dumps memory like `dump()`, but shows encrypted and compressed blocks
+    /// as the plaintext a read would return.
+    pub fn dump_decrypted(&self) {
+        info!("{}", self.render_dump(true).trim_end());
+    }
+
+    /// Dumps memory like `dump()`, with blocks listed in the order given by `key`.
+    ///
+    /// # Behavior:
+    /// - Ties are broken by ID, so the output is the same on every run.
+    pub fn dump_sorted_by(&self, key: SortKey) {
+        info!("{}", self.render_sorted(key).trim_end());
+    }
+
+    /// Returns the text `dump()` logs, for callers that display it themselves
+    /// (e.g. a browser, where there is no standard output).
+    pub fn dump_text(&self) -> String {
+        self.render_dump(false)
+    }
+
+    /// Returns the text `dump_sorted_by()` logs.
+    pub fn dump_text_sorted_by(&self, key: SortKey) -> String {
+        self.render_sorted(key)
+    }
+
+    /// Returns the number of changes applied so far.
+    ///
+    /// Every successful insert, update, and delete (and every rollback or
+    /// restore) bumps it, so two dumps with the same sequence number show
+    /// the same state.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Returns and clears the most recent failure to write the journal or
+    /// the allocation index, if any.
+    ///
+    /// Such failures are not printed; a change whose journal write failed is
+    /// not applied, while a failed index write leaves memory updated but the
+    /// index on disk stale until the next successful save.
+    pub fn take_io_error(&mut self) -> Option<io::Error> {
+        self.io_error.take()
+    }
+
+    /// Builds the text printed by `dump()` and `dump_decrypted()`.
+    pub(crate) fn render_dump(&self, decode: bool) -> String {
+        let ids: Vec<BlockId> = self.allocations.keys().copied().collect();
+        self.render_blocks(&ids, decode)
+    }
+
+    /// Builds the text printed by `dump_sorted_by()`.
+    pub(crate) fn render_sorted(&self, key: SortKey) -> String {
+        let mut ids: Vec<BlockId> = self.allocations.keys().copied().collect();
+        match key {
+            SortKey::Id => {}
+            SortKey::Address => ids.sort_by_key(|id| {
+                let block = &self.allocations[id];
+                (block.inline.is_some(), block.start)
+            }),
+            SortKey::Size => ids.sort_by_key(|id| {
+                let block = &self.allocations[id];
+                if block.inline.is_some() { block.stored_size } else { block.size }
+            }),
         }
-        println!("--------------------");
+        self.render_blocks(&ids, false)
+    }
+
+    /// Renders the dump text for the blocks in `ids`, in that order.
+    pub(crate) fn render_blocks(&self, ids: &[BlockId], decode: bool) -> String {
+        let mut out = format!("--- Memory Dump (seq {}) ---\n", self.sequence);
+        for (id, block) in ids.iter().filter_map(|id| Some((id, self.allocations.get(id)?))) {
+            let data = if decode {
+                self.decode(block).unwrap_or_default()
+            } else {
+                Cow::Borrowed(self.stored(block))
+            };
+            let display_data = String::from_utf8_lossy(&data);
+            let tags = self.format_tags(*id);
+            if block.inline.is_some() {
+                out.push_str(&format!(
+                    "ID {} -> Inline, Size: {}, Data: {}{}\n",
+                    id, block.stored_size, display_data, tags
+                ));
+            } else {
+                out.push_str(&format!(
+                    "ID {} -> Start: {}, Size: {}, Data: {}{}\n",
+                    id, block.start, block.size, display_data, tags
+                ));
+            }
+        }
+        out.push_str("--------------------\n");
+        out
+    }
+}
+
+impl Default for MemoryManager {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -230,4 +1036,2252 @@ mod tests {
 
         assert_eq!(manager.read(id), None); // Data should be deleted
     }
+
+    /// Tests that a memory-mapped manager keeps its state across reopening.
+    ///
+    /// - Opens a manager backed by a temporary file and inserts data.
+    /// - Drops the manager and opens the same file again.
+    /// - Expects the data to be readable under the same ID.
+    #[test]
+    fn test_open_mmap_persists() {
+        let path = std::env::temp_dir().join(format!("mm_test_{}.bin", std::process::id()));
+        let data = vec![72, 101, 108, 108, 111]; // "Hello"
+
+        {
+            let mut manager = MemoryManager::open_mmap(&path, 4096).unwrap();
+            manager.insert(1, data.clone());
+        }
+
+        let manager = MemoryManager::open_mmap(&path, 4096).unwrap();
+        assert_eq!(manager.read(1), Some(data));
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("bin.idx"));
+    }
+
+    /// Tests that checksums catch corrupted blocks.
+    ///
+    /// - Inserts two blocks and flips a bit inside the second one.
+    /// - Expects reading the corrupted block to fail while the other still reads.
+    /// - Expects `verify_all` to report only the corrupted ID.
+    #[test]
+    fn test_checksum_detects_bit_flip() {
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![72, 101, 108, 108, 111]); // "Hello"
+        manager.insert(2, vec![82, 117, 115, 116]); // "Rust"
+        assert!(manager.verify_all().is_empty());
+
+        manager.flip_bit(6, 3); // Inside block 2, which starts at 5
+
+        assert_eq!(manager.read(2), None);
+        assert!(manager.read(1).is_some());
+        assert_eq!(manager.verify_all(), vec![2]);
+    }
+
+    /// Tests that compressed blocks round-trip and take less space.
+    ///
+    /// - Inserts a highly repetitive payload into a compressing manager.
+    /// - Expects reads to return the original bytes.
+    /// - Expects `stats` to show fewer stored bytes than raw bytes.
+    #[test]
+    fn test_compression_round_trip() {
+        let mut manager = MemoryManager::with_compression();
+        let data = vec![7; 1000];
+
+        manager.insert(1, data.clone());
+        assert_eq!(manager.read(1), Some(data));
+
+        let stats = manager.stats();
+        assert_eq!(stats.raw_bytes, 1000);
+        assert!(stats.stored_bytes < stats.raw_bytes);
+    }
+
+    /// Tests that subscribers only receive the changes they asked for.
+    ///
+    /// - Subscribes to ID 2 and to everything.
+    /// - Inserts two blocks, then updates and deletes ID 2.
+    /// - Expects the ID subscription to see only ID 2's changes, in order.
+    #[test]
+    fn test_subscriptions() {
+        let mut manager = MemoryManager::new();
+        let only_two = manager.subscribe(Subscription::Id(2));
+        let everything = manager.subscribe(Subscription::All);
+
+        manager.insert(1, vec![1, 2, 3]);
+        manager.insert(2, vec![4, 5]);
+        manager.update(2, vec![6]);
+        manager.delete(2);
+
+        let seen: Vec<Change> = only_two.try_iter().collect();
+        assert_eq!(
+            seen,
+            vec![
+                Change::Inserted { id: 2, size: 2 },
+                Change::Updated { id: 2, size: 1 },
+                Change::Deleted { id: 2 },
+            ]
+        );
+        assert_eq!(everything.try_iter().count(), 4);
+    }
+
+    /// Tests that encrypted blocks are unreadable in memory but read back intact.
+    ///
+    /// - Inserts data into an encrypting manager.
+    /// - Expects the raw memory to differ from the plaintext.
+    /// - Expects reads, including after an update, to return the plaintext.
+    #[test]
+    fn test_encryption_round_trip() {
+        let mut manager = MemoryManager::with_encryption([42; 32]);
+        let data = vec![72, 101, 108, 108, 111]; // "Hello"
+
+        manager.insert(1, data.clone());
+        assert_ne!(&manager.memory[0..5], &data[..]);
+        assert_eq!(manager.read(1), Some(data));
+
+        manager.update(1, vec![82, 117, 115, 116]); // "Rust"
+        assert_eq!(manager.read(1), Some(vec![82, 117, 115, 116]));
+    }
+
+    /// Tests that replaying the same operations reproduces the same state hash.
+    ///
+    /// - Parses a small operation log and replays it twice.
+    /// - Expects identical hashes, and a different hash after an extra operation.
+    #[test]
+    fn test_replay_state_hash() {
+        let ops = crate::operation::Operation::parse_all("INSERT 1 48656c6c6f\nINSERT 2 52757374\nDELETE 1\n").unwrap();
+
+        let first = crate::replay::replay(&ops);
+        let mut second = crate::replay::replay(&ops);
+        assert_eq!(first.state_hash(), second.state_hash());
+
+        second.insert(3, vec![1]);
+        assert_ne!(first.state_hash(), second.state_hash());
+    }
+
+    /// Tests that a rolled-back transaction leaves no trace and a committed one sticks.
+    ///
+    /// - Inserts a block, then opens a transaction that inserts, updates, and deletes.
+    /// - Rolls back and expects the original state.
+    /// - Repeats the changes, commits, and expects them to remain.
+    #[test]
+    fn test_transaction_rollback_and_commit() {
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![72, 101, 108, 108, 111]); // "Hello"
+        let before = manager.state_hash();
+
+        manager.begin_transaction().unwrap();
+        assert_eq!(manager.begin_transaction(), None); // No nesting
+        manager.insert(2, vec![1, 2, 3]);
+        manager.update(1, vec![74]);
+        manager.delete(2);
+        manager.rollback().unwrap();
+
+        assert_eq!(manager.state_hash(), before);
+        assert_eq!(manager.read(1), Some(vec![72, 101, 108, 108, 111]));
+
+        manager.begin_transaction().unwrap();
+        manager.insert(2, vec![1, 2, 3]);
+        manager.commit().unwrap();
+
+        assert_eq!(manager.read(2), Some(vec![1, 2, 3]));
+        assert_eq!(manager.rollback(), None);
+    }
+
+    /// Tests that small values are stored inline without using memory.
+    ///
+    /// - Inserts a tiny and a larger value into a manager with an inline threshold.
+    /// - Expects only the larger value to consume memory.
+    /// - Expects both to read back, and the tiny one to update in place.
+    #[test]
+    fn test_inline_small_values() {
+        let mut manager = MemoryManager::with_inline_threshold(8);
+        manager.insert(1, vec![1, 2, 3]);
+        manager.insert(2, vec![9; 20]);
+
+        assert_eq!(manager.stats().used_bytes, 20);
+        assert_eq!(manager.read(1), Some(vec![1, 2, 3]));
+        assert_eq!(manager.read(2), Some(vec![9; 20]));
+
+        manager.update(1, vec![4, 5, 6, 7, 8]);
+        assert_eq!(manager.read(1), Some(vec![4, 5, 6, 7, 8]));
+        assert_eq!(manager.update(1, vec![0; 9]), None); // Over the threshold
+    }
+
+    /// Tests undoing and redoing a sequence of operations.
+    ///
+    /// - Inserts, updates, and deletes a block.
+    /// - Undoes all three and expects the manager to be empty again.
+    /// - Redoes the insert and update and expects the updated data.
+    /// - Expects nothing to be undoable without opting in to history.
+    #[test]
+    fn test_undo_redo() {
+        let mut manager = MemoryManager::new();
+        manager.set_history_depth(64);
+        manager.insert(1, vec![72, 101, 108, 108, 111]); // "Hello"
+        manager.update(1, vec![74, 101, 108, 108, 111]); // "Jello"
+        manager.delete(1);
+
+        manager.undo().unwrap();
+        assert_eq!(manager.read(1), Some(vec![74, 101, 108, 108, 111]));
+        manager.undo().unwrap();
+        assert_eq!(manager.read(1), Some(vec![72, 101, 108, 108, 111]));
+        manager.undo().unwrap();
+        assert_eq!(manager.read(1), None);
+        assert_eq!(manager.undo(), None);
+
+        manager.redo().unwrap();
+        manager.redo().unwrap();
+        assert_eq!(manager.read(1), Some(vec![74, 101, 108, 108, 111]));
+
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![1]).unwrap();
+        assert_eq!(manager.undo(), None);
+        assert_eq!(manager.read(1), Some(vec![1]));
+    }
+
+    /// Tests that identical content is stored once and reference counted.
+    ///
+    /// - Inserts the same payload twice by content.
+    /// - Expects the same hash both times and memory used only once.
+    /// - Expects the content to survive one delete and vanish after the second.
+    #[test]
+    fn test_content_addressing() {
+        let mut manager = MemoryManager::new();
+        let data = vec![72, 101, 108, 108, 111]; // "Hello"
+
+        let first = manager.insert_content(data.clone()).unwrap();
+        let second = manager.insert_content(data.clone()).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(manager.stats().used_bytes, 5);
+        assert_eq!(manager.read_content(first), Some(data));
+
+        assert_eq!(manager.delete_content(first), Some(1));
+        assert!(manager.read_content(first).is_some());
+        assert_eq!(manager.delete_content(first), Some(0));
+        assert_eq!(manager.read_content(first), None);
+    }
+
+    /// Tests that a journal rebuilds the same state, minus rolled-back work.
+    ///
+    /// - Journals a few operations, including an aligned insert, a compaction
+    ///   around a pinned block, content-addressed inserts and deletes, and a
+    ///   rolled-back and an unfinished transaction.
+    /// - Recovers from the journal into a fresh manager.
+    /// - Expects the recovered state to match the original's committed state.
+    #[test]
+    fn test_journal_recover() {
+        let path = std::env::temp_dir().join(format!("mm_journal_{}.log", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let (expected, hash) = {
+            let mut manager = MemoryManager::with_journal(&path).unwrap();
+            manager.insert(1, vec![72, 101, 108, 108, 111]); // "Hello"
+            manager.insert(2, vec![82, 117, 115, 116]); // "Rust"
+            manager.begin_transaction();
+            manager.delete(1);
+            manager.rollback();
+            manager.update(2, vec![74]);
+            manager.insert_aligned(4, vec![4; 3], 32).unwrap();
+            manager.insert(6, vec![6; 4]).unwrap();
+            manager.insert(7, vec![7; 4]).unwrap();
+            manager.pin(7).unwrap();
+            manager.delete(6).unwrap();
+            assert_eq!(manager.compact().moved, 1);
+            let hash = manager.insert_content(vec![8; 6]).unwrap();
+            manager.insert_content(vec![8; 6]).unwrap();
+            manager.delete_content(hash).unwrap();
+            let gone = manager.insert_content(vec![9; 2]).unwrap();
+            manager.delete_content(gone).unwrap();
+            let expected = (manager.state_hash(), hash);
+
+            manager.begin_transaction(); // Never committed
+            manager.delete(2);
+            expected
+        };
+
+        let mut recovered = MemoryManager::recover(&path).unwrap();
+        assert_eq!(recovered.state_hash(), expected);
+        assert_eq!(recovered.read(1), Some(vec![72, 101, 108, 108, 111]));
+        assert_eq!(recovered.read_content(hash), Some(vec![8; 6]));
+        assert_eq!(recovered.delete_content(hash), Some(0));
+
+        // Work after recovery must survive the next recovery
+        recovered.insert(3, vec![1]);
+        drop(recovered);
+        assert_eq!(MemoryManager::recover(&path).unwrap().read(3), Some(vec![1]));
+
+        let _ = fs::remove_file(&path);
+    }
+
+    /// Tests that dumps from a shared manager are consistent under concurrent writes.
+    ///
+    /// - Starts a writer thread that keeps inserting blocks.
+    /// - Takes dumps concurrently from the main thread.
+    /// - Expects each dump to list exactly as many blocks as its sequence number.
+    #[test]
+    fn test_shared_dump_is_consistent() {
+        let shared = crate::shared::SharedMemoryManager::new(MemoryManager::new());
+        let writer = {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                for id in 0..200 {
+                    shared.insert(id, vec![b'x'; 4]);
+                }
+            })
+        };
+
+        for _ in 0..50 {
+            let dump = shared.dump_snapshot();
+            let header = dump.lines().next().unwrap();
+            let seq: usize = header.trim_start_matches("--- Memory Dump (seq ").trim_end_matches(") ---").parse().unwrap();
+            assert_eq!(dump.lines().filter(|line| line.starts_with("ID ")).count(), seq);
+        }
+
+        writer.join().unwrap();
+        assert_eq!(shared.sequence(), 200);
+    }
+
+    /// Tests saving, listing, and restoring named snapshots.
+    ///
+    /// - Snapshots an initial state, then changes memory.
+    /// - Restores the snapshot and expects the initial state back.
+    /// - Expects the snapshot to remain listed and restorable.
+    #[test]
+    fn test_named_snapshots() {
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![72, 101, 108, 108, 111]); // "Hello"
+        manager.snapshot("before");
+        let before = manager.state_hash();
+
+        manager.delete(1);
+        manager.insert(2, vec![1, 2, 3]);
+        manager.snapshot("after");
+
+        manager.restore("before").unwrap();
+        assert_eq!(manager.state_hash(), before);
+        assert_eq!(manager.read(2), None);
+        assert_eq!(manager.list_snapshots(), vec!["after".to_string(), "before".to_string()]);
+        assert_eq!(manager.restore("missing"), None);
+
+        manager.restore("after").unwrap();
+        assert_eq!(manager.read(2), Some(vec![1, 2, 3]));
+    }
+
+    /// Tests copying and renaming blocks.
+    ///
+    /// - Copies a block and expects both IDs to hold the same data independently.
+    /// - Renames the copy and expects the old ID to be gone.
+    /// - Expects copying onto an existing ID to fail.
+    #[test]
+    fn test_copy_and_rename() {
+        let mut manager = MemoryManager::new();
+        manager.set_history_depth(64);
+        manager.insert(1, vec![72, 101, 108, 108, 111]); // "Hello"
+
+        manager.copy(1, 2).unwrap();
+        manager.update(2, vec![74]);
+        assert_eq!(manager.read(1), Some(vec![72, 101, 108, 108, 111]));
+        assert_eq!(manager.read(2), Some(vec![74, 0, 0, 0, 0]));
+
+        manager.rename(2, 3).unwrap();
+        assert_eq!(manager.read(2), None);
+        assert_eq!(manager.read(3), Some(vec![74, 0, 0, 0, 0]));
+
+        assert_eq!(manager.copy(1, 3), None);
+        manager.undo().unwrap(); // Undo the rename
+        assert_eq!(manager.read(2), Some(vec![74, 0, 0, 0, 0]));
+    }
+
+    /// Tests applying a batch of operations with per-operation results.
+    ///
+    /// - Applies inserts, a read, a failing duplicate insert, an update, and a delete.
+    /// - Expects one result per operation, with the failure reported in place.
+    #[test]
+    fn test_apply_batch() {
+        use crate::operation::{OpResult, Operation};
+
+        let mut manager = MemoryManager::new();
+        let results = manager.apply(&[
+            Operation::Insert { id: 1, data: vec![1, 2] },
+            Operation::Insert { id: 1, data: vec![3] },
+            Operation::Read { id: 1 },
+            Operation::Update { id: 1, data: vec![9] },
+            Operation::Delete { id: 1 },
+            Operation::Read { id: 1 },
+        ]);
+
+        assert_eq!(
+            results,
+            vec![
+                OpResult::Done,
+                OpResult::Failed,
+                OpResult::Data(vec![1, 2]),
+                OpResult::Done,
+                OpResult::Done,
+                OpResult::Failed,
+            ]
+        );
+    }
+
+    /// Tests that the shrink policy truncates inserts instead of failing them.
+    ///
+    /// - Fills most of memory, then sets a soft limit with `Backpressure::Shrink`.
+    /// - Inserts more data than fits.
+    /// - Expects the insert to succeed with the data truncated to the free space.
+    #[test]
+    fn test_backpressure_shrink() {
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![0; 65000]);
+        manager.set_soft_limit(60000, crate::throttle::Backpressure::Shrink);
+
+        manager.insert(2, vec![1; 1000]).unwrap();
+        assert_eq!(manager.read(2), Some(vec![1; 535]));
+        assert_eq!(manager.free_bytes(), 0);
+    }
+
+    /// Tests sealing a block and detecting later modification.
+    ///
+    /// - Seals a block with a toy keyed-hash signer.
+    /// - Expects verification to pass, then fail after an update.
+    /// - Expects a block deleted in a rolled-back transaction to come back sealed.
+    #[test]
+    fn test_seal_and_verify() {
+        use crate::seal::{SigningKey, VerifyingKey};
+
+        struct ToyKey(u8);
+        impl SigningKey for ToyKey {
+            fn sign(&self, message: &[u8]) -> Vec<u8> {
+                let mut keyed = vec![self.0];
+                keyed.extend_from_slice(message);
+                crate::checksum::fnv1a(&keyed).to_le_bytes().to_vec()
+            }
+        }
+        impl VerifyingKey for ToyKey {
+            fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+                self.sign(message) == signature
+            }
+        }
+
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![72, 101, 108, 108, 111]); // "Hello"
+        assert_eq!(manager.verify_seal(1, &ToyKey(7)), None);
+
+        manager.seal(1, &ToyKey(7)).unwrap();
+        assert_eq!(manager.verify_seal(1, &ToyKey(7)), Some(true));
+        assert_eq!(manager.verify_seal(1, &ToyKey(8)), Some(false));
+
+        manager.update(1, vec![74]);
+        assert_eq!(manager.verify_seal(1, &ToyKey(7)), Some(false));
+
+        manager.seal(1, &ToyKey(7)).unwrap();
+        manager.begin_transaction().unwrap();
+        manager.delete(1).unwrap();
+        manager.rollback().unwrap();
+        assert_eq!(manager.verify_seal(1, &ToyKey(7)), Some(true));
+    }
+
+    /// Tests that invariants hold through normal use and catch corruption.
+    ///
+    /// - Runs a mix of operations and expects `check_invariants` to pass.
+    /// - Flips a bit in memory and expects it to report the affected block.
+    #[test]
+    fn test_check_invariants() {
+        let mut manager = MemoryManager::with_inline_threshold(2);
+        manager.insert(1, vec![1; 10]);
+        manager.insert(2, vec![2]);
+        manager.copy(1, 3);
+        manager.delete(1);
+        manager.insert_content(vec![5; 8]);
+        assert_eq!(manager.check_invariants(), Ok(()));
+
+        manager.flip_bit(12, 0); // Inside the copy of block 1
+        assert_eq!(manager.check_invariants(), Err("ID 3 does not match its checksum".to_string()));
+    }
+
+    /// Tests tagging blocks and finding them by tag.
+    ///
+    /// - Tags several blocks and expects `find_by_tag` to return the matches in order.
+    /// - Expects tags to follow a rename and disappear on delete.
+    /// - Expects a tag subscription to see changes to tagged blocks only.
+    #[test]
+    fn test_tags() {
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![1]);
+        manager.insert(2, vec![2]);
+        manager.insert(3, vec![3]);
+        manager.set_tag(3, "type", "string").unwrap();
+        manager.set_tag(1, "type", "string").unwrap();
+        manager.set_tag(2, "type", "int").unwrap();
+        assert_eq!(manager.set_tag(9, "type", "string"), None);
+
+        assert_eq!(manager.find_by_tag("type", "string"), vec![1, 3]);
+        assert!(manager.render_dump(false).contains("Tags: {type=string}"));
+
+        let strings = manager.subscribe(Subscription::Tag("type".to_string(), "string".to_string()));
+        manager.update(2, vec![4]);
+        manager.rename(3, 4);
+        assert_eq!(manager.tag(4, "type"), Some("string"));
+        manager.delete(1);
+        assert_eq!(manager.find_by_tag("type", "string"), vec![4]);
+
+        let seen: Vec<BlockId> = strings.try_iter().map(|change| change.id()).collect();
+        assert_eq!(seen, vec![3, 4, 1]);
+    }
+
+    /// Tests aligned inserts and reuse of freed memory.
+    ///
+    /// - Inserts an aligned block after an odd-sized one and expects an aligned start.
+    /// - Expects the padding to stay free and be used by a later small insert.
+    /// - Deletes a block and expects a same-sized insert to reuse its space.
+    /// - Expects non-power-of-two alignments to be rejected.
+    #[test]
+    fn test_insert_aligned() {
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![1; 3]);
+        manager.insert_aligned(2, vec![2; 4], 16).unwrap();
+        assert_eq!(manager.allocations[&2].start, 16);
+        assert_eq!(manager.free_bytes(), 65535 - 7);
+
+        manager.insert(3, vec![3; 13]);
+        assert_eq!(manager.allocations[&3].start, 3);
+
+        manager.delete(1);
+        manager.insert(4, vec![4; 3]);
+        assert_eq!(manager.allocations[&4].start, 0);
+        assert_eq!(manager.read(2), Some(vec![2; 4]));
+
+        assert_eq!(manager.insert_aligned(5, vec![5], 3), None);
+        assert_eq!(manager.insert_aligned(5, vec![5], 0), None);
+        assert_eq!(manager.check_invariants(), Ok(()));
+    }
+
+    /// Tests migrating a shared manager into a larger one while it is in use.
+    ///
+    /// - Starts a writer thread that keeps inserting and updating blocks.
+    /// - Migrates to a larger, compressed manager concurrently.
+    /// - Expects the shared manager to have the new capacity and every block,
+    ///   tag, pin, and subscriber to carry over.
+    #[test]
+    fn test_migrate_to() {
+        let mut manager = MemoryManager::new();
+        for id in 0..100 {
+            manager.insert(id, vec![id as u8; 8]);
+        }
+        manager.set_tag(7, "kind", "seven").unwrap();
+        manager.pin(8).unwrap();
+        let changes = manager.subscribe(Subscription::Id(1000));
+        let shared = crate::shared::SharedMemoryManager::new(manager);
+
+        let writer = {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                for id in 100..1000 {
+                    shared.insert(id, vec![1; 8]);
+                    shared.update(id - 100, vec![2; 8]);
+                }
+            })
+        };
+        let old = shared.migrate_to(MemoryManager { compression: true, ..MemoryManager::with_capacity(1 << 20) }).unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(old.stats().capacity, 65535);
+        assert_eq!(shared.with(|manager| manager.stats().capacity), 1 << 20);
+        for id in 0..900 {
+            assert_eq!(shared.read(id), Some(vec![2; 8]));
+        }
+        for id in 900..1000 {
+            assert_eq!(shared.read(id), Some(vec![1; 8]));
+        }
+        assert_eq!(shared.with(|manager| manager.tag(7, "kind").map(str::to_string)), Some("seven".to_string()));
+        assert!(shared.with(|manager| manager.is_pinned(8)));
+        assert_eq!(shared.with(|manager| manager.check_invariants()), Ok(()));
+
+        shared.insert(1000, vec![3]);
+        assert_eq!(changes.try_iter().count(), 1);
+    }
+
+    /// Tests recording and sorting by access times.
+    ///
+    /// - Inserts two blocks, then reads the first one later.
+    /// - Expects the read time to be recorded and to order the blocks.
+    /// - Expects a delete to forget the block's times.
+    #[cfg(feature = "access-times")]
+    #[test]
+    fn test_access_times() {
+        use crate::access_times::Access;
+
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![1]);
+        manager.insert(2, vec![2]);
+        assert_eq!(manager.last_access(1, Access::Read), None);
+
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let before_read = std::time::SystemTime::now();
+        manager.read(1);
+        assert!(manager.last_access(1, Access::Read).unwrap() > manager.last_access(1, Access::Write).unwrap());
+        assert_eq!(manager.ids_by_access(Access::Read), vec![2, 1]);
+        assert_eq!(manager.accessed_since(Access::Read, before_read), vec![1]);
+        assert_eq!(manager.accessed_since(Access::Write, before_read), Vec::<BlockId>::new());
+
+        manager.update(2, vec![3]);
+        assert_eq!(manager.ids_by_access(Access::Write), vec![1, 2]);
+
+        manager.delete(1);
+        assert_eq!(manager.last_access(1, Access::Write), None);
+    }
+
+    /// Tests the erase policies applied on delete.
+    ///
+    /// - Deletes with `ErasePolicy::None` and expects the bytes to remain.
+    /// - Deletes with the default policy and expects zeros.
+    /// - Deletes with `ErasePolicy::Random` and expects the original bytes gone.
+    #[test]
+    fn test_erase_policies() {
+        let mut manager = MemoryManager::with_erase_policy(ErasePolicy::None);
+        manager.insert(1, vec![7; 16]);
+        manager.delete(1);
+        assert_eq!(&manager.memory[0..16], &[7; 16]);
+
+        manager.set_erase_policy(ErasePolicy::Zero);
+        manager.insert(2, vec![7; 16]);
+        manager.delete(2);
+        assert_eq!(&manager.memory[0..16], &[0; 16]);
+
+        manager.insert(3, vec![7; 16]);
+        manager.secure_delete(3, ErasePolicy::Random(3)).unwrap();
+        assert!(manager.memory[0..16].iter().any(|&byte| byte != 7 && byte != 0));
+        assert_eq!(manager.read(3), None);
+    }
+
+    /// Tests the context reported for failed operations.
+    ///
+    /// - Fills memory and expects an oversized insert to report the space left.
+    /// - Expects duplicate, missing, oversized, and corrupted cases to be told apart.
+    #[test]
+    fn test_failure_context() {
+        use crate::error::MemoryError;
+
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![1; 65000]);
+        manager.insert(2, vec![2; 100]);
+        manager.delete(1);
+        assert_eq!(manager.insert(3, vec![3; 66000]), None);
+        assert_eq!(
+            manager.insert_failure(3, 66000),
+            MemoryError::OutOfSpace { requested: 66000, free: 65435, largest_free: 65000 }
+        );
+        assert_eq!(manager.insert_failure(2, 1), MemoryError::DuplicateId { id: 2 });
+
+        assert_eq!(manager.update(2, vec![0; 101]), None);
+        assert_eq!(manager.update_failure(2, 101), MemoryError::TooLarge { requested: 101, capacity: 100 });
+        assert_eq!(manager.read_failure(9), MemoryError::NotFound { id: 9 });
+        assert_eq!(manager.delete_failure(9).to_string(), "ID not found");
+
+        manager.flip_bit(65000, 0);
+        assert_eq!(manager.read_failure(2), MemoryError::Corrupted { id: 2 });
+    }
+
+    /// Tests that dumps list blocks in a deterministic order.
+    ///
+    /// - Inserts blocks out of ID order with varying sizes, one of them inline.
+    /// - Expects `dump()` to list them by ID.
+    /// - Expects sorting by address and by size to order them accordingly.
+    #[test]
+    fn test_dump_sorted_by() {
+        let mut manager = MemoryManager::with_inline_threshold(1);
+        manager.insert(3, vec![b'c'; 2]);
+        manager.insert(1, vec![b'a'; 5]);
+        manager.insert(2, vec![b'b']);
+        manager.insert(4, vec![b'd'; 3]);
+        manager.delete(3);
+        manager.insert(5, vec![b'e'; 2]);
+
+        let order = |dump: String| -> Vec<String> {
+            dump.lines().filter(|line| line.starts_with("ID ")).map(|line| line[3..4].to_string()).collect()
+        };
+        assert_eq!(order(manager.render_dump(false)), vec!["1", "2", "4", "5"]);
+        assert_eq!(order(manager.render_sorted(SortKey::Id)), vec!["1", "2", "4", "5"]);
+        assert_eq!(order(manager.render_sorted(SortKey::Address)), vec!["5", "1", "4", "2"]);
+        assert_eq!(order(manager.render_sorted(SortKey::Size)), vec!["2", "5", "4", "1"]);
+    }
+
+    /// Tests exporting the allocation table as JSON and CSV.
+    ///
+    /// - Inserts a tagged block and an inline block.
+    /// - Expects both exports to list them in ID order with base64 data and stats.
+    #[test]
+    fn test_export_json_and_csv() {
+        let mut manager = MemoryManager::with_inline_threshold(2);
+        manager.insert(2, vec![b'h', b'i']);
+        manager.insert(1, b"Hello".to_vec());
+        manager.set_tag(1, "note", "a \"quoted\", value").unwrap();
+
+        let json = manager.export_json();
+        assert!(json.contains("\"sequence\": 2,"));
+        assert!(json.contains("\"capacity\": 65535, \"used_bytes\": 5, \"raw_bytes\": 7, \"stored_bytes\": 7, \"free_bytes\": 65530}"));
+        assert!(json.contains(
+            "{\"id\": 1, \"start\": 0, \"size\": 5, \"inline\": false, \"tags\": {\"note\": \"a \\\"quoted\\\", value\"}, \"data\": \"SGVsbG8=\"},\n"
+        ));
+        assert!(json.contains("{\"id\": 2, \"start\": 0, \"size\": 0, \"inline\": true, \"tags\": {}, \"data\": \"aGk=\"}\n"));
+
+        let csv = manager.export_csv();
+        let rows: Vec<&str> = csv.lines().filter(|line| !line.starts_with('#')).collect();
+        assert_eq!(
+            rows,
+            vec!["id,start,size,inline,tags,data", "1,0,5,false,\"note=a \"\"quoted\"\", value\",SGVsbG8=", "2,0,0,true,,aGk="]
+        );
+        assert!(csv.starts_with("# sequence=2\n# allocations=2 capacity=65535"));
+    }
+
+    /// Tests that the built-in self-test passes every stage.
+    #[test]
+    fn test_selftest_passes() {
+        let results = crate::selftest::run_selftest();
+        let stages: Vec<&str> = results.iter().map(|stage| stage.stage).collect();
+        assert_eq!(stages, vec!["fill", "fragment", "compact", "persist", "reload", "verify"]);
+        for stage in results {
+            assert_eq!(stage.result, Ok(()), "stage {} failed", stage.stage);
+        }
+    }
+
+    /// Tests the C interface end to end.
+    ///
+    /// - Inserts, reads, updates, and deletes through the `mm_*` functions.
+    /// - Expects a NULL output buffer to report the length without copying.
+    /// - Expects failures and NULL managers to return -1.
+    #[test]
+    fn test_ffi_round_trip() {
+        use crate::ffi::*;
+
+        unsafe {
+            let mm = mm_new();
+            let data = b"Hello";
+            assert_eq!(mm_insert(mm, 1, data.as_ptr(), data.len()), 0);
+            assert_eq!(mm_insert(mm, 1, data.as_ptr(), data.len()), -1);
+
+            assert_eq!(mm_read(mm, 1, std::ptr::null_mut(), 0), 5);
+            let mut out = [0u8; 5];
+            assert_eq!(mm_read(mm, 1, out.as_mut_ptr(), out.len()), 5);
+            assert_eq!(&out, data);
+
+            assert_eq!(mm_update(mm, 1, b"Jelly".as_ptr(), 5), 0);
+            assert_eq!(mm_read(mm, 1, out.as_mut_ptr(), out.len()), 5);
+            assert_eq!(&out, b"Jelly");
+
+            assert_eq!(mm_delete(mm, 1), 0);
+            assert_eq!(mm_read(mm, 1, out.as_mut_ptr(), out.len()), -1);
+            assert_eq!(mm_delete(std::ptr::null_mut(), 1), -1);
+            mm_free(mm);
+        }
+    }
+
+    /// Tests that write failures are kept for the caller instead of printed.
+    ///
+    /// - Points the index at a directory that does not exist and inserts.
+    /// - Expects the insert to succeed and `take_io_error` to report the failure once.
+    #[test]
+    fn test_io_errors_are_reported() {
+        let mut manager = MemoryManager::new();
+        manager.index_path = Some(std::env::temp_dir().join("mm_missing_dir").join("index.idx"));
+        assert!(manager.insert(1, vec![1]).is_some());
+
+        let error = manager.take_io_error().unwrap();
+        assert!(error.to_string().starts_with("failed to save allocation index"));
+        assert!(manager.take_io_error().is_none());
+        assert_eq!(manager.dump_text(), manager.render_dump(false));
+    }
+
+    /// Tests the text protocol over a real TCP connection.
+    ///
+    /// - Serves a shared manager on an ephemeral port.
+    /// - Sends inserts, reads, updates, deletes, and a dump, one per line.
+    /// - Expects one response line per command, with reasons for failures.
+    /// - Expects reads to be served while another thread holds the shared lock.
+    #[test]
+    fn test_serve_text_protocol() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shared = crate::shared::SharedMemoryManager::new(MemoryManager::new());
+        {
+            let shared = shared.clone();
+            std::thread::spawn(move || crate::server::serve_listener(listener, shared));
+        }
+
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        stream.write_all(b"INSERT 1 48656c6c6f\nINSERT 1 00\n\nREAD 1\nUPDATE 1 4a656c6c79\nDUMP\nDELETE 9\nBOGUS\nQUIT\n").unwrap();
+        let responses: Vec<String> = BufReader::new(stream).lines().map(Result::unwrap).collect();
+        assert_eq!(
+            responses,
+            vec![
+                "OK",
+                "ERR ID already exists",
+                "OK 48656c6c6f",
+                "OK",
+                "OK seq=2 1:0:5:4a656c6c79",
+                "ERR ID not found",
+                "ERR bad operation: BOGUS",
+            ]
+        );
+        assert_eq!(shared.read(1), Some(b"Jelly".to_vec()));
+
+        // Serving these through the exclusive lock would time out
+        let responses: Vec<String> = shared.with_shared(|_| {
+            let mut stream = std::net::TcpStream::connect(addr).unwrap();
+            stream.set_read_timeout(Some(std::time::Duration::from_secs(5))).unwrap();
+            stream.write_all(b"READ 1\nREAD 9\nDUMP\nQUIT\n").unwrap();
+            BufReader::new(stream).lines().map(Result::unwrap).collect()
+        });
+        assert_eq!(responses, vec!["OK 4a656c6c79", "ERR ID not found", "OK seq=2 1:0:5:4a656c6c79"]);
+    }
+
+    /// Tests event callbacks.
+    ///
+    /// - Registers a callback that records every event.
+    /// - Inserts, updates, renames, and deletes blocks.
+    /// - Expects events with addresses and sizes, in order.
+    #[test]
+    fn test_on_event() {
+        use crate::events::Event;
+        use std::sync::{Arc, Mutex};
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut manager = MemoryManager::new();
+        {
+            let events = events.clone();
+            manager.on_event(move |event| events.lock().unwrap().push(event.clone()));
+        }
+
+        manager.insert(1, vec![1; 4]);
+        manager.insert(2, vec![2; 3]);
+        manager.update(2, vec![3; 3]);
+        manager.rename(1, 5);
+        manager.delete(2);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                Event::Inserted { id: 1, start: 0, size: 4 },
+                Event::Inserted { id: 2, start: 4, size: 3 },
+                Event::Updated { id: 2, start: 4, size: 3 },
+                Event::Deleted { id: 1 },
+                Event::Inserted { id: 5, start: 0, size: 4 },
+                Event::Deleted { id: 2 },
+            ]
+        );
+    }
+
+    /// Tests explicit placement and reservation.
+    ///
+    /// - Places blocks at chosen addresses to build a fragmented layout.
+    /// - Expects overlapping or out-of-bounds placements to fail with context.
+    /// - Reserves a region, expects it to read as zeros, then fills it with an update.
+    /// - Expects the new operations to round-trip through the text format.
+    #[test]
+    fn test_reserve_and_insert_at() {
+        use crate::error::MemoryError;
+
+        let mut manager = MemoryManager::new();
+        manager.insert_at(1, 100, vec![1; 10]).unwrap();
+        manager.insert_at(2, 200, vec![2; 10]).unwrap();
+        assert_eq!(manager.insert_at(3, 105, vec![3; 10]), None);
+        assert_eq!(manager.insert_failure(3, 10), MemoryError::Rejected);
+        assert_eq!(manager.insert_at_failure(3, 105, 10), MemoryError::Occupied { start: 105, size: 10 });
+        assert_eq!(manager.insert_at(3, 65530, vec![3; 10]), None);
+        manager.insert_at(3, 110, vec![3; 90]).unwrap();
+        assert_eq!(manager.allocator.regions(), &[(0, 100), (210, 65325)]);
+
+        manager.reserve(4, 8).unwrap();
+        assert_eq!(manager.allocations[&4].start, 0);
+        assert_eq!(manager.read(4), Some(vec![0; 8]));
+        manager.update(4, vec![4; 8]).unwrap();
+        assert_eq!(manager.read(4), Some(vec![4; 8]));
+        assert_eq!(manager.check_invariants(), Ok(()));
+
+        for line in ["RESERVE 4 8", "INSERT_AT 3 110 0303", "PIN 3", "UNPIN 3", "COMPACT"] {
+            assert_eq!(Operation::parse(line).unwrap().unwrap().to_string(), line);
+        }
+    }
+
+    /// Tests compaction around pinned blocks.
+    ///
+    /// - Builds a fragmented layout with one pinned block in the middle.
+    /// - Compacts and expects unpinned blocks to slide down and the pinned one to stay.
+    /// - Expects the gap below the pinned block to be reported as stranded.
+    /// - Unpins, compacts again, and expects all free space in one region.
+    /// - Expects a pinned block deleted in a rolled-back transaction to come back pinned.
+    #[test]
+    fn test_compact_with_pins() {
+        use crate::compaction::CompactReport;
+
+        let mut manager = MemoryManager::with_capacity(100);
+        manager.insert_at(1, 10, vec![1; 10]).unwrap();
+        manager.insert_at(2, 40, vec![2; 10]).unwrap();
+        manager.insert_at(3, 70, vec![3; 10]).unwrap();
+        let hash = manager.insert_content(vec![9; 5]).unwrap();
+        manager.pin(2).unwrap();
+        assert_eq!(manager.pin(9), None);
+
+        let report = manager.compact();
+        assert_eq!(report, CompactReport { moved: 2, bytes_moved: 20, largest_free: 40, stranded: 25 });
+        assert_eq!(manager.content[&hash].block.start, 0);
+        assert_eq!(manager.allocations[&1].start, 5);
+        assert_eq!(manager.allocations[&2].start, 40);
+        assert_eq!(manager.allocations[&3].start, 50);
+        assert_eq!(manager.read(3), Some(vec![3; 10]));
+        assert_eq!(manager.read_content(hash), Some(vec![9; 5]));
+        assert_eq!(manager.check_invariants(), Ok(()));
+
+        manager.rename(2, 4).unwrap();
+        assert!(manager.is_pinned(4));
+        manager.unpin(4).unwrap();
+        let report = manager.compact();
+        assert_eq!((report.largest_free, report.stranded), (65, 0));
+        assert_eq!(manager.read(4), Some(vec![2; 10]));
+        assert_eq!(manager.check_invariants(), Ok(()));
+
+        manager.pin(4).unwrap();
+        manager.begin_transaction().unwrap();
+        manager.delete(4).unwrap();
+        manager.rollback().unwrap();
+        assert!(manager.is_pinned(4));
+    }
+
+    /// Tests that handles follow blocks across relocation and go stale on delete.
+    ///
+    /// - Inserts blocks, deletes the first, and compacts so the second moves.
+    /// - Expects the second block's handle to resolve to its new address.
+    /// - Expects a handle to a deleted ID to stay stale after the ID is reused,
+    ///   including one whose insert was rolled back.
+    #[test]
+    fn test_handles_survive_compaction() {
+        let mut manager = MemoryManager::new();
+        let first = manager.insert(1, vec![1; 16]).unwrap();
+        let second = manager.insert(2, vec![2; 8]).unwrap();
+        assert_eq!(manager.resolve(second), Some(16));
+
+        manager.delete_handle(first).unwrap();
+        manager.compact();
+        assert_eq!(manager.resolve(second), Some(0));
+        assert_eq!(manager.read_handle(second), Some(vec![2; 8]));
+        manager.update_handle(second, vec![3; 8]).unwrap();
+        assert_eq!(manager.read(2), Some(vec![3; 8]));
+
+        let reused = manager.insert(1, vec![4; 4]).unwrap();
+        assert!(!manager.is_valid(first));
+        assert_eq!(manager.read_handle(first), None);
+        assert_eq!(manager.read_handle(reused), Some(vec![4; 4]));
+        assert_eq!(manager.handle(1), Some(reused));
+
+        manager.rename(2, 5).unwrap();
+        assert_eq!(manager.read_handle(second), None);
+
+        // A rolled-back insert retires its handles like a delete
+        manager.begin_transaction().unwrap();
+        let rolled_back = manager.insert(6, vec![6; 4]).unwrap();
+        manager.rollback().unwrap();
+        manager.insert(6, vec![7; 4]).unwrap();
+        assert!(!manager.is_valid(rolled_back));
+    }
+
+    /// Tests deduplication, copy-on-write, and the bytes-saved statistic.
+    ///
+    /// - Inserts the same data three times and copies one block.
+    /// - Expects all four blocks to share one region and stats to report the saving.
+    /// - Updates and deletes shared blocks, expecting the others to keep their data.
+    #[test]
+    fn test_dedup() {
+        let mut manager = MemoryManager::with_dedup();
+        manager.insert(1, vec![7; 20]).unwrap();
+        manager.insert(2, vec![7; 20]).unwrap();
+        manager.insert(3, vec![8; 20]).unwrap();
+        manager.copy(1, 4).unwrap();
+        assert_eq!(manager.allocations[&2].start, manager.allocations[&1].start);
+        assert_eq!(manager.allocations[&4].start, manager.allocations[&1].start);
+        assert_eq!(manager.refcount(1), Some(3));
+        assert_eq!(manager.refcount(3), Some(1));
+        assert_eq!(manager.free_bytes(), 65535 - 40);
+        let stats = manager.stats();
+        assert_eq!((stats.used_bytes, stats.dedup_saved), (40, 40));
+        assert_eq!(manager.check_invariants(), Ok(()));
+
+        // Copy-on-write: the updated block moves, the others keep the old bytes
+        manager.update(2, vec![9; 20]).unwrap();
+        assert_ne!(manager.allocations[&2].start, manager.allocations[&1].start);
+        assert_eq!(manager.read(1), Some(vec![7; 20]));
+        assert_eq!(manager.read(2), Some(vec![9; 20]));
+        assert_eq!(manager.refcount(1), Some(2));
+
+        // Deleting drops a reference; the last one frees the region
+        manager.delete(1).unwrap();
+        assert_eq!(manager.read(4), Some(vec![7; 20]));
+        assert_eq!(manager.stats().dedup_saved, 0);
+        manager.delete(4).unwrap();
+        assert_eq!(manager.free_bytes(), 65535 - 40);
+        assert_eq!(manager.check_invariants(), Ok(()));
+
+        manager.insert(5, vec![8; 20]).unwrap();
+        manager.delete(3).unwrap();
+        manager.compact();
+        assert_eq!(manager.read(5), Some(vec![8; 20]));
+        assert_eq!(manager.check_invariants(), Ok(()));
+    }
+
+    /// Tests the bitmap allocator against the free list on the same workload.
+    ///
+    /// - Fills both with 10-byte blocks and deletes every other one.
+    /// - Expects the bitmap to round blocks up to 16-byte cells, so it holds fewer blocks.
+    /// - Expects both to keep reads, compaction, and invariants working.
+    #[test]
+    fn test_bitmap_allocator() {
+        use crate::bitmap::BitmapAllocator;
+        use crate::free_list::FreeList;
+
+        let mut list = MemoryManager::with_allocator(Box::new(FreeList::new(200)));
+        let mut bitmap = MemoryManager::with_allocator(Box::new(BitmapAllocator::new(200, 16)));
+        for manager in [&mut list, &mut bitmap] {
+            let mut id = 0;
+            while manager.insert(id, vec![id as u8; 10]).is_some() {
+                id += 1;
+            }
+            for id in (0..id).step_by(2) {
+                manager.delete(id).unwrap();
+            }
+            assert_eq!(manager.check_invariants(), Ok(()));
+        }
+        assert_eq!(list.allocations.len(), 10);
+        assert_eq!(bitmap.allocations.len(), 6);
+        assert_eq!(list.allocator.largest(), 10);
+        assert_eq!(bitmap.allocator.largest(), 16);
+        assert_eq!(bitmap.allocator.regions()[..2], [(0, 16), (32, 16)]);
+
+        // Alignment coarser than a cell, and exact placement inside a free cell run
+        bitmap.insert_aligned(19, vec![1; 4], 64).unwrap();
+        let aligned = bitmap.insert_aligned(20, vec![1; 4], 64).unwrap();
+        assert_eq!(bitmap.resolve(aligned), Some(64));
+        assert!(bitmap.insert_at(21, 35, vec![2; 4]).is_some());
+        assert!(bitmap.insert_at(22, 40, vec![3; 4]).is_none());
+
+        let report = bitmap.compact();
+        assert_eq!(report.largest_free, 200 - 16 * 9);
+        assert_eq!(bitmap.allocations[&21].start, 32);
+        assert_eq!(bitmap.read(21), Some(vec![2; 4]));
+        assert_eq!(bitmap.check_invariants(), Ok(()));
+    }
+
+    /// Tests the operation counters and their Prometheus rendering.
+    ///
+    /// - Inserts, reads, updates, and deletes blocks, including a failed allocation.
+    /// - Compacts so a block moves.
+    /// - Expects the counters, the size histogram, and the text format to agree.
+    #[test]
+    fn test_metrics() {
+        let mut manager = MemoryManager::with_capacity(100);
+        manager.insert(1, vec![1; 10]).unwrap();
+        manager.insert(2, vec![2; 70]).unwrap();
+        assert!(manager.insert(3, vec![3; 50]).is_none());
+        manager.read(2).unwrap();
+        manager.update(2, vec![4; 70]).unwrap();
+        manager.delete(1).unwrap();
+        manager.compact();
+
+        let metrics = manager.metrics();
+        assert_eq!((metrics.inserts, metrics.updates, metrics.deletes), (2, 1, 1));
+        assert_eq!((metrics.reads, metrics.failed_allocations, metrics.bytes_compacted), (1, 1, 70));
+        assert_eq!(metrics.allocation_sizes.buckets, [1, 1, 2, 2, 2, 2, 2]);
+        assert_eq!((metrics.allocation_sizes.count, metrics.allocation_sizes.sum), (2, 80));
+
+        let text = manager.metrics_text();
+        assert!(text.contains("# TYPE memory_manager_inserts_total counter\nmemory_manager_inserts_total 2\n"));
+        assert!(text.contains("memory_manager_free_bytes 30\n"));
+        assert!(text.contains("memory_manager_allocation_size_bytes_bucket{le=\"64\"} 1\n"));
+        assert!(text.contains("memory_manager_allocation_size_bytes_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.ends_with("memory_manager_allocation_size_bytes_count 2\n"));
+    }
+
+    /// Tests the borrowing read APIs.
+    ///
+    /// - Expects `read_ref` to point into memory for a plain block.
+    /// - Expects `read_cow` to borrow plain blocks and own decoded ones.
+    /// - Expects `read_ref` to refuse compressed and corrupted blocks.
+    #[test]
+    fn test_read_ref_and_cow() {
+        use std::borrow::Cow;
+
+        let mut manager = MemoryManager::with_compression();
+        manager.insert(1, vec![1, 2, 3]).unwrap();
+        manager.insert(2, vec![5; 40]).unwrap();
+        assert!(manager.allocations[&2].compressed);
+
+        let data = manager.read_ref(1).unwrap();
+        assert_eq!(data, &[1, 2, 3]);
+        assert_eq!(data.as_ptr(), manager.memory[manager.allocations[&1].start..].as_ptr());
+        assert!(matches!(manager.read_cow(1), Some(Cow::Borrowed(&[1, 2, 3]))));
+        assert!(matches!(manager.read_cow(2), Some(Cow::Owned(data)) if data == vec![5; 40]));
+        assert_eq!(manager.read_ref(2), None);
+        assert_eq!(manager.read_ref(3), None);
+        assert_eq!(manager.metrics().reads, 3);
+
+        let start = manager.allocations[&1].start;
+        manager.memory[start] ^= 0xff;
+        assert_eq!(manager.read_ref(1), None);
+        assert_eq!(manager.read_cow(1), None);
+    }
+
+    /// Tests building managers from options.
+    ///
+    /// - Builds a bitmap-backed, compressing manager and checks both options apply.
+    /// - Expects invalid combinations to be rejected with the matching error.
+    #[test]
+    fn test_builder() {
+        use crate::builder::{BuildError, Strategy};
+        use crate::throttle::Backpressure;
+
+        let mut manager = MemoryManager::builder()
+            .capacity(256)
+            .strategy(Strategy::Bitmap { cell: 32 })
+            .compression(true)
+            .erase_policy(ErasePolicy::None)
+            .history_depth(0)
+            .build()
+            .unwrap();
+        assert_eq!(manager.memory.len(), 256);
+        assert_eq!(manager.allocator.granularity(), 32);
+        manager.insert(1, vec![3; 100]).unwrap();
+        assert!(manager.allocations[&1].compressed);
+        assert_eq!(manager.free_bytes(), 256 - 32);
+        assert_eq!(manager.erase_policy, ErasePolicy::None);
+
+        assert!(matches!(MemoryManager::builder().capacity(0).build(), Err(BuildError::ZeroCapacity)));
+        assert!(matches!(
+            MemoryManager::builder().capacity(16).strategy(Strategy::Bitmap { cell: 32 }).build(),
+            Err(BuildError::BadCellSize { cell: 32, capacity: 16 })
+        ));
+        assert!(matches!(
+            MemoryManager::builder().capacity(100).soft_limit(200, Backpressure::Shrink).build(),
+            Err(BuildError::SoftLimitTooLarge { limit: 200, capacity: 100 })
+        ));
+        assert!(matches!(
+            MemoryManager::builder().dedup(true).encryption([7; 32]).build(),
+            Err(BuildError::DedupWithEncryption)
+        ));
+        assert!(MemoryManager::builder().soft_limit(usize::MAX, Backpressure::Off).build().is_ok());
+    }
+
+    /// Tests IDs beyond the old 16-bit range.
+    ///
+    /// - Inserts under IDs above 65535 directly and through the text format.
+    /// - Expects them to stay distinct from the IDs they would have truncated to.
+    #[test]
+    fn test_wide_ids() {
+        let mut manager = MemoryManager::new();
+        manager.insert(70_000, vec![1; 4]).unwrap();
+        manager.insert(70_000 % 65536, vec![2; 4]).unwrap();
+        let op = Operation::parse("INSERT 4000000000 0303").unwrap().unwrap();
+        manager.apply(&[op]);
+        assert_eq!(manager.read(70_000), Some(vec![1; 4]));
+        assert_eq!(manager.read(4_464), Some(vec![2; 4]));
+        assert_eq!(manager.read(4_000_000_000), Some(vec![3, 3]));
+        assert_eq!(crate::delete::delete(&mut manager, 70_000), Ok(()));
+        assert!(crate::read::read(&manager, 70_000).is_err());
+    }
+
+    /// Tests the free-region iterator and the memory map.
+    ///
+    /// - Fragments memory with inserts and a delete, plus a content block and an inline block.
+    /// - Expects the map to interleave blocks and free regions in address order.
+    #[test]
+    fn test_memory_map() {
+        use crate::memory_map::{Segment, SegmentKind};
+
+        let mut manager = MemoryManager::with_capacity(100);
+        manager.insert_at(1, 10, vec![1; 10]).unwrap();
+        manager.insert_at(2, 30, vec![2; 20]).unwrap();
+        manager.insert_at(3, 50, vec![3; 5]).unwrap();
+        manager.delete(2).unwrap();
+        let hash = manager.insert_content(vec![9; 4]).unwrap();
+        manager.inline_threshold = 8;
+        manager.insert(4, vec![4; 2]).unwrap();
+
+        assert_eq!(manager.free_regions().collect::<Vec<_>>(), [(4, 6), (20, 30), (55, 45)]);
+        let segment = |start, size, kind| Segment { start, size, kind };
+        assert_eq!(
+            manager.memory_map(),
+            [
+                segment(0, 4, SegmentKind::Content(hash)),
+                segment(4, 6, SegmentKind::Free),
+                segment(10, 10, SegmentKind::Block(1)),
+                segment(20, 30, SegmentKind::Free),
+                segment(50, 5, SegmentKind::Block(3)),
+                segment(55, 45, SegmentKind::Free),
+            ]
+        );
+    }
+
+    /// Tests splitting a block in two and merging the halves back.
+    ///
+    /// - Splits a block and expects two new IDs holding its bytes, in the same region.
+    /// - Rejects an offset at the edge and a merge of non-adjacent blocks.
+    /// - Merges the halves, then undoes the merge and the split.
+    #[test]
+    fn test_split_merge() {
+        let mut manager = MemoryManager::with_capacity(100);
+        manager.set_history_depth(64);
+        manager.insert(0, b"HelloWorld".to_vec()).unwrap();
+        manager.insert(3, vec![7; 4]).unwrap();
+        manager.pin(0).unwrap();
+        assert_eq!(manager.split(0, 0), None);
+        assert_eq!(manager.split(0, 10), None);
+
+        assert_eq!(manager.split(0, 5), Some((1, 2)));
+        assert_eq!(manager.read(0), None);
+        assert_eq!(manager.read(1), Some(b"Hello".to_vec()));
+        assert_eq!(manager.read(2), Some(b"World".to_vec()));
+        assert_eq!(manager.allocations[&2].start, 5);
+        assert!(manager.is_pinned(1) && manager.is_pinned(2));
+        assert_eq!(manager.merge(2, 1), None);
+        assert_eq!(manager.merge(1, 3), None);
+
+        manager.merge(1, 2).unwrap();
+        assert_eq!(manager.read(1), Some(b"HelloWorld".to_vec()));
+        assert_eq!(manager.read(2), None);
+        manager.check_invariants().unwrap();
+
+        manager.undo().unwrap();
+        assert_eq!(manager.read(2), Some(b"World".to_vec()));
+        manager.undo().unwrap();
+        assert_eq!(manager.read(0), Some(b"HelloWorld".to_vec()));
+        assert_eq!(manager.read(1), None);
+        manager.check_invariants().unwrap();
+    }
+
+    /// Tests that reads through a shared manager do not wait for each other.
+    ///
+    /// - Holds the shared lock on one thread and reads from another.
+    /// - Expects the read to complete while the lock is still held.
+    #[test]
+    fn test_shared_concurrent_reads() {
+        let shared = crate::shared::SharedMemoryManager::new(MemoryManager::new());
+        shared.insert(1, vec![1, 2, 3]).unwrap();
+        let data = shared.with_shared(|manager| {
+            assert_eq!(manager.read_ref(1), Some(&[1, 2, 3][..]));
+            let reader = shared.clone();
+            std::thread::spawn(move || reader.read(1)).join().unwrap()
+        });
+        assert_eq!(data, Some(vec![1, 2, 3]));
+    }
+
+    /// Tests keeping previous versions of updated blocks.
+    ///
+    /// - Updates a block three times with a depth of 2.
+    /// - Expects the current data and the two latest previous versions, newest first.
+    /// - Expects renames to carry the versions and deletes to discard them.
+    #[test]
+    fn test_versions() {
+        let mut manager = MemoryManager::with_versions(2);
+        manager.insert(1, vec![1; 4]).unwrap();
+        assert_eq!(manager.history(1), Some(vec![vec![1; 4]]));
+        manager.update(1, vec![2; 4]).unwrap();
+        manager.update(1, vec![3; 2]).unwrap();
+        manager.update(1, vec![4; 3]).unwrap();
+
+        assert_eq!(manager.read_version(1, 0), Some(vec![4, 4, 4, 0]));
+        assert_eq!(manager.read_version(1, 1), Some(vec![3, 3, 0, 0]));
+        assert_eq!(manager.read_version(1, 2), Some(vec![2; 4]));
+        assert_eq!(manager.read_version(1, 3), None);
+
+        manager.rename(1, 2).unwrap();
+        assert_eq!(manager.history(2).map(|versions| versions.len()), Some(3));
+        manager.set_version_depth(1);
+        assert_eq!(manager.history(2), Some(vec![vec![4, 4, 4, 0], vec![3, 3, 0, 0]]));
+        manager.delete(2).unwrap();
+        manager.insert(2, vec![5]).unwrap();
+        assert_eq!(manager.read_version(2, 1), None);
+    }
+
+    /// Tests the ASCII memory map.
+    ///
+    /// - Places blocks so one fills whole cells, one is pinned, and two meet inside a cell.
+    /// - Expects free cells as dots, blocks as letters, and the shared cell as a bar.
+    #[test]
+    fn test_visualize() {
+        let mut manager = MemoryManager::with_capacity(100);
+        manager.insert_at(1, 0, vec![1; 20]).unwrap();
+        manager.insert_at(2, 40, vec![2; 15]).unwrap();
+        manager.insert_at(3, 55, vec![3; 10]).unwrap();
+        manager.pin(3).unwrap();
+
+        assert_eq!(
+            manager.visualize_cells(10),
+            "100 bytes, 10 per cell\n\
+             [aa..b|C...]\n\
+             a  ID 1 at 0, 20 bytes\n\
+             b  ID 2 at 40, 15 bytes\n\
+             C  ID 3 at 55, 10 bytes, pinned\n\
+             .  free  |  several blocks\n"
+        );
+        assert_eq!(manager.visualize().lines().nth(1).map(str::len), Some(102));
+    }
+
+    /// Tests guard bytes around blocks.
+    ///
+    /// - Inserts blocks with canaries on and expects guards between them.
+    /// - Deletes and compacts, expecting the guards to move with the blocks.
+    /// - Writes one byte past a block and expects the sweep, reads, and updates to catch it.
+    #[test]
+    fn test_canaries() {
+        use crate::canary::{GuardSide, SmashedGuard, CANARY, CANARY_LEN};
+        use crate::error::MemoryError;
+        use crate::memory_map::SegmentKind;
+
+        let mut manager = MemoryManager::with_capacity(100);
+        manager.set_canaries(true).unwrap();
+        manager.insert(1, vec![1; 10]).unwrap();
+        manager.insert(2, vec![2; 10]).unwrap();
+        assert_eq!(manager.set_canaries(false), None);
+        assert_eq!(manager.allocations[&1].start, CANARY_LEN);
+        assert_eq!(manager.allocations[&2].start, 3 * CANARY_LEN + 10);
+        assert_eq!(manager.memory[CANARY_LEN + 10], CANARY);
+        assert_eq!(manager.free_bytes(), 100 - 2 * (10 + 2 * CANARY_LEN));
+
+        manager.delete(1).unwrap();
+        manager.compact();
+        assert_eq!(manager.allocations[&2].start, CANARY_LEN);
+        manager.check_invariants().unwrap();
+        assert!(manager.check_integrity().is_empty());
+
+        manager.memory[CANARY_LEN + 10] = 0;
+        assert_eq!(
+            manager.check_integrity(),
+            [SmashedGuard { owner: SegmentKind::Block(2), side: GuardSide::After, address: CANARY_LEN + 10 }]
+        );
+        assert_eq!(manager.read(2), None);
+        assert_eq!(manager.read_failure(2), MemoryError::GuardSmashed { id: 2 });
+        assert_eq!(manager.update(2, vec![3; 4]), None);
+        manager.delete(2).unwrap();
+        assert_eq!(manager.free_bytes(), 100);
+    }
+
+    /// Tests the per-ID audit trail.
+    ///
+    /// - Inserts, reads, and updates a block as one actor, then deletes it as another.
+    /// - Expects the report to credit the insert, count updates and reads, and list every change.
+    /// - Expects the trail to survive the delete and to be discarded when auditing is turned off.
+    #[test]
+    fn test_audit() {
+        use crate::audit::AuditAction;
+
+        let mut manager = MemoryManager::with_audit();
+        manager.set_actor(Some("alice"));
+        manager.insert(1, vec![1; 4]).unwrap();
+        manager.read(1).unwrap();
+        manager.read(1).unwrap();
+        manager.update(1, vec![2; 2]).unwrap();
+        manager.set_actor(Some("bob"));
+        manager.delete(1).unwrap();
+        manager.set_actor(None);
+        manager.insert(2, vec![3]).unwrap();
+
+        let report = manager.audit(1).unwrap();
+        assert_eq!(report.inserted_by.as_deref(), Some("alice"));
+        assert_eq!((report.updates, report.reads), (1, 2));
+        assert!(report.last_read.is_some());
+        let actions: Vec<_> = report.entries.iter().map(|entry| (entry.sequence, entry.action.clone())).collect();
+        assert_eq!(
+            actions,
+            [(1, AuditAction::Inserted { size: 4 }), (2, AuditAction::Updated { size: 2 }), (3, AuditAction::Deleted)]
+        );
+        assert_eq!(report.entries[2].actor.as_deref(), Some("bob"));
+        assert!(report.to_string().starts_with("ID 1: inserted by alice, 1 updates, 2 reads, last read at "));
+
+        assert_eq!(manager.audit_all().iter().map(|report| report.id).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(manager.audit(2).unwrap().inserted_by, None);
+        manager.set_audit(false);
+        assert_eq!(manager.audit(1), None);
+    }
+
+    /// Tests virtual address translation.
+    ///
+    /// - Maps two blocks and translates, reads, and writes through their virtual addresses.
+    /// - Expects address 0, the gap after a block, lengths that overflow, and unmapped blocks to fault.
+    /// - Expects compressed blocks to be readable but not translatable.
+    #[test]
+    fn test_virtual_memory() {
+        use crate::virtual_memory::{VirtualMemory, VmError, PAGE_SIZE};
+
+        let mut vm = VirtualMemory::new(MemoryManager::new());
+        let a = vm.insert(1, vec![1, 2, 3, 4]).unwrap();
+        let b = vm.insert(2, vec![5; 8]).unwrap();
+        assert_eq!((a, b), (PAGE_SIZE, 3 * PAGE_SIZE));
+        assert_eq!(vm.base_of(2), Some(b));
+
+        let physical = vm.manager().allocations[&2].start;
+        assert_eq!(vm.translate(b + 3), Ok(physical + 3));
+        assert_eq!(vm.vread(a + 1, 2), Ok(vec![2, 3]));
+        vm.vwrite(a + 2, &[9, 9]).unwrap();
+        assert_eq!(vm.manager().read(1), Some(vec![1, 2, 9, 9]));
+
+        assert_eq!(vm.vread(0, 1), Err(VmError::SegmentationFault { vaddr: 0 }));
+        assert_eq!(vm.vread(a + 2, 4), Err(VmError::SegmentationFault { vaddr: a + 4 }));
+        assert_eq!(vm.vwrite(a + 4, &[0]), Err(VmError::SegmentationFault { vaddr: a + 4 }));
+        assert_eq!(vm.vread(a + 1, usize::MAX), Err(VmError::SegmentationFault { vaddr: a + 4 }));
+        assert_eq!(vm.manager().read(1), Some(vec![1, 2, 9, 9]));
+        assert_eq!(vm.map(1), Err(VmError::AlreadyMapped { id: 1, base: a }));
+
+        vm.unmap(2).unwrap();
+        assert_eq!(vm.translate(b), Err(VmError::SegmentationFault { vaddr: b }));
+        vm.manager_mut().delete(1).unwrap();
+        assert_eq!(vm.vread(a, 1), Err(VmError::SegmentationFault { vaddr: a }));
+
+        let mut vm = VirtualMemory::new(MemoryManager::with_compression());
+        let c = vm.insert(3, vec![7; 40]).unwrap();
+        assert_eq!(vm.translate(c), Err(VmError::NotAddressable { id: 3 }));
+        assert_eq!(vm.vread(c + 38, 2), Ok(vec![7, 7]));
+    }
+
+    /// Tests the paging simulation.
+    ///
+    /// - Runs the same page reference string under each replacement policy with three frames.
+    /// - Expects the textbook fault counts: 6 for FIFO, 5 for LRU, and 6 for Clock.
+    /// - Expects dirty pages to survive eviction, processes to be isolated, and
+    ///   accesses past the address space to fault.
+    #[test]
+    fn test_paging() {
+        use crate::paging::{Pager, PagingError, Replacement};
+
+        for (policy, faults) in [(Replacement::Fifo, 6), (Replacement::Lru, 5), (Replacement::Clock, 6)] {
+            let mut pager = Pager::new(MemoryManager::with_capacity(48), 16, policy);
+            let pid = pager.spawn(5);
+            for page in [0, 1, 2, 0, 3, 0, 4] {
+                pager.read(pid, page * 16, 1).unwrap();
+            }
+            let stats = pager.stats();
+            assert_eq!((stats.accesses, stats.faults, stats.evictions), (7, faults, faults - 3), "{:?}", policy);
+            assert_eq!(pager.faults(pid), Some(faults));
+            assert_eq!(pager.manager().allocations.len(), 3);
+        }
+
+        let mut pager = Pager::new(MemoryManager::with_capacity(32), 16, Replacement::Lru);
+        let (a, b) = (pager.spawn(4), pager.spawn(4));
+        pager.write(a, 14, &[1, 2, 3, 4]).unwrap();
+        pager.write(b, 14, &[9; 4]).unwrap();
+        assert_eq!(pager.stats().writebacks, 2);
+        assert_eq!(pager.page_table(a).unwrap()[0].frame, None);
+        assert_eq!(pager.read(a, 12, 8).unwrap(), [0, 0, 1, 2, 3, 4, 0, 0]);
+        assert_eq!(pager.read(b, 15, 2).unwrap(), [9, 9]);
+        assert_eq!(pager.read(a, 60, 5), Err(PagingError::SegmentationFault { pid: a, vaddr: 64 }));
+        assert_eq!(pager.read(7, 0, 1), Err(PagingError::NoSuchProcess { pid: 7 }));
+
+        pager.exit(b).unwrap();
+        assert!(pager.manager().allocations.len() <= 1);
+        assert_eq!(pager.read(a, 16, 2).unwrap(), [3, 4]);
+    }
+
+    /// Tests swapping cold blocks out to a file.
+    ///
+    /// - Fills memory, then inserts another block, which swaps out the least recently used one.
+    /// - Expects the swapped block to stay readable and to come back into memory on update.
+    /// - Expects deletes, duplicate IDs, and rollbacks to account for swapped blocks.
+    #[test]
+    fn test_swap() {
+        use crate::error::MemoryError;
+        use crate::swap::SwapStats;
+
+        let path = std::env::temp_dir().join(format!("mm_swap_{}.bin", std::process::id()));
+        let mut manager = MemoryManager::with_capacity(30);
+        manager.set_swap(&path, 100).unwrap();
+        for id in 1..=3 {
+            manager.insert(id, vec![id as u8; 10]).unwrap();
+        }
+        manager.read(1).unwrap();
+
+        // Block 2 is the coldest
+        manager.insert(4, vec![4; 10]).unwrap();
+        assert!(manager.is_swapped(2));
+        assert_eq!(manager.read(2), Some(vec![2; 10]));
+        assert_eq!(manager.insert(2, vec![0]), None);
+        assert_eq!(manager.insert_failure(2, 1), MemoryError::DuplicateId { id: 2 });
+
+        // Bringing block 2 back pushes out block 3, now the coldest
+        manager.update(2, vec![5; 10]).unwrap();
+        assert!(!manager.is_swapped(2) && manager.is_swapped(3));
+        assert_eq!(manager.read(2), Some(vec![5; 10]));
+        assert_eq!(
+            manager.swap_stats(),
+            SwapStats { swap_outs: 2, swap_ins: 2, swapped: 1, swapped_bytes: 10 }
+        );
+
+        manager.begin_transaction().unwrap();
+        manager.delete(3).unwrap();
+        assert_eq!(manager.read(3), None);
+        manager.rollback().unwrap();
+        assert_eq!(manager.read(3), Some(vec![3; 10]));
+
+        manager.delete(3).unwrap();
+        assert_eq!(manager.swap_stats().swapped, 0);
+        manager.check_invariants().unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Tests per-block access counters and simulated latency.
+    ///
+    /// - Reads and updates blocks different numbers of times.
+    /// - Expects the counts per block and `hot_blocks` to rank them by total accesses.
+    /// - Expects blocks outside the simulated cache to take the slower latency.
+    #[test]
+    fn test_access_counts() {
+        use crate::access_counts::{AccessCounts, Latency};
+        use std::time::{Duration, Instant};
+
+        let mut manager = MemoryManager::new();
+        for id in 1..=3 {
+            manager.insert(id, vec![0; 4]).unwrap();
+        }
+        for _ in 0..3 {
+            manager.read(2).unwrap();
+        }
+        manager.update(3, vec![1; 4]).unwrap();
+        manager.read(3).unwrap();
+
+        assert_eq!(manager.access_counts(2), Some(AccessCounts { reads: 3, writes: 1 }));
+        assert_eq!(manager.access_counts(3).unwrap().total(), 3);
+        assert_eq!(manager.access_counts(9), None);
+        assert_eq!(manager.hot_blocks(2), [2, 3]);
+        manager.delete(2).unwrap();
+        assert_eq!(manager.hot_blocks(5), [3, 1]);
+        manager.reset_access_counts();
+        assert_eq!(manager.access_counts(3), Some(AccessCounts::default()));
+
+        let slow = Duration::from_millis(50);
+        manager.set_latency(Some(Latency { cache: Duration::ZERO, memory: slow, cache_blocks: 1 }));
+        let started = Instant::now();
+        manager.read(1).unwrap(); // Miss
+        manager.read(1).unwrap(); // Hit
+        manager.read(1).unwrap(); // Hit
+        let elapsed = started.elapsed();
+        assert!(elapsed >= slow && elapsed < 2 * slow, "{:?}", elapsed);
+        manager.read(3).unwrap(); // Miss, evicting 1
+        assert!(started.elapsed() >= 2 * slow);
+    }
+
+    /// Tests watchpoints on blocks and address ranges.
+    ///
+    /// - Watches one block and a range of memory covering another.
+    /// - Expects reads and changes of each to emit watch events with the old and new bytes.
+    /// - Expects changes elsewhere, and writes of identical bytes to the range, to emit nothing.
+    #[test]
+    fn test_watchpoints() {
+        use crate::events::Event;
+        use std::sync::{Arc, Mutex};
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut manager = MemoryManager::new();
+        {
+            let events = events.clone();
+            manager.on_event(move |event| {
+                if matches!(event, Event::WatchRead { .. } | Event::WatchWrite { .. }) {
+                    events.lock().unwrap().push(event.clone());
+                }
+            });
+        }
+        let block = manager.watch(1);
+        let range = manager.watch_range(6, 2);
+        assert_eq!(manager.watchpoints(), [block, range]);
+
+        manager.insert(1, vec![1; 4]).unwrap();
+        manager.insert(2, vec![2; 4]).unwrap();
+        manager.read(1).unwrap();
+        manager.read(2).unwrap();
+        manager.update(2, vec![2; 4]).unwrap(); // Same bytes: the range does not change
+        manager.update(1, vec![3; 4]).unwrap();
+        manager.delete(1).unwrap();
+        manager.insert(3, vec![9; 1]).unwrap(); // Lands at 0, outside both watchpoints
+        assert_eq!(
+            std::mem::take(&mut *events.lock().unwrap()),
+            [
+                Event::WatchWrite { point: block, id: Some(1), old: None, new: Some(vec![1; 4]) },
+                Event::WatchWrite { point: range, id: Some(2), old: Some(vec![0; 2]), new: Some(vec![2; 2]) },
+                Event::WatchRead { point: block, id: 1, data: vec![1; 4] },
+                Event::WatchRead { point: range, id: 2, data: vec![2; 2] },
+                Event::WatchWrite { point: block, id: Some(1), old: Some(vec![1; 4]), new: Some(vec![3; 4]) },
+                Event::WatchWrite { point: block, id: Some(1), old: Some(vec![3; 4]), new: None },
+            ]
+        );
+
+        manager.unwatch(block).unwrap();
+        assert_eq!(manager.unwatch(block), None);
+        manager.compact();
+        assert_eq!(
+            *events.lock().unwrap(),
+            [Event::WatchWrite { point: range, id: None, old: Some(vec![2; 2]), new: Some(vec![0; 2]) }]
+        );
+    }
+
+    /// Tests bulk import and export in the `id<TAB>base64` format.
+    ///
+    /// - Exports a manager's blocks and imports them into a fresh one.
+    /// - Expects the blocks to round-trip, including empty and binary data.
+    /// - Expects a malformed file or a clashing ID to be refused without inserting anything.
+    /// - Expects `execute` to refuse `IMPORT` and `EXPORT`, so network clients cannot touch files.
+    #[test]
+    fn test_import_export() {
+        use crate::export::{decode_base64, encode_base64};
+
+        for data in [&b""[..], b"f", b"fo", b"foo", b"foob", &[0, 255, 128]] {
+            assert_eq!(decode_base64(&encode_base64(data)).as_deref(), Some(data));
+        }
+        assert_eq!(decode_base64("Zm9v!"), None);
+
+        let path = std::env::temp_dir().join(format!("mm_export_{}.tsv", std::process::id()));
+        let mut manager = MemoryManager::new();
+        manager.insert(2, b"world".to_vec()).unwrap();
+        manager.insert(1, b"hello".to_vec()).unwrap();
+        manager.insert(3, Vec::new()).unwrap();
+        assert_eq!(manager.export(&path).unwrap(), 3);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "1\taGVsbG8=\n2\td29ybGQ=\n3\t\n");
+
+        let mut copy = MemoryManager::new();
+        assert!(copy.execute(&format!("IMPORT {}", path.display())).unwrap().starts_with("ERR"));
+        assert!(copy.execute(&format!("EXPORT {}", path.display())).unwrap().starts_with("ERR"));
+        assert_eq!(copy.read(1), None);
+        assert_eq!(copy.import(&path).unwrap(), 3);
+        for id in 1..=3 {
+            assert_eq!(copy.read(id), manager.read(id));
+        }
+
+        // Block 2 already exists, so nothing is imported
+        let mut clash = MemoryManager::new();
+        clash.insert(2, vec![0]).unwrap();
+        assert!(clash.import(&path).unwrap_err().to_string().contains("block 2: ID already exists"));
+        assert_eq!(clash.read(1), None);
+
+        std::fs::write(&path, "# comment\n1\taGk=\n\nx\ty\n").unwrap();
+        assert_eq!(MemoryManager::new().import(&path).unwrap_err().to_string(), "line 4: bad ID");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Tests the workload generator and fragmentation sampling.
+    ///
+    /// - Expects the same seed to generate the same operations, and a different seed different ones.
+    /// - Runs a workload and expects every operation to succeed and reads to see only the fill byte and zero padding.
+    /// - Expects fragmentation to be sampled at the start, every `sample_every` operations, and at the end.
+    #[test]
+    fn test_workload() {
+        use crate::operation::{OpResult, Operation};
+        use crate::workload::{SizeDistribution, Workload};
+
+        let workload = Workload {
+            operations: 2_000,
+            live_blocks: 50,
+            sizes: SizeDistribution::Exponential { mean: 16, max: 64 },
+            ..Workload::default()
+        };
+        let ops = workload.generate();
+        assert_eq!(ops.len(), 2_000);
+        assert_eq!(ops, workload.generate());
+        assert_ne!(ops, Workload { seed: 2, ..workload }.generate());
+        assert!(ops.iter().any(|op| matches!(op, Operation::Delete { .. })));
+        assert!(ops.iter().any(|op| matches!(op, Operation::Update { .. })));
+
+        let mut manager = MemoryManager::new();
+        for (op, result) in ops.iter().zip(manager.apply(&ops)) {
+            assert!(result.is_ok(), "{:?}", op);
+            if let (Operation::Read { id }, OpResult::Data(data)) = (op, result) {
+                assert!(data.iter().all(|&byte| byte == *id as u8 || byte == 0));
+            }
+        }
+
+        let mut manager = MemoryManager::new();
+        let report = manager.run_workload(&ops, 500);
+        assert_eq!((report.operations, report.failed), (2_000, 0));
+        let sampled: Vec<usize> = report.samples.iter().map(|sample| sample.operation).collect();
+        assert_eq!(sampled, [0, 500, 1_000, 1_500, 2_000]);
+        assert_eq!(report.samples[0].fragmentation, 0.0);
+        let last = report.samples.last().unwrap();
+        assert_eq!(last.free_bytes, manager.free_bytes());
+        assert_eq!(last.fragmentation, manager.fragmentation());
+        assert!((0.0..1.0).contains(&manager.fragmentation()));
+    }
+
+    /// Tests random operation sequences against a `HashMap` reference model.
+    ///
+    /// - Generates seeded sequences of inserts, reads, updates, deletes, copies, and renames over a few IDs.
+    /// - Expects every `OpResult` to match what the model predicts, and the final contents to match it.
+    /// - Expects `apply_all` to apply a whole batch, or nothing if any operation in it fails.
+    #[test]
+    fn test_oracle() {
+        use crate::erase::splitmix64;
+        use crate::operation::{OpResult, Operation};
+
+        /// What `op` should return, applied to the model. Blocks never grow,
+        /// and a shrinking update leaves zeros after the new data.
+        fn model_apply(model: &mut HashMap<u16, Vec<u8>>, op: &Operation) -> OpResult {
+            let key = |id: BlockId| id as u16;
+            match op {
+                Operation::Insert { id, data } if !model.contains_key(&key(*id)) => {
+                    model.insert(key(*id), data.clone());
+                    OpResult::Done
+                }
+                Operation::Read { id } => model.get(&key(*id)).map_or(OpResult::Failed, |data| OpResult::Data(data.clone())),
+                Operation::Update { id, data } => match model.get_mut(&key(*id)) {
+                    Some(block) if data.len() <= block.len() => {
+                        block[..data.len()].copy_from_slice(data);
+                        block[data.len()..].fill(0);
+                        OpResult::Done
+                    }
+                    _ => OpResult::Failed,
+                },
+                Operation::Delete { id } => model.remove(&key(*id)).map_or(OpResult::Failed, |_| OpResult::Done),
+                Operation::Copy { src, dst } if !model.contains_key(&key(*dst)) => match model.get(&key(*src)).cloned() {
+                    Some(data) => {
+                        model.insert(key(*dst), data);
+                        OpResult::Done
+                    }
+                    None => OpResult::Failed,
+                },
+                Operation::Rename { from, to } if !model.contains_key(&key(*to)) => match model.remove(&key(*from)) {
+                    Some(data) => {
+                        model.insert(key(*to), data);
+                        OpResult::Done
+                    }
+                    None => OpResult::Failed,
+                },
+                _ => OpResult::Failed,
+            }
+        }
+
+        for seed in 0..50u64 {
+            let mut rng = seed;
+            let mut random = |n: u64| splitmix64(&mut rng) % n;
+            let mut manager = MemoryManager::new();
+            let mut model = HashMap::new();
+            for step in 0..200 {
+                let id = random(8) as BlockId;
+                let other = random(8) as BlockId;
+                let data: Vec<u8> = (0..random(24)).map(|_| random(256) as u8).collect();
+                let op = match random(6) {
+                    0 => Operation::Insert { id, data },
+                    1 => Operation::Read { id },
+                    2 => Operation::Update { id, data },
+                    3 => Operation::Delete { id },
+                    4 => Operation::Copy { src: id, dst: other },
+                    _ => Operation::Rename { from: id, to: other },
+                };
+                assert_eq!(manager.apply_one(&op), model_apply(&mut model, &op), "seed {} step {}: {}", seed, step, op);
+            }
+            for id in 0..8 {
+                assert_eq!(manager.read(id), model.get(&(id as u16)).cloned(), "seed {} block {}", seed, id);
+            }
+        }
+
+        let mut manager = MemoryManager::new();
+        let batch = [Operation::Insert { id: 1, data: b"one".to_vec() }, Operation::Read { id: 1 }];
+        assert_eq!(manager.apply_all(&batch), Ok(vec![OpResult::Done, OpResult::Data(b"one".to_vec())]));
+        let batch = [Operation::Insert { id: 2, data: b"two".to_vec() }, Operation::Delete { id: 1 }, Operation::Read { id: 3 }];
+        assert_eq!(manager.apply_all(&batch), Err(2));
+        assert_eq!((manager.read(1), manager.read(2)), (Some(b"one".to_vec()), None));
+        assert!(!manager.in_transaction());
+    }
+
+    /// Tests aliasing blocks with `share`.
+    ///
+    /// - Shares a block twice and expects the aliases to read the same bytes from the same region.
+    /// - Expects deleting aliases to keep the bytes until the last one is gone.
+    /// - Expects updating an alias to give it a private copy, leaving the others unchanged.
+    /// - Expects invariants to hold for aliases of a block placed off the allocator's unit boundaries.
+    #[test]
+    fn test_share() {
+        let mut manager = MemoryManager::with_capacity(64);
+        manager.insert(0, b"shared bytes".to_vec()).unwrap();
+        let free = manager.free_bytes();
+
+        let first = manager.share(0).unwrap();
+        let second = manager.share(first).unwrap();
+        assert_eq!((first, second), (1, 2));
+        assert_eq!(manager.free_bytes(), free);
+        assert_eq!(manager.refcount(0), Some(3));
+        assert_eq!(manager.read(second), Some(b"shared bytes".to_vec()));
+        assert_eq!(manager.share_into(0, first), None);
+        assert_eq!(manager.share(9), None);
+
+        // Copy-on-write: only the updated alias changes
+        manager.update(first, b"private".to_vec()).unwrap();
+        assert_eq!(manager.free_bytes(), free - 12);
+        assert_eq!(manager.refcount(0), Some(2));
+        assert_eq!(manager.read(0), Some(b"shared bytes".to_vec()));
+
+        manager.delete(0).unwrap();
+        assert_eq!(manager.read(second), Some(b"shared bytes".to_vec()));
+        assert_eq!(manager.refcount(second), Some(1));
+        manager.delete(second).unwrap();
+        assert_eq!(manager.free_bytes(), free);
+        manager.check_invariants().unwrap();
+
+        // A shared region that does not start on a bitmap cell boundary
+        let mut manager = MemoryManager::with_allocator(Box::new(crate::bitmap::BitmapAllocator::new(64, 8)));
+        manager.insert_at(14, 3, vec![1; 4]).unwrap();
+        assert_eq!(manager.share(14), Some(0));
+        manager.check_invariants().unwrap();
+    }
+
+    /// Tests the segmentation model.
+    ///
+    /// - Spawns two processes and expects each segment to be a block whose base and limit match its allocation.
+    /// - Expects reads and writes to stay inside their own segment and process.
+    /// - Expects accesses past a limit and writes to code to be refused with a descriptive error.
+    /// - Expects a process that does not fit to leave nothing behind, and `exit` to free its segments.
+    #[test]
+    fn test_segmentation() {
+        use crate::segmentation::{SegmentError, SegmentType, Segmentation};
+
+        let mut segments = Segmentation::new(MemoryManager::with_capacity(128));
+        let first = segments.spawn(b"code", 16, 8).unwrap();
+        let second = segments.spawn(b"other", 32, 8).unwrap();
+        assert_ne!(first, second);
+
+        let data = segments.segment(first, SegmentType::Data).unwrap();
+        assert_eq!((data.limit, data.writable), (16, true));
+        assert_eq!(segments.manager().allocations[&data.block].start, data.base);
+        assert_eq!(segments.translate(first, SegmentType::Data, 3), Ok(data.base + 3));
+        assert!(!segments.segment(second, SegmentType::Code).unwrap().writable);
+
+        segments.write(first, SegmentType::Data, 12, b"abcd").unwrap();
+        assert_eq!(segments.read(first, SegmentType::Data, 10, 6).unwrap(), b"\0\0abcd");
+        assert_eq!(segments.read(second, SegmentType::Data, 12, 4).unwrap(), vec![0; 4]);
+        assert_eq!(segments.read(second, SegmentType::Code, 0, 5).unwrap(), b"other");
+
+        let overflow = segments.write(first, SegmentType::Data, 14, b"xyz").unwrap_err();
+        assert_eq!(overflow, SegmentError::LimitExceeded { pid: first, segment: SegmentType::Data, offset: 14, len: 3, limit: 16 });
+        assert_eq!(
+            overflow.to_string(),
+            format!("segmentation fault in process {}: 3 bytes at offset 0xe exceed the data segment limit of 0x10", first)
+        );
+        assert!(matches!(segments.translate(first, SegmentType::Stack, 8), Err(SegmentError::LimitExceeded { .. })));
+        assert_eq!(segments.write(first, SegmentType::Code, 0, b"x"), Err(SegmentError::ReadOnly { pid: first, segment: SegmentType::Code }));
+        assert_eq!(segments.read(first, SegmentType::Data, 10, 6).unwrap(), b"\0\0abcd");
+
+        let blocks = segments.manager().stats().allocations;
+        assert!(matches!(segments.spawn(b"big", 64, 64), Err(SegmentError::Memory(_))));
+        assert_eq!(segments.manager().stats().allocations, blocks);
+
+        segments.exit(first).unwrap();
+        assert_eq!(segments.read(first, SegmentType::Data, 0, 1), Err(SegmentError::NoSuchProcess { pid: first }));
+        assert_eq!(segments.manager().stats().allocations, 3);
+    }
+
+    /// Tests the heap-free `Pool` and `MemoryManager::from_slice`.
+    ///
+    /// - Fills a pool's table and buffer and expects the matching errors.
+    /// - Expects deletes to zero their bytes and leave a gap the next insert reuses first-fit.
+    /// - Expects gaps freed in any order to coalesce, without more free-list entries than blocks.
+    /// - Expects a manager built on a caller's buffer to store its blocks there.
+    #[test]
+    fn test_pool() {
+        use crate::pool::{Pool, PoolError};
+
+        let mut buffer = [0xffu8; 16];
+        let mut pool: Pool<'_, 3> = Pool::from_slice(&mut buffer);
+        pool.insert(1, b"aaaa").unwrap();
+        pool.insert(2, b"bbbbbb").unwrap();
+        assert_eq!(pool.insert(2, b"x"), Err(PoolError::DuplicateId { id: 2 }));
+        assert_eq!(pool.insert(3, b"ccccccc"), Err(PoolError::OutOfSpace { requested: 7, largest_free: 6 }));
+        pool.insert(3, b"cc").unwrap();
+        assert_eq!(pool.insert(4, b""), Err(PoolError::TableFull { blocks: 3 }));
+        assert_eq!((pool.len(), pool.free_bytes()), (3, 4));
+
+        pool.update(2, b"bb").unwrap();
+        assert_eq!(pool.read(2), Some(&b"bb"[..]));
+        assert_eq!(pool.update(2, b"1234567"), Err(PoolError::TooLarge { requested: 7, capacity: 6 }));
+
+        pool.delete(1).unwrap();
+        assert_eq!(pool.delete(1), Err(PoolError::NotFound { id: 1 }));
+        pool.insert(5, b"ddd").unwrap();
+        let blocks: Vec<(BlockId, &[u8])> = pool.iter().collect();
+        assert_eq!(blocks, [(5, &b"ddd"[..]), (2, &b"bb"[..]), (3, &b"cc"[..])]);
+        assert_eq!(buffer, *b"ddd\0bb\0\0\0\0cc\0\0\0\0");
+
+        let mut buffer = [0u8; 12];
+        let mut pool: Pool<'_, 3> = Pool::from_slice(&mut buffer);
+        for (id, data) in [(1, b"aaaa"), (2, b"bbbb"), (3, b"cccc")] {
+            pool.insert(id, data).unwrap();
+        }
+        pool.delete(1).unwrap();
+        pool.delete(3).unwrap();
+        pool.delete(2).unwrap();
+        pool.insert(4, &[4; 12]).unwrap();
+        assert_eq!(pool.free_bytes(), 0);
+
+        let memory: &'static mut [u8] = Box::leak(vec![0xff; 32].into_boxed_slice());
+        let address = memory.as_ptr();
+        let mut manager = MemoryManager::from_slice(memory);
+        assert_eq!(manager.free_bytes(), 32);
+        manager.insert(1, b"static".to_vec()).unwrap();
+        assert_eq!(manager.read(1), Some(b"static".to_vec()));
+        let start = manager.allocations[&1].start;
+        assert_eq!(manager.memory.as_ptr(), address);
+        assert_eq!(&manager.memory[start..start + 6], b"static");
+    }
+
+    /// Tests `AsyncMemoryManager` from concurrent tokio tasks.
+    ///
+    /// - Inserts from several tasks at once and expects every block to arrive.
+    /// - Expects `with` to run a group of operations as one command.
+    /// - Expects a panicking command to fail only its own caller, leaving the worker serving.
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_async_manager() {
+        use crate::async_manager::AsyncMemoryManager;
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(async {
+            let manager = AsyncMemoryManager::new(MemoryManager::new());
+            let tasks: Vec<_> = (0..8)
+                .map(|id| {
+                    let manager = manager.clone();
+                    tokio::spawn(async move { manager.insert(id, vec![id as u8; 4]).await.is_some() })
+                })
+                .collect();
+            for task in tasks {
+                assert!(task.await.unwrap());
+            }
+            assert_eq!(manager.read(5).await, Some(vec![5; 4]));
+            assert_eq!(manager.sequence().await, 8);
+
+            let moved = manager.with(|manager| manager.rename(1, 10).and_then(|_| manager.delete(2))).await;
+            assert_eq!(moved, Some(()));
+            assert_eq!(manager.update(10, vec![1]).await, Some(()));
+
+            let panicking = manager.clone();
+            let failed = tokio::spawn(async move { panicking.with(|_| panic!("boom")).await }).await;
+            assert!(failed.unwrap_err().is_panic());
+            assert_eq!(manager.delete(10).await, Some(()));
+            assert_eq!(manager.read(10).await, None);
+        });
+    }
+
+    /// Tests recording a trace and replaying it.
+    ///
+    /// - Records successful and failed operations, reads, and a rolled-back transaction.
+    /// - Expects each line to carry its result and the trace to end with the state hash.
+    /// - Expects `replay_trace` to reproduce every result and the final state.
+    /// - Expects a tampered result to be reported as a divergence, and an unfinished trace to be refused.
+    #[test]
+    fn test_trace() {
+        use crate::replay::replay_trace;
+
+        let path = std::env::temp_dir().join(format!("mm_trace_{}.log", std::process::id()));
+        let mut manager = MemoryManager::new();
+        manager.start_trace(&path).unwrap();
+        assert!(manager.start_trace(&path).is_err());
+        manager.insert(1, b"one".to_vec()).unwrap();
+        assert_eq!(manager.insert(1, b"again".to_vec()), None);
+        manager.read(1).unwrap();
+        assert_eq!(manager.read(2), None);
+        manager.begin_transaction().unwrap();
+        manager.copy(1, 2).unwrap();
+        manager.rollback().unwrap();
+        manager.insert(3, Vec::new()).unwrap();
+        manager.read(3).unwrap();
+        manager.update(1, b"1".to_vec()).unwrap();
+        manager.insert_aligned(4, vec![4; 4], 16).unwrap();
+        assert_eq!(manager.stop_trace().unwrap(), 8);
+        assert!(!manager.is_tracing());
+
+        // Inserts refused for a duplicate ID are not recorded
+        let trace = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(lines[..3], ["INSERT 1 6f6e65 -> OK", "READ 1 -> OK 6f6e65", "READ 2 -> ERR"]);
+        assert_eq!(lines[3..6], ["BEGIN", "COPY 1 2 -> OK", "ROLLBACK"]);
+        assert_eq!(lines[9], "INSERT_ALIGNED 4 16 04040404 -> OK");
+        assert_eq!(lines[10], format!("END {:016x}", manager.state_hash()));
+
+        let report = replay_trace(&path).unwrap();
+        assert!(report.matches(), "{:?}", report);
+        assert_eq!(report.operations, 8);
+
+        std::fs::write(&path, trace.replace("READ 2 -> ERR", "READ 2 -> OK 00")).unwrap();
+        assert_eq!(replay_trace(&path).unwrap().diverged, Some(3));
+        std::fs::write(&path, &trace[..trace.find("END").unwrap()]).unwrap();
+        assert!(replay_trace(&path).is_err());
+
+        // Replays start empty, so a trace cannot start from existing blocks
+        assert!(manager.start_trace(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Tests `fill`, `fill_range`, and `copy_within`.
+    ///
+    /// - Edits a plain block in place and expects its checksum to stay valid.
+    /// - Expects overlapping copies to behave like `memmove`, and out-of-range edits to change nothing.
+    /// - Expects encrypted blocks to be edited through `update`, and undo to restore data edited in place.
+    #[test]
+    fn test_fill_and_copy_within() {
+        let mut manager = MemoryManager::new();
+        manager.insert(1, b"abcdefgh".to_vec()).unwrap();
+        let start = manager.allocations[&1].start;
+
+        manager.fill_range(1, 2, 3, b'x').unwrap();
+        assert_eq!(manager.read(1), Some(b"abxxxfgh".to_vec()));
+        assert_eq!(manager.allocations[&1].start, start);
+        manager.copy_within(1, 0, 3, 5).unwrap();
+        assert_eq!(manager.read(1), Some(b"abxabxxx".to_vec()));
+        manager.copy_within(1, 3, 1, 4).unwrap();
+        assert_eq!(manager.read(1), Some(b"aabxxxxx".to_vec()));
+
+        assert_eq!(manager.fill_range(1, 6, 3, b'!'), None);
+        assert_eq!(manager.copy_within(1, 0, 5, 4), None);
+        assert_eq!(manager.fill(2, 0), None);
+        assert_eq!(manager.read(1), Some(b"aabxxxxx".to_vec()));
+        manager.fill(1, 0).unwrap();
+        assert_eq!(manager.read(1), Some(vec![0; 8]));
+        manager.check_invariants().unwrap();
+
+        // Encrypted bytes in memory are not the data, so edits are re-encrypted
+        let mut manager = MemoryManager::with_encryption([7; 32]);
+        manager.insert(1, b"secret".to_vec()).unwrap();
+        manager.fill_range(1, 0, 3, b'*').unwrap();
+        assert_eq!(manager.read(1), Some(b"***ret".to_vec()));
+        let start = manager.allocations[&1].start;
+        assert_ne!(&manager.memory[start..start + 6], b"***ret");
+
+        let mut manager = MemoryManager::new();
+        manager.set_history_depth(64);
+        manager.insert(1, b"keep".to_vec()).unwrap();
+        manager.fill(1, b'-').unwrap();
+        manager.undo().unwrap();
+        assert_eq!(manager.read(1), Some(b"keep".to_vec()));
+    }
+
+    /// Tests memory pressure levels and threshold callbacks.
+    ///
+    /// - Fills memory and expects `pressure` to step from `Low` through `Medium` to `High`.
+    /// - Expects threshold callbacks only on crossings, in both directions.
+    /// - Expects each level change to be reported as `Event::PressureChanged`.
+    #[test]
+    fn test_pressure() {
+        use crate::events::Event;
+        use crate::pressure::{Crossing, Pressure};
+        use std::sync::{Arc, Mutex};
+
+        let mut manager = MemoryManager::with_capacity(100);
+        let crossings = Arc::new(Mutex::new(Vec::new()));
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let seen = crossings.clone();
+        manager.on_threshold(0.8, move |crossing: &Crossing| seen.lock().unwrap().push((crossing.rising, crossing.utilization)));
+        let seen = changes.clone();
+        manager.on_event(move |event| {
+            if let Event::PressureChanged { from, to } = event {
+                seen.lock().unwrap().push((*from, *to));
+            }
+        });
+
+        manager.insert(1, vec![0; 60]).unwrap();
+        assert_eq!((manager.utilization(), manager.pressure()), (0.6, Pressure::Low));
+        manager.insert(2, vec![0; 15]).unwrap();
+        assert_eq!(manager.pressure(), Pressure::Medium);
+        assert!(crossings.lock().unwrap().is_empty());
+        manager.insert(3, vec![0; 20]).unwrap();
+        assert_eq!(manager.pressure(), Pressure::High);
+        manager.insert(4, vec![0; 1]).unwrap();
+        manager.delete(1).unwrap();
+        assert_eq!(manager.pressure(), Pressure::Low);
+
+        assert_eq!(*crossings.lock().unwrap(), [(true, 0.95), (false, 0.36)]);
+        let expected = [(Pressure::Low, Pressure::Medium), (Pressure::Medium, Pressure::High), (Pressure::High, Pressure::Low)];
+        assert_eq!(*changes.lock().unwrap(), expected);
+
+        manager.set_pressure_levels(0.1, 0.3);
+        assert_eq!(manager.pressure(), Pressure::High);
+    }
+
+    /// Tests routing and rebalancing across a cluster of managers.
+    ///
+    /// - Expects every block to be readable through the cluster, on the node `node_of` names.
+    /// - Adds a node and expects only some blocks to move, all of them to the new node.
+    /// - Removes a node and expects its blocks to move to the others.
+    /// - Expects a rebalance that does not fit to leave every block where it was.
+    #[test]
+    fn test_cluster() {
+        use crate::cluster::{Cluster, ClusterError};
+
+        let mut cluster = Cluster::new(64);
+        assert_eq!(cluster.insert(1, vec![1; 16]), None);
+        let a = cluster.add_node(MemoryManager::with_capacity(4096)).unwrap();
+        let b = cluster.add_node(MemoryManager::with_capacity(4096)).unwrap();
+        for id in 0..100 {
+            cluster.insert(id, vec![id as u8; 16]).unwrap();
+        }
+        let placement = |cluster: &Cluster| (0..100).map(|id| cluster.node_of(id).unwrap()).collect::<Vec<_>>();
+        let before = placement(&cluster);
+        assert!(before.contains(&a) && before.contains(&b));
+        for id in 0..100 {
+            assert!(cluster.node(before[id as usize]).unwrap().exists(id));
+        }
+
+        let c = cluster.add_node(MemoryManager::with_capacity(4096)).unwrap();
+        let after = placement(&cluster);
+        let moved = (0..100).filter(|&i| before[i] != after[i]).count();
+        assert!(moved > 0 && moved < 100);
+        assert!((0..100).all(|i| before[i] == after[i] || after[i] == c));
+        assert_eq!(cluster.node_stats().values().map(|stats| stats.allocations).sum::<usize>(), 100);
+        assert_eq!(cluster.node_stats()[&c].allocations, moved);
+        for id in 0..100 {
+            assert_eq!(cluster.read(id), Some(vec![id as u8; 16]));
+        }
+        cluster.update(5, vec![50; 16]).unwrap();
+        cluster.delete(6).unwrap();
+        assert_eq!(cluster.read(6), None);
+
+        let removed = cluster.remove_node(a).unwrap();
+        assert_eq!(removed.stats().allocations, 0);
+        assert_eq!(cluster.nodes(), vec![b, c]);
+        assert_eq!(cluster.read(5), Some(vec![50; 16]));
+        assert!((0..100).filter(|&id| id != 6).all(|id| cluster.read(id) == Some(vec![if id == 5 { 50 } else { id as u8 }; 16])));
+
+        // The new node is too small to take its share, so nothing moves
+        let stats = cluster.node_stats();
+        assert!(matches!(cluster.add_node(MemoryManager::with_capacity(32)), Err(ClusterError::Rebalance { .. })));
+        assert_eq!(cluster.nodes(), vec![b, c]);
+        assert_eq!(cluster.node_stats(), stats);
+
+        cluster.remove_node(b).unwrap();
+        assert_eq!(cluster.remove_node(c).err(), Some(ClusterError::LastNode { node: c }));
+        assert_eq!(cluster.remove_node(b).err(), Some(ClusterError::NoSuchNode { node: b }));
+        assert_eq!(cluster.node_stats()[&c].allocations, 99);
+    }
+
+    /// Tests a serde round trip through JSON.
+    ///
+    /// - Serializes a manager with freed gaps, tags, and compression, and deserializes it.
+    /// - Expects the same `state_hash`, data, and tags, and intact invariants.
+    /// - Expects a state whose free list was tampered with to be refused.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut manager = MemoryManager::with_compression();
+        for id in 0..8 {
+            manager.insert(id, vec![id as u8; 40]).unwrap();
+        }
+        manager.delete(2).unwrap();
+        manager.delete(5).unwrap();
+        manager.set_tag(3, "kind", "config").unwrap();
+
+        let json = serde_json::to_string(&manager).unwrap();
+        let restored: MemoryManager = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.state_hash(), manager.state_hash());
+        restored.check_invariants().unwrap();
+        assert_eq!(restored.read(7), Some(vec![7; 40]));
+        assert_eq!(restored.tag(3, "kind"), Some("config"));
+        assert_eq!(restored.read(2), None);
+
+        let mut state: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let size = state["free"][0][1].as_u64().unwrap();
+        state["free"][0][1] = (size + 1).into();
+        assert!(serde_json::from_value::<MemoryManager>(state).is_err());
+    }
+
+    /// Tests that empty blocks do not split the free space around them.
+    ///
+    /// - Leaves an empty block inside a free gap and compacts.
+    /// - Expects one coalesced free region, intact invariants, and the whole
+    ///   free space usable by a single insert.
+    #[test]
+    fn test_rebuild_ignores_empty_blocks() {
+        let mut manager = MemoryManager::new();
+        manager.insert(1, vec![1; 5]).unwrap();
+        manager.insert(2, vec![2; 5]).unwrap();
+        manager.insert(9, Vec::new()).unwrap();
+        manager.delete(1).unwrap();
+        manager.compact();
+
+        assert_eq!(manager.allocator.regions(), vec![(5, 65530)]);
+        manager.check_invariants().unwrap();
+        manager.insert(3, vec![3; 65530]).unwrap();
+    }
+
+    /// Tests that corrupted blocks are not split or merged.
+    ///
+    /// - Flips a bit in one of two adjacent blocks.
+    /// - Expects `split` and `merge` to refuse, leaving the corruption detectable.
+    #[test]
+    fn test_split_merge_corrupted() {
+        let mut manager = MemoryManager::with_capacity(100);
+        manager.insert(1, b"Hello".to_vec()).unwrap();
+        manager.insert(2, b"World".to_vec()).unwrap();
+        manager.flip_bit(manager.allocations[&1].start, 0);
+
+        assert_eq!(manager.split(1, 2), None);
+        assert_eq!(manager.merge(1, 2), None);
+        assert_eq!(manager.merge_into(1, 2, 3), None);
+        assert_eq!(manager.verify_all(), vec![1]);
+
+        manager.split(2, 2).unwrap();
+        assert_eq!(manager.verify_all(), vec![1]);
+    }
+
+    /// Tests that file-backed managers refuse content-addressed blocks.
+    ///
+    /// - Opens a memory-mapped manager and expects `insert_content` to fail.
+    /// - Reopens it and expects an insert to land next to the existing block, not over it.
+    #[test]
+    fn test_content_file_backed() {
+        let path = std::env::temp_dir().join(format!("mm_content_{}.bin", std::process::id()));
+
+        {
+            let mut manager = MemoryManager::open_mmap(&path, 64).unwrap();
+            manager.insert(1, b"Hello".to_vec()).unwrap();
+            assert_eq!(manager.insert_content(b"World".to_vec()), None);
+            assert!(manager.content.is_empty());
+        }
+
+        let mut manager = MemoryManager::open_mmap(&path, 64).unwrap();
+        assert_eq!(manager.insert_content(b"World".to_vec()), None);
+        manager.insert(2, b"Again".to_vec()).unwrap();
+        assert_eq!(manager.read(1), Some(b"Hello".to_vec()));
+        assert_eq!(manager.read(2), Some(b"Again".to_vec()));
+        manager.check_invariants().unwrap();
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(path.with_extension("bin.idx"));
+    }
+
+    /// Tests deleting content-addressed blocks with canaries on.
+    ///
+    /// - Overwrites a guard of a content block, then drops its last reference.
+    /// - Expects the delete to go ahead, erase the bytes, and free the block together with its guards.
+    #[test]
+    fn test_delete_content_canaries() {
+        let mut manager = MemoryManager::with_canaries();
+        let capacity = manager.free_bytes();
+        let hash = manager.insert_content(b"Hello".to_vec()).unwrap();
+        let start = manager.content[&hash].block.start;
+        manager.flip_bit(start + 5, 0);
+        assert_eq!(manager.check_integrity().len(), 1);
+
+        assert_eq!(manager.delete_content(hash), Some(0));
+        assert_eq!(manager.read_content(hash), None);
+        assert_eq!(manager.free_bytes(), capacity);
+        assert_eq!(&manager.memory[start..start + 5], &[0; 5]);
+        manager.check_invariants().unwrap();
+    }
+
+    /// Tests undo and redo through the text protocol.
+    ///
+    /// - Deletes a block by mistake and expects `UNDO` to bring it back and `REDO` to delete it again.
+    /// - Expects both to fail with a reason once there is nothing left, or with history off.
+    #[test]
+    fn test_execute_undo_redo() {
+        let mut manager = MemoryManager::new();
+        assert_eq!(manager.execute("INSERT 1 48656c6c6f"), Some("OK".to_string()));
+        assert_eq!(manager.execute("UNDO"), Some("ERR nothing to undo".to_string()));
+
+        manager.set_history_depth(8);
+        assert_eq!(manager.execute("DELETE 1"), Some("OK".to_string()));
+        assert_eq!(manager.execute("undo"), Some("OK".to_string()));
+        assert_eq!(manager.execute("READ 1"), Some("OK 48656c6c6f".to_string()));
+        assert_eq!(manager.execute("UNDO"), Some("ERR nothing to undo".to_string()));
+        assert_eq!(manager.execute("REDO"), Some("OK".to_string()));
+        assert_eq!(manager.execute("READ 1"), Some("ERR ID not found".to_string()));
+        assert_eq!(manager.execute("REDO"), Some("ERR nothing to redo".to_string()));
+    }
 }
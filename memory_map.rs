@@ -0,0 +1,140 @@
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// Number of cells in the bar drawn by `visualize()`.
+pub const VISUAL_CELLS: usize = 128;
+
+/// What occupies a segment of memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SegmentKind {
+    /// The block stored under this ID.
+    Block(BlockId),
+    /// The content-addressed block with this hash.
+    Content(u64),
+    /// Free space.
+    Free,
+}
+
+/// A contiguous run of memory, as listed by `memory_map()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub start: usize,      // Index of the first byte
+    pub size: usize,       // Number of bytes
+    pub kind: SegmentKind, // What the bytes hold
+}
+
+impl MemoryManager {
+    /// Returns the free regions of memory as `(start, size)` pairs in address order.
+    ///
+    /// Adjacent free space is always merged into one region, so the number
+    /// of regions is a direct measure of fragmentation.
+    pub fn free_regions(&self) -> impl Iterator<Item = (usize, usize)> {
+        self.allocator.regions().into_iter()
+    }
+
+    /// Lists every block and free region in address order.
+    ///
+    /// # Returns
+    ///
+    /// One `Segment` per free region and per block that occupies memory.
+    /// Inline and empty blocks take no memory and are left out. Blocks that
+    /// share a region through dedup each get a segment, with the same start,
+    /// ordered by ID. With an allocator coarser than a byte, the slack after
+    /// a block up to the end of its last unit belongs to no segment, and so
+    /// do guard bytes when canaries are on.
+    pub fn memory_map(&self) -> Vec<Segment> {
+        let blocks = self
+            .allocations
+            .iter()
+            .filter(|(_, block)| block.inline.is_none())
+            .map(|(&id, block)| Segment { start: block.start, size: block.size, kind: SegmentKind::Block(id) });
+        let content = self.content.iter().map(|(&hash, entry)| Segment {
+            start: entry.block.start,
+            size: entry.block.size,
+            kind: SegmentKind::Content(hash),
+        });
+        let free = self.free_regions().map(|(start, size)| Segment { start, size, kind: SegmentKind::Free });
+
+        let mut map: Vec<Segment> = blocks.chain(content).filter(|segment| segment.size > 0).chain(free).collect();
+        // Stable, so blocks sharing a region stay in ID order
+        map.sort_by_key(|segment| segment.start);
+        map
+    }
+
+    /// Draws memory as a bar of `VISUAL_CELLS` characters. See `visualize_cells`.
+    pub fn visualize(&self) -> String {
+        self.visualize_cells(VISUAL_CELLS)
+    }
+
+    /// Draws memory as a bar of up to `cells` characters, followed by a legend.
+    ///
+    /// # Returns
+    ///
+    /// A header with the bytes per cell, the bar, and one legend line per
+    /// block, e.g.:
+    ///
+    /// ```text
+    /// 65535 bytes, 512 per cell
+    /// [aaaB|c......]
+    /// a  ID 1 at 0, 1500 bytes
+    /// B  ID 2 at 1500, 600 bytes, pinned
+    /// c  ID 3 at 2100, 400 bytes
+    /// .  free  |  several blocks
+    /// ```
+    ///
+    /// # Behavior
+    ///
+    /// Each block gets a letter in address order, uppercase if it is pinned.
+    /// A cell shows `.` if it is entirely free, the block's letter if it
+    /// holds part of exactly one block (free bytes beside it are not shown),
+    /// and `|` where several blocks meet. Letters repeat after 26 blocks;
+    /// the legend gives each block's address to tell them apart. Blocks
+    /// sharing a region through dedup share a letter. Inline and empty
+    /// blocks take no memory and are not drawn.
+    pub fn visualize_cells(&self, cells: usize) -> String {
+        let capacity = self.memory.len();
+        let per_cell = capacity.div_ceil(cells.max(1)).max(1);
+        let cells = capacity.div_ceil(per_cell);
+
+        // Blocks sharing a region are listed together, in ID order
+        let mut regions: Vec<(usize, usize, Vec<SegmentKind>)> = Vec::new();
+        for segment in self.memory_map().into_iter().filter(|segment| segment.kind != SegmentKind::Free) {
+            match regions.last_mut() {
+                Some((start, size, kinds)) if *start == segment.start && *size == segment.size => kinds.push(segment.kind),
+                _ => regions.push((segment.start, segment.size, vec![segment.kind])),
+            }
+        }
+
+        let mut bar = vec![None; cells];
+        let mut legend = String::new();
+        for (index, (start, size, kinds)) in regions.iter().enumerate() {
+            let pinned = kinds.iter().any(|kind| matches!(kind, SegmentKind::Block(id) if self.is_pinned(*id)));
+            let letter = (b'a' + (index % 26) as u8) as char;
+            let symbol = if pinned { letter.to_ascii_uppercase() } else { letter };
+            for cell in &mut bar[start / per_cell..=(start + size - 1) / per_cell] {
+                *cell = match cell {
+                    None => Some(symbol),
+                    Some(_) => Some('|'),
+                };
+            }
+
+            let ids: Vec<String> = kinds
+                .iter()
+                .map(|kind| match kind {
+                    SegmentKind::Block(id) => id.to_string(),
+                    SegmentKind::Content(hash) => format!("content {:016x}", hash),
+                    SegmentKind::Free => unreachable!(),
+                })
+                .collect();
+            let label = match kinds.as_slice() {
+                [SegmentKind::Block(_)] => "ID ",
+                [SegmentKind::Block(_), ..] => "IDs ",
+                _ => "",
+            };
+            let pinned = if pinned { ", pinned" } else { "" };
+            legend.push_str(&format!("{}  {}{} at {}, {} bytes{}\n", symbol, label, ids.join(", "), start, size, pinned));
+        }
+
+        let bar: String = bar.into_iter().map(|cell| cell.unwrap_or('.')).collect();
+        format!("{} bytes, {} per cell\n[{}]\n{}.  free  |  several blocks\n", capacity, per_cell, bar, legend)
+    }
+}
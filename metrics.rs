@@ -0,0 +1,136 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::memory_manager::MemoryManager;
+use crate::subscriptions::Change;
+
+/// Upper bounds, in bytes, of the allocation size histogram buckets.
+pub const SIZE_BUCKETS: [usize; 7] = [16, 64, 256, 1024, 4096, 16384, 65536];
+
+/// Counters accumulated since the manager was created.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub inserts: u64,                // Blocks inserted, copied, or renamed into place
+    pub updates: u64,                // Successful updates
+    pub deletes: u64,                // Blocks deleted or renamed away
+    pub reads: u64,                  // Successful reads
+    pub failed_allocations: u64,     // Requests for memory the allocator could not satisfy
+    pub bytes_compacted: u64,        // Bytes moved by `compact()`
+    pub allocation_sizes: Histogram, // Sizes of successful allocations
+}
+
+/// A distribution of sizes in the buckets of `SIZE_BUCKETS`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Histogram {
+    pub buckets: Vec<u64>, // Count of values up to each bound in `SIZE_BUCKETS`, cumulative
+    pub count: u64,        // Number of values recorded
+    pub sum: u64,          // Total of the values recorded
+}
+
+/// The live counters behind `Metrics`.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    inserts: AtomicU64,
+    updates: AtomicU64,
+    deletes: AtomicU64,
+    reads: AtomicU64,
+    failed_allocations: AtomicU64,
+    bytes_compacted: AtomicU64,
+    size_buckets: [AtomicU64; SIZE_BUCKETS.len()],
+    size_count: AtomicU64,
+    size_sum: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn read(&self) {
+        self.reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn compacted(&self, bytes: usize) {
+        self.bytes_compacted.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records the outcome of asking the allocator for `size` bytes.
+    pub(crate) fn allocation(&self, size: usize, succeeded: bool) {
+        if !succeeded {
+            self.failed_allocations.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if let Some(bucket) = SIZE_BUCKETS.iter().position(|&bound| size <= bound) {
+            self.size_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        self.size_count.fetch_add(1, Ordering::Relaxed);
+        self.size_sum.fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn change(&self, change: &Change) {
+        let counter = match change {
+            Change::Inserted { .. } => &self.inserts,
+            Change::Updated { .. } => &self.updates,
+            Change::Deleted { .. } => &self.deletes,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl MemoryManager {
+    /// Returns the counters accumulated since the manager was created.
+    ///
+    /// # Behavior
+    ///
+    /// Counters only go up: snapshot restores, rollbacks, and undo do not
+    /// rewind them. Changes made by undo, redo, and replay are counted like
+    /// any other.
+    pub fn metrics(&self) -> Metrics {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let counters = &self.counters;
+        let mut buckets = Vec::with_capacity(SIZE_BUCKETS.len());
+        let mut total = 0;
+        for bucket in &counters.size_buckets {
+            total += load(bucket);
+            buckets.push(total);
+        }
+        Metrics {
+            inserts: load(&counters.inserts),
+            updates: load(&counters.updates),
+            deletes: load(&counters.deletes),
+            reads: load(&counters.reads),
+            failed_allocations: load(&counters.failed_allocations),
+            bytes_compacted: load(&counters.bytes_compacted),
+            allocation_sizes: Histogram { buckets, count: load(&counters.size_count), sum: load(&counters.size_sum) },
+        }
+    }
+
+    /// Renders `metrics()` and the current `stats()` in the Prometheus text
+    /// exposition format, with every name prefixed by `memory_manager_`.
+    pub fn metrics_text(&self) -> String {
+        let metrics = self.metrics();
+        let stats = self.stats();
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+            out.push_str(&format!("# HELP memory_manager_{} {}\n", name, help));
+            out.push_str(&format!("# TYPE memory_manager_{} {}\n", name, kind));
+            out.push_str(&format!("memory_manager_{} {}\n", name, value));
+        };
+        metric("inserts_total", "counter", "Blocks inserted.", metrics.inserts);
+        metric("updates_total", "counter", "Blocks updated.", metrics.updates);
+        metric("deletes_total", "counter", "Blocks deleted.", metrics.deletes);
+        metric("reads_total", "counter", "Successful reads.", metrics.reads);
+        metric("failed_allocations_total", "counter", "Allocations that found no space.", metrics.failed_allocations);
+        metric("compacted_bytes_total", "counter", "Bytes moved by compaction.", metrics.bytes_compacted);
+        metric("allocations", "gauge", "Live blocks.", stats.allocations as u64);
+        metric("used_bytes", "gauge", "Bytes of memory reserved by live blocks.", stats.used_bytes as u64);
+        metric("free_bytes", "gauge", "Bytes of memory not reserved.", self.free_bytes() as u64);
+        metric("capacity_bytes", "gauge", "Size of the memory block.", stats.capacity as u64);
+
+        let histogram = &metrics.allocation_sizes;
+        out.push_str("# HELP memory_manager_allocation_size_bytes Sizes of successful allocations.\n");
+        out.push_str("# TYPE memory_manager_allocation_size_bytes histogram\n");
+        for (bound, count) in SIZE_BUCKETS.iter().zip(&histogram.buckets) {
+            out.push_str(&format!("memory_manager_allocation_size_bytes_bucket{{le=\"{}\"}} {}\n", bound, count));
+        }
+        out.push_str(&format!("memory_manager_allocation_size_bytes_bucket{{le=\"+Inf\"}} {}\n", histogram.count));
+        out.push_str(&format!("memory_manager_allocation_size_bytes_sum {}\n", histogram.sum));
+        out.push_str(&format!("memory_manager_allocation_size_bytes_count {}\n", histogram.count));
+        out
+    }
+}
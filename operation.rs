@@ -0,0 +1,267 @@
+use std::fmt;
+use std::io;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// A single operation against a `MemoryManager`.
+///
+/// # Text format
+///
+/// Operations are written one per line, with data as lowercase hex:
+///
+/// ```text
+/// INSERT 1 48656c6c6f
+/// READ 1
+/// UPDATE 1 52757374
+/// COPY 1 2
+/// RENAME 2 3
+/// DELETE 1
+/// RESERVE 4 64
+/// INSERT_AT 5 128 52757374
+/// INSERT_ALIGNED 8 16 52757374
+/// PIN 8
+/// UNPIN 8
+/// COMPACT
+/// INSERT_CONTENT 52757374
+/// DELETE_CONTENT 8a1f6b0c3e2d4f57
+/// SPLIT 5 2 6 7
+/// MERGE 6 7 5
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Insert { id: BlockId, data: Vec<u8> },
+    Read { id: BlockId },
+    Update { id: BlockId, data: Vec<u8> },
+    Delete { id: BlockId },
+    Copy { src: BlockId, dst: BlockId },
+    Rename { from: BlockId, to: BlockId },
+    Reserve { id: BlockId, size: usize },
+    InsertAt { id: BlockId, start: usize, data: Vec<u8> },
+    InsertAligned { id: BlockId, alignment: usize, data: Vec<u8> },
+    Split { id: BlockId, offset: usize, first: BlockId, second: BlockId },
+    Merge { first: BlockId, second: BlockId, into: BlockId },
+    Pin { id: BlockId },
+    Unpin { id: BlockId },
+    Compact,
+    InsertContent { data: Vec<u8> },
+    DeleteContent { hash: u64 },
+}
+
+impl Operation {
+    /// Parses one line of the text format.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(op))` for an operation line.
+    /// - `Ok(None)` for blank lines and `#` comments.
+    /// - `Err(e)` if the line is malformed.
+    pub fn parse(line: &str) -> io::Result<Option<Operation>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("bad operation: {}", line));
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let id_at = |i: usize| fields.get(i).and_then(|id| id.parse::<BlockId>().ok()).ok_or_else(invalid);
+        let id = || id_at(1);
+        let data_at = |i: usize| fields.get(i).map_or(Some(Vec::new()), |hex| decode_hex(hex)).ok_or_else(invalid);
+        let data = || data_at(2);
+        let number = |i: usize| fields.get(i).and_then(|n| n.parse::<usize>().ok()).ok_or_else(invalid);
+
+        let op = match (fields[0].to_ascii_uppercase().as_str(), fields.len()) {
+            ("INSERT", 2 | 3) => Operation::Insert { id: id()?, data: data()? },
+            ("READ", 2) => Operation::Read { id: id()? },
+            ("UPDATE", 2 | 3) => Operation::Update { id: id()?, data: data()? },
+            ("DELETE", 2) => Operation::Delete { id: id()? },
+            ("COPY", 3) => Operation::Copy { src: id_at(1)?, dst: id_at(2)? },
+            ("RENAME", 3) => Operation::Rename { from: id_at(1)?, to: id_at(2)? },
+            ("RESERVE", 3) => Operation::Reserve { id: id()?, size: number(2)? },
+            ("INSERT_AT", 3 | 4) => Operation::InsertAt { id: id()?, start: number(2)?, data: data_at(3)? },
+            ("INSERT_ALIGNED", 3 | 4) => Operation::InsertAligned { id: id()?, alignment: number(2)?, data: data_at(3)? },
+            ("SPLIT", 5) => Operation::Split { id: id()?, offset: number(2)?, first: id_at(3)?, second: id_at(4)? },
+            ("MERGE", 4) => Operation::Merge { first: id_at(1)?, second: id_at(2)?, into: id_at(3)? },
+            ("PIN", 2) => Operation::Pin { id: id()? },
+            ("UNPIN", 2) => Operation::Unpin { id: id()? },
+            ("COMPACT", 1) => Operation::Compact,
+            ("INSERT_CONTENT", 1 | 2) => Operation::InsertContent { data: data_at(1)? },
+            ("DELETE_CONTENT", 2) => {
+                let hash = u64::from_str_radix(fields[1], 16).map_err(|_| invalid())?;
+                Operation::DeleteContent { hash }
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(Some(op))
+    }
+
+    /// Parses every operation in a text log, skipping blank lines and comments.
+    pub fn parse_all(contents: &str) -> io::Result<Vec<Operation>> {
+        let mut ops = Vec::new();
+        for line in contents.lines() {
+            if let Some(op) = Operation::parse(line)? {
+                ops.push(op);
+            }
+        }
+        Ok(ops)
+    }
+
+    /// Returns the ID this operation targets. For copies and renames this
+    /// is the source ID; for merges, the first block. `Compact` and the
+    /// content-addressed operations target no ID and return `None`.
+    pub fn id(&self) -> Option<BlockId> {
+        let id = match *self {
+            Operation::Insert { id, .. }
+            | Operation::Read { id }
+            | Operation::Update { id, .. }
+            | Operation::Delete { id }
+            | Operation::Copy { src: id, .. }
+            | Operation::Rename { from: id, .. }
+            | Operation::Reserve { id, .. }
+            | Operation::InsertAt { id, .. }
+            | Operation::InsertAligned { id, .. }
+            | Operation::Split { id, .. }
+            | Operation::Merge { first: id, .. }
+            | Operation::Pin { id }
+            | Operation::Unpin { id } => id,
+            Operation::Compact | Operation::InsertContent { .. } | Operation::DeleteContent { .. } => return None,
+        };
+        Some(id)
+    }
+}
+
+impl fmt::Display for Operation {
+    /// Formats the operation as one line of the text format.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Insert { id, data } => write!(f, "INSERT {} {}", id, encode_hex(data)),
+            Operation::Read { id } => write!(f, "READ {}", id),
+            Operation::Update { id, data } => write!(f, "UPDATE {} {}", id, encode_hex(data)),
+            Operation::Delete { id } => write!(f, "DELETE {}", id),
+            Operation::Copy { src, dst } => write!(f, "COPY {} {}", src, dst),
+            Operation::Rename { from, to } => write!(f, "RENAME {} {}", from, to),
+            Operation::Reserve { id, size } => write!(f, "RESERVE {} {}", id, size),
+            Operation::InsertAt { id, start, data } => write!(f, "INSERT_AT {} {} {}", id, start, encode_hex(data)),
+            Operation::InsertAligned { id, alignment, data } => {
+                write!(f, "INSERT_ALIGNED {} {} {}", id, alignment, encode_hex(data))
+            }
+            Operation::Split { id, offset, first, second } => write!(f, "SPLIT {} {} {} {}", id, offset, first, second),
+            Operation::Merge { first, second, into } => write!(f, "MERGE {} {} {}", first, second, into),
+            Operation::Pin { id } => write!(f, "PIN {}", id),
+            Operation::Unpin { id } => write!(f, "UNPIN {}", id),
+            Operation::Compact => write!(f, "COMPACT"),
+            Operation::InsertContent { data } => write!(f, "INSERT_CONTENT {}", encode_hex(data)),
+            Operation::DeleteContent { hash } => write!(f, "DELETE_CONTENT {:016x}", hash),
+        }
+    }
+}
+
+/// Formats bytes as lowercase hex.
+pub fn encode_hex(data: &[u8]) -> String {
+    data.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Parses lowercase or uppercase hex into bytes. Returns `None` if invalid.
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| hex.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect()
+}
+
+/// The outcome of applying a single `Operation`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OpResult {
+    /// A mutating operation succeeded.
+    Done,
+    /// A read succeeded and returned this data.
+    Data(Vec<u8>),
+    /// The operation failed (missing ID, duplicate ID, not enough space, ...).
+    Failed,
+}
+
+impl OpResult {
+    /// Returns `true` unless the operation failed.
+    pub fn is_ok(&self) -> bool {
+        *self != OpResult::Failed
+    }
+}
+
+impl MemoryManager {
+    /// Applies a batch of operations in order.
+    ///
+    /// # Parameters
+    ///
+    /// - `ops`: The operations to run.
+    ///
+    /// # Returns
+    ///
+    /// One `OpResult` per operation, in the same order. A failed operation
+    /// does not stop the batch.
+    pub fn apply(&mut self, ops: &[Operation]) -> Vec<OpResult> {
+        ops.iter().map(|op| self.apply_one(op)).collect()
+    }
+
+    /// Applies a batch of operations as a unit: all of them or none.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(results)` with one `OpResult` per operation if every one succeeded.
+    /// - `Err(index)` with the position of the first operation that failed.
+    ///   The operations before it are undone, unless a transaction was
+    ///   already open, in which case they stay until it is rolled back.
+    ///
+    /// # Behavior
+    ///
+    /// Runs the batch in a transaction of its own unless one is already open.
+    /// A failed `Read` counts as a failure too, so a batch can assert that
+    /// a block exists before changing it.
+    pub fn apply_all(&mut self, ops: &[Operation]) -> Result<Vec<OpResult>, usize> {
+        let own_transaction = self.begin_transaction().is_some();
+        let mut results = Vec::with_capacity(ops.len());
+        for (index, op) in ops.iter().enumerate() {
+            let result = self.apply_one(op);
+            if result == OpResult::Failed {
+                if own_transaction {
+                    self.rollback();
+                }
+                return Err(index);
+            }
+            results.push(result);
+        }
+        if own_transaction {
+            self.commit();
+        }
+        Ok(results)
+    }
+
+    /// Applies a single operation.
+    pub fn apply_one(&mut self, op: &Operation) -> OpResult {
+        let done = |result: Option<()>| result.map_or(OpResult::Failed, |_| OpResult::Done);
+        match op {
+            Operation::Insert { id, data } => done(self.insert(*id, data.clone()).map(|_| ())),
+            Operation::Read { id } => self.read(*id).map_or(OpResult::Failed, OpResult::Data),
+            Operation::Update { id, data } => done(self.update(*id, data.clone())),
+            Operation::Delete { id } => done(self.delete(*id)),
+            Operation::Copy { src, dst } => done(self.copy(*src, *dst)),
+            Operation::Rename { from, to } => done(self.rename(*from, *to)),
+            Operation::Reserve { id, size } => done(self.reserve(*id, *size).map(|_| ())),
+            Operation::InsertAt { id, start, data } => done(self.insert_at(*id, *start, data.clone()).map(|_| ())),
+            Operation::InsertAligned { id, alignment, data } => {
+                done(self.insert_aligned(*id, data.clone(), *alignment).map(|_| ()))
+            }
+            Operation::Split { id, offset, first, second } => done(self.split_into(*id, *offset, *first, *second)),
+            Operation::Merge { first, second, into } => done(self.merge_into(*first, *second, *into)),
+            Operation::Pin { id } => done(self.pin(*id)),
+            Operation::Unpin { id } => done(self.unpin(*id)),
+            Operation::Compact => {
+                self.compact();
+                OpResult::Done
+            }
+            Operation::InsertContent { data } => done(self.insert_content(data.clone()).map(|_| ())),
+            Operation::DeleteContent { hash } => done(self.delete_content(*hash).map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::error::MemoryError;
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// Identifies a simulated process.
+pub type ProcessId = u32;
+
+/// How the pager picks a resident page to evict when no frame is free.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Replacement {
+    #[default]
+    Fifo,  // Evict the page that was loaded first
+    Lru,   // Evict the page that was used least recently
+    Clock, // Sweep the frames in order, giving referenced pages a second chance
+}
+
+/// One entry of a process's page table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PageTableEntry {
+    pub frame: Option<BlockId>, // Frame holding the page, if it is resident
+    pub referenced: bool,       // Set on every access, cleared by the clock hand
+    pub dirty: bool,            // Written since it was loaded
+}
+
+/// Counters kept by a `Pager`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PagingStats {
+    pub accesses: u64,   // Page accesses (an access spanning two pages counts twice)
+    pub faults: u64,     // Accesses to pages that were not resident
+    pub evictions: u64,  // Pages evicted to free a frame
+    pub writebacks: u64, // Evicted pages that were dirty and had to be saved
+}
+
+impl PagingStats {
+    /// Returns the share of page accesses that faulted, from 0.0 to 1.0.
+    pub fn fault_rate(&self) -> f64 {
+        if self.accesses == 0 {
+            0.0
+        } else {
+            self.faults as f64 / self.accesses as f64
+        }
+    }
+}
+
+/// Why a paged access failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PagingError {
+    /// No process has this ID.
+    NoSuchProcess { pid: ProcessId },
+    /// The address lies outside the process's address space.
+    SegmentationFault { pid: ProcessId, vaddr: usize },
+    /// The frame allocator refused the operation, e.g. memory cannot hold
+    /// even one page.
+    Memory(MemoryError),
+}
+
+impl fmt::Display for PagingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PagingError::NoSuchProcess { pid } => write!(f, "no process {}", pid),
+            PagingError::SegmentationFault { pid, vaddr } => {
+                write!(f, "segmentation fault in process {} at virtual address {:#x}", pid, vaddr)
+            }
+            PagingError::Memory(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for PagingError {}
+
+/// A page held in a frame.
+struct Resident {
+    frame: BlockId,
+    pid: ProcessId,
+    page: usize,
+    loaded: u64, // Tick at which the page was loaded
+    used: u64,   // Tick of the page's last access
+}
+
+/// A demand-paging simulation on top of a `MemoryManager`.
+///
+/// Every process gets a fixed-size virtual address space and its own page
+/// table. The manager is the physical frame allocator: each resident page
+/// is one block of `page_size` bytes, so the number of frames is however
+/// many such blocks memory holds. Touching a page that is not resident is a
+/// page fault; the page is loaded into a new frame, and when none can be
+/// allocated a resident page is evicted according to the replacement
+/// policy. Dirty pages are written back to a backing store on eviction, and
+/// pages never written read as zeros.
+pub struct Pager {
+    manager: MemoryManager,                          // Physical memory; one block per frame
+    page_size: usize,                                // Bytes per page and per frame
+    policy: Replacement,                             // How victims are chosen
+    tables: HashMap<ProcessId, Vec<PageTableEntry>>, // pid -> page table, indexed by page number
+    resident: Vec<Resident>,                         // Resident pages, in clock order
+    hand: usize,                                     // Clock hand, an index into `resident`
+    backing: HashMap<(ProcessId, usize), Vec<u8>>,   // Contents of written pages not resident
+    faults: HashMap<ProcessId, u64>,                 // pid -> page faults
+    next_pid: ProcessId,                             // ID of the next process created
+    tick: u64,                                       // Page accesses so far, used as a clock
+    stats: PagingStats,                              // Counters since creation or `reset_stats`
+}
+
+impl Pager {
+    /// Creates a pager using `manager` to allocate frames of `page_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// If `page_size` is 0.
+    ///
+    /// # Behavior
+    ///
+    /// The pager owns the manager from then on, and every block in it is a
+    /// frame; it should start out empty. Managers that compress blocks may
+    /// refuse writes that make a frame less compressible.
+    pub fn new(manager: MemoryManager, page_size: usize, policy: Replacement) -> Self {
+        assert!(page_size > 0, "page size must be at least 1 byte");
+        Self {
+            manager,
+            page_size,
+            policy,
+            tables: HashMap::new(),
+            resident: Vec::new(),
+            hand: 0,
+            backing: HashMap::new(),
+            faults: HashMap::new(),
+            next_pid: 1,
+            tick: 0,
+            stats: PagingStats::default(),
+        }
+    }
+
+    /// Returns the number of bytes per page.
+    pub fn page_size(&self) -> usize {
+        self.page_size
+    }
+
+    /// Returns the replacement policy.
+    pub fn policy(&self) -> Replacement {
+        self.policy
+    }
+
+    /// Changes the replacement policy. Resident pages stay where they are.
+    pub fn set_policy(&mut self, policy: Replacement) {
+        self.policy = policy;
+    }
+
+    /// Returns the frame allocator.
+    pub fn manager(&self) -> &MemoryManager {
+        &self.manager
+    }
+
+    /// Returns the counters accumulated so far.
+    pub fn stats(&self) -> PagingStats {
+        self.stats
+    }
+
+    /// Resets all counters, including per-process fault counts.
+    pub fn reset_stats(&mut self) {
+        self.stats = PagingStats::default();
+        self.faults.values_mut().for_each(|faults| *faults = 0);
+    }
+
+    /// Creates a process with an address space of `pages` pages, none of
+    /// them resident, and returns its ID.
+    pub fn spawn(&mut self, pages: usize) -> ProcessId {
+        let pid = self.next_pid;
+        self.next_pid += 1;
+        self.tables.insert(pid, vec![PageTableEntry::default(); pages]);
+        self.faults.insert(pid, 0);
+        pid
+    }
+
+    /// Ends a process, freeing its frames and discarding its pages.
+    /// Returns `None` if it does not exist.
+    pub fn exit(&mut self, pid: ProcessId) -> Option<()> {
+        self.tables.remove(&pid)?;
+        self.faults.remove(&pid);
+        self.backing.retain(|&(owner, _), _| owner != pid);
+        let mut index = 0;
+        while index < self.resident.len() {
+            if self.resident[index].pid == pid {
+                let resident = self.remove_resident(index);
+                self.manager.delete(resident.frame);
+            } else {
+                index += 1;
+            }
+        }
+        Some(())
+    }
+
+    /// Returns the page table of process `pid`, indexed by page number.
+    pub fn page_table(&self, pid: ProcessId) -> Option<&[PageTableEntry]> {
+        self.tables.get(&pid).map(Vec::as_slice)
+    }
+
+    /// Returns the number of page faults process `pid` has taken.
+    pub fn faults(&self, pid: ProcessId) -> Option<u64> {
+        self.faults.get(&pid).copied()
+    }
+
+    /// Reads `len` bytes from the address space of process `pid`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(data)` with the bytes at `vaddr..vaddr + len`, loading every
+    ///   page they touch.
+    /// - `Err(PagingError::SegmentationFault)` if any byte lies outside the
+    ///   address space. Nothing is loaded.
+    /// - `Err(PagingError::NoSuchProcess)` or `Err(PagingError::Memory(e))`
+    ///   otherwise.
+    pub fn read(&mut self, pid: ProcessId, vaddr: usize, len: usize) -> Result<Vec<u8>, PagingError> {
+        self.check_range(pid, vaddr, len)?;
+        let mut data = Vec::with_capacity(len);
+        for (page, offset, count) in self.pieces(vaddr, len) {
+            let frame = self.access(pid, page, false)?;
+            let contents = self.manager.read_cow(frame).ok_or_else(|| PagingError::Memory(self.manager.read_failure(frame)))?;
+            data.extend_from_slice(&contents[offset..offset + count]);
+        }
+        Ok(data)
+    }
+
+    /// Writes `data` into the address space of process `pid`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` once every byte is written, loading every page it touches
+    ///   and marking it dirty.
+    /// - `Err(PagingError::SegmentationFault)` if any byte lies outside the
+    ///   address space. Nothing is written.
+    /// - `Err(PagingError::NoSuchProcess)` or `Err(PagingError::Memory(e))`
+    ///   otherwise.
+    pub fn write(&mut self, pid: ProcessId, vaddr: usize, data: &[u8]) -> Result<(), PagingError> {
+        self.check_range(pid, vaddr, data.len())?;
+        let mut written = 0;
+        for (page, offset, count) in self.pieces(vaddr, data.len()) {
+            let frame = self.access(pid, page, true)?;
+            let mut contents = self.manager.peek(frame).ok_or_else(|| PagingError::Memory(self.manager.read_failure(frame)))?;
+            contents[offset..offset + count].copy_from_slice(&data[written..written + count]);
+            written += count;
+            if self.manager.update(frame, contents).is_none() {
+                return Err(PagingError::Memory(self.manager.update_failure(frame, self.page_size)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails unless `len` bytes from `vaddr` lie in the address space of `pid`.
+    fn check_range(&self, pid: ProcessId, vaddr: usize, len: usize) -> Result<(), PagingError> {
+        let table = self.tables.get(&pid).ok_or(PagingError::NoSuchProcess { pid })?;
+        let size = table.len() * self.page_size;
+        if vaddr.checked_add(len).is_none_or(|end| end > size) {
+            return Err(PagingError::SegmentationFault { pid, vaddr: vaddr.max(size) });
+        }
+        Ok(())
+    }
+
+    /// Splits `vaddr..vaddr + len` into `(page, offset, count)` pieces that
+    /// each stay within one page.
+    fn pieces(&self, vaddr: usize, len: usize) -> Vec<(usize, usize, usize)> {
+        let mut pieces = Vec::new();
+        let (mut address, end) = (vaddr, vaddr + len);
+        while address < end {
+            let (page, offset) = (address / self.page_size, address % self.page_size);
+            let count = (self.page_size - offset).min(end - address);
+            pieces.push((page, offset, count));
+            address += count;
+        }
+        pieces
+    }
+
+    /// Touches page `page` of process `pid`, loading it on a fault, and
+    /// returns the frame holding it.
+    fn access(&mut self, pid: ProcessId, page: usize, write: bool) -> Result<BlockId, PagingError> {
+        self.tick += 1;
+        self.stats.accesses += 1;
+        let frame = match self.tables[&pid][page].frame {
+            Some(frame) => frame,
+            None => {
+                self.stats.faults += 1;
+                *self.faults.entry(pid).or_default() += 1;
+                self.load(pid, page)?
+            }
+        };
+        let entry = &mut self.tables.get_mut(&pid).expect("checked by check_range")[page];
+        entry.referenced = true;
+        entry.dirty |= write;
+        if let Some(resident) = self.resident.iter_mut().find(|resident| resident.frame == frame) {
+            resident.used = self.tick;
+        }
+        Ok(frame)
+    }
+
+    /// Brings page `page` of process `pid` into a frame, evicting other
+    /// pages until the manager can allocate one.
+    fn load(&mut self, pid: ProcessId, page: usize) -> Result<BlockId, PagingError> {
+        let contents = self.backing.get(&(pid, page)).cloned().unwrap_or_else(|| vec![0; self.page_size]);
+        let mut slot = self.resident.len();
+        let frame = loop {
+            let frame = (0..=BlockId::MAX).find(|id| !self.manager.allocations.contains_key(id)).expect("a free block ID");
+            if self.manager.insert(frame, contents.clone()).is_some() {
+                break frame;
+            }
+            if self.resident.is_empty() {
+                return Err(PagingError::Memory(self.manager.insert_failure(frame, self.page_size)));
+            }
+            slot = self.evict();
+        };
+
+        self.resident.insert(slot, Resident { frame, pid, page, loaded: self.tick, used: self.tick });
+        if self.policy == Replacement::Clock {
+            self.hand = (slot + 1) % self.resident.len();
+        }
+        let entry = &mut self.tables.get_mut(&pid).expect("checked by check_range")[page];
+        *entry = PageTableEntry { frame: Some(frame), referenced: false, dirty: false };
+        Ok(frame)
+    }
+
+    /// Evicts the page chosen by the replacement policy, writing it back if
+    /// it is dirty, and returns the slot it held in `resident`.
+    fn evict(&mut self) -> usize {
+        let index = match self.policy {
+            Replacement::Fifo => (0..self.resident.len()).min_by_key(|&index| self.resident[index].loaded),
+            Replacement::Lru => (0..self.resident.len()).min_by_key(|&index| self.resident[index].used),
+            Replacement::Clock => loop {
+                self.hand %= self.resident.len();
+                let resident = &self.resident[self.hand];
+                let entry = &mut self.tables.get_mut(&resident.pid).expect("resident pages have a process")[resident.page];
+                if !entry.referenced {
+                    break Some(self.hand);
+                }
+                entry.referenced = false;
+                self.hand += 1;
+            },
+        }
+        .expect("at least one resident page");
+
+        let resident = self.remove_resident(index);
+        let entry = &mut self.tables.get_mut(&resident.pid).expect("resident pages have a process")[resident.page];
+        let dirty = entry.dirty;
+        *entry = PageTableEntry::default();
+        if dirty {
+            if let Some(contents) = self.manager.peek(resident.frame) {
+                self.backing.insert((resident.pid, resident.page), contents);
+            }
+            self.stats.writebacks += 1;
+        }
+        self.manager.delete(resident.frame);
+        self.stats.evictions += 1;
+        index
+    }
+
+    /// Removes a page from `resident`, keeping the clock hand on the page
+    /// it pointed at.
+    fn remove_resident(&mut self, index: usize) -> Resident {
+        if index < self.hand {
+            self.hand -= 1;
+        }
+        self.resident.remove(index)
+    }
+}
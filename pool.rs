@@ -0,0 +1,184 @@
+//! A memory pool that needs neither `std` nor a heap.
+//!
+//! `Pool` manages a caller-provided buffer with an allocation table of a
+//! fixed number of entries, so it can run on embedded targets from a
+//! `static` buffer. It places blocks with the same `FreeList` as
+//! `MemoryManager`, over a fixed array instead of a `Vec`. Without the `std`
+//! feature this and `free_list` are all the crate builds; `MemoryManager`
+//! needs `std` for files, locks, and hash maps.
+
+use core::fmt;
+
+use crate::free_list::{FixedRegions, FreeList};
+
+/// The type of block IDs: 32 bits wide, or 64 bits with the `wide-ids` feature.
+#[cfg(not(feature = "wide-ids"))]
+pub type BlockId = u32;
+/// The type of block IDs: 32 bits wide, or 64 bits with the `wide-ids` feature.
+#[cfg(feature = "wide-ids")]
+pub type BlockId = u64;
+
+/// Why a `Pool` refused an operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolError {
+    /// No block has this ID.
+    NotFound { id: BlockId },
+    /// A block with this ID already exists.
+    DuplicateId { id: BlockId },
+    /// The allocation table has no free entry.
+    TableFull { blocks: usize },
+    /// No free region can hold the block.
+    OutOfSpace { requested: usize, largest_free: usize },
+    /// An update is larger than the block it replaces.
+    TooLarge { requested: usize, capacity: usize },
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PoolError::NotFound { id } => write!(f, "ID {} not found", id),
+            PoolError::DuplicateId { id } => write!(f, "ID {} already exists", id),
+            PoolError::TableFull { blocks } => write!(f, "allocation table is full ({} blocks)", blocks),
+            PoolError::OutOfSpace { requested, largest_free } => {
+                write!(f, "no free region for {} bytes (largest free region: {} bytes)", requested, largest_free)
+            }
+            PoolError::TooLarge { requested, capacity } => {
+                write!(f, "{} bytes do not fit in a block of {} bytes", requested, capacity)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PoolError {}
+
+/// One entry of the allocation table.
+#[derive(Clone, Copy, Debug)]
+struct Slot {
+    id: BlockId,
+    start: usize, // Index of the first byte in the buffer
+    size: usize,  // Bytes reserved for the block
+    len: usize,   // Bytes of data currently stored, at most `size`
+}
+
+/// A first-fit memory pool over a borrowed buffer, holding up to `BLOCKS` blocks.
+///
+/// The allocation table is an array kept in address order, so every
+/// operation is a linear scan and nothing is allocated. Deleted blocks are
+/// zeroed. Blocks never move, so free space can fragment.
+pub struct Pool<'a, const BLOCKS: usize> {
+    memory: &'a mut [u8],
+    slots: [Slot; BLOCKS], // The first `len` entries are live, in address order
+    len: usize,
+    free: FreeList<FixedRegions<BLOCKS>>, // Which bytes no block reserves
+}
+
+impl<'a, const BLOCKS: usize> Pool<'a, BLOCKS> {
+    /// Creates an empty pool managing `memory`, whose contents are cleared.
+    ///
+    /// # Panics
+    ///
+    /// If `BLOCKS` is 0 and `memory` is not empty.
+    pub fn from_slice(memory: &'a mut [u8]) -> Self {
+        memory.fill(0);
+        let free = FreeList::with_regions(memory.len(), FixedRegions::new());
+        Self { memory, slots: [Slot { id: 0, start: 0, size: 0, len: 0 }; BLOCKS], len: 0, free }
+    }
+
+    /// Returns the size of the buffer in bytes.
+    pub fn capacity(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Returns the number of blocks stored.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no blocks are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of bytes not reserved by any block.
+    pub fn free_bytes(&self) -> usize {
+        self.free.free_bytes()
+    }
+
+    /// Stores a copy of `data` under `id`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the block was stored in the first free region large enough.
+    /// - `Err(PoolError)` if the ID is taken, the table is full, or no free
+    ///   region is large enough.
+    pub fn insert(&mut self, id: BlockId, data: &[u8]) -> Result<(), PoolError> {
+        if self.position(id).is_some() {
+            return Err(PoolError::DuplicateId { id });
+        }
+        if self.len == BLOCKS {
+            return Err(PoolError::TableFull { blocks: BLOCKS });
+        }
+
+        let start = self
+            .free
+            .allocate(data.len(), 1)
+            .ok_or(PoolError::OutOfSpace { requested: data.len(), largest_free: self.free.largest() })?;
+        let index = self.live().partition_point(|slot| slot.start <= start);
+        self.slots.copy_within(index..self.len, index + 1);
+        self.slots[index] = Slot { id, start, size: data.len(), len: data.len() };
+        self.len += 1;
+        self.memory[start..start + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Returns the data stored under `id`, or `None` if it does not exist.
+    pub fn read(&self, id: BlockId) -> Option<&[u8]> {
+        let slot = self.slots[self.position(id)?];
+        Some(&self.memory[slot.start..slot.start + slot.len])
+    }
+
+    /// Replaces the data of block `id` in place.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the block was updated.
+    /// - `Err(PoolError)` if it does not exist, or `data` is larger than the
+    ///   block was when inserted. Blocks never grow.
+    pub fn update(&mut self, id: BlockId, data: &[u8]) -> Result<(), PoolError> {
+        let index = self.position(id).ok_or(PoolError::NotFound { id })?;
+        let slot = &mut self.slots[index];
+        if data.len() > slot.size {
+            return Err(PoolError::TooLarge { requested: data.len(), capacity: slot.size });
+        }
+        slot.len = data.len();
+        let region = &mut self.memory[slot.start..slot.start + slot.size];
+        region[..data.len()].copy_from_slice(data);
+        region[data.len()..].fill(0);
+        Ok(())
+    }
+
+    /// Deletes block `id`, zeroing its bytes and freeing its region.
+    pub fn delete(&mut self, id: BlockId) -> Result<(), PoolError> {
+        let index = self.position(id).ok_or(PoolError::NotFound { id })?;
+        let slot = self.slots[index];
+        self.memory[slot.start..slot.start + slot.size].fill(0);
+        self.free.free(slot.start, slot.size);
+        self.slots.copy_within(index + 1..self.len, index);
+        self.len -= 1;
+        Ok(())
+    }
+
+    /// Returns every block as `(id, data)`, in address order.
+    pub fn iter(&self) -> impl Iterator<Item = (BlockId, &[u8])> {
+        self.live().iter().map(|slot| (slot.id, &self.memory[slot.start..slot.start + slot.len]))
+    }
+
+    fn live(&self) -> &[Slot] {
+        &self.slots[..self.len]
+    }
+
+    fn position(&self, id: BlockId) -> Option<usize> {
+        self.live().iter().position(|slot| slot.id == id)
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Mutex;
+
+use crate::events::Event;
+use crate::memory_manager::MemoryManager;
+
+/// How full memory is, as returned by `pressure`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Pressure {
+    #[default]
+    Low,    // Below the medium level
+    Medium, // At or above the medium level, below the high level
+    High,   // At or above the high level
+}
+
+/// A threshold crossing, passed to `on_threshold` callbacks.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Crossing {
+    pub threshold: f64,   // The threshold that was crossed
+    pub utilization: f64, // Utilization right after the change that crossed it
+    pub rising: bool,     // True when memory filled up past it, false when it drained below
+}
+
+/// A callback registered with `on_threshold`.
+type Callback = Box<dyn FnMut(&Crossing) + Send>;
+
+struct Threshold {
+    level: f64,
+    above: bool, // Whether utilization was at or above `level` at the last check
+    callback: Mutex<Callback>,
+}
+
+/// Pressure levels and thresholds, checked after every change.
+pub(crate) struct PressureWatch {
+    medium: f64,
+    high: f64,
+    level: Pressure, // Pressure at the last check
+    thresholds: Vec<Threshold>,
+}
+
+impl Default for PressureWatch {
+    fn default() -> Self {
+        Self { medium: 0.7, high: 0.9, level: Pressure::Low, thresholds: Vec::new() }
+    }
+}
+
+impl MemoryManager {
+    /// Returns the share of memory reserved by blocks, from 0.0 to 1.0.
+    ///
+    /// Inline blocks take no memory and do not count. A manager without
+    /// any memory counts as full.
+    pub fn utilization(&self) -> f64 {
+        let capacity = self.memory.len();
+        if capacity == 0 {
+            return 1.0;
+        }
+        (capacity - self.free_bytes()) as f64 / capacity as f64
+    }
+
+    /// Returns the current memory pressure.
+    ///
+    /// Utilization at or above 0.9 is `High`, at or above 0.7 `Medium`, and
+    /// anything less `Low`, unless changed with `set_pressure_levels`.
+    pub fn pressure(&self) -> Pressure {
+        self.pressure_at(self.utilization())
+    }
+
+    /// Sets the utilization at which pressure becomes `Medium` and `High`.
+    ///
+    /// # Panics
+    ///
+    /// Unless `0.0 <= medium <= high <= 1.0`.
+    pub fn set_pressure_levels(&mut self, medium: f64, high: f64) {
+        assert!((0.0..=high).contains(&medium) && high <= 1.0, "pressure levels must satisfy 0 <= medium <= high <= 1");
+        self.pressure_watch.medium = medium;
+        self.pressure_watch.high = high;
+        self.pressure_watch.level = self.pressure();
+    }
+
+    /// Registers `callback` to be called whenever utilization crosses `threshold`.
+    ///
+    /// # Behavior
+    ///
+    /// After every insert, update, and delete, the callback is called if
+    /// utilization went from below `threshold` to at or above it (a rising
+    /// crossing), or back below it. It is not called for the state at
+    /// registration. Callbacks run synchronously before the operation
+    /// returns and cannot reach the manager, so they typically set a flag
+    /// that the application acts on, e.g. by compacting or evicting blocks
+    /// before inserts start failing. Pressure level changes are also
+    /// reported to `on_event` callbacks as `Event::PressureChanged`.
+    pub fn on_threshold<F: FnMut(&Crossing) + Send + 'static>(&mut self, threshold: f64, callback: F) {
+        let above = self.utilization() >= threshold;
+        self.pressure_watch.thresholds.push(Threshold { level: threshold, above, callback: Mutex::new(Box::new(callback)) });
+    }
+
+    /// Calls the callbacks of every threshold crossed since the last check,
+    /// and emits `Event::PressureChanged` if the pressure level changed.
+    pub(crate) fn check_pressure(&mut self) {
+        let utilization = self.utilization();
+        for threshold in &mut self.pressure_watch.thresholds {
+            let above = utilization >= threshold.level;
+            if above != threshold.above {
+                threshold.above = above;
+                let crossing = Crossing { threshold: threshold.level, utilization, rising: above };
+                let callback = threshold.callback.get_mut().unwrap_or_else(|poisoned| poisoned.into_inner());
+                callback(&crossing);
+            }
+        }
+
+        let level = self.pressure_at(utilization);
+        let from = std::mem::replace(&mut self.pressure_watch.level, level);
+        if from != level {
+            self.emit(Event::PressureChanged { from, to: level });
+        }
+    }
+
+    fn pressure_at(&self, utilization: f64) -> Pressure {
+        if utilization >= self.pressure_watch.high {
+            Pressure::High
+        } else if utilization >= self.pressure_watch.medium {
+            Pressure::Medium
+        } else {
+            Pressure::Low
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Python bindings, built with the `python` feature.
+//!
+//! Build the extension module with `maturin develop`, then:
+//!
+//! ```python
+//! from project2 import MemoryManager
+//! mm = MemoryManager()
+//! mm.insert(1, b"Hello")
+//! mm.read(1)   # b'Hello'
+//! mm.stats()   # {'allocations': 1, 'capacity': 65535, ...}
+//! ```
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::{PyKeyError, PyMemoryError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::error::MemoryError;
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// A `MemoryManager` exposed to Python as `project2.MemoryManager`.
+#[pyclass(name = "MemoryManager")]
+pub struct PyMemoryManager {
+    inner: MemoryManager,
+}
+
+#[pymethods]
+impl PyMemoryManager {
+    /// Creates a manager with `capacity` bytes of memory.
+    #[new]
+    #[pyo3(signature = (capacity = 65535))]
+    fn new(capacity: usize) -> Self {
+        Self { inner: MemoryManager::with_capacity(capacity) }
+    }
+
+    /// Stores `data` under `id`. Raises `KeyError` if the ID exists and
+    /// `MemoryError` if there is not enough space.
+    fn insert(&mut self, id: BlockId, data: &[u8]) -> PyResult<()> {
+        match self.inner.insert(id, data.to_vec()) {
+            Some(_) => Ok(()),
+            None => Err(to_py_err(self.inner.insert_failure(id, data.len()))),
+        }
+    }
+
+    /// Returns the data stored under `id` as `bytes`. Raises `KeyError` if
+    /// the ID does not exist.
+    fn read<'py>(&self, py: Python<'py>, id: BlockId) -> PyResult<Bound<'py, PyBytes>> {
+        match self.inner.read(id) {
+            Some(data) => Ok(PyBytes::new(py, &data)),
+            None => Err(to_py_err(self.inner.read_failure(id))),
+        }
+    }
+
+    /// Replaces the data stored under `id`. Raises `KeyError` if the ID does
+    /// not exist and `ValueError` if the data does not fit in the block.
+    fn update(&mut self, id: BlockId, data: &[u8]) -> PyResult<()> {
+        match self.inner.update(id, data.to_vec()) {
+            Some(()) => Ok(()),
+            None => Err(to_py_err(self.inner.update_failure(id, data.len()))),
+        }
+    }
+
+    /// Deletes the data stored under `id`. Raises `KeyError` if the ID does not exist.
+    fn delete(&mut self, id: BlockId) -> PyResult<()> {
+        match self.inner.delete(id) {
+            Some(()) => Ok(()),
+            None => Err(to_py_err(self.inner.delete_failure(id))),
+        }
+    }
+
+    /// Returns usage statistics as a `dict`.
+    fn stats(&self) -> HashMap<&'static str, usize> {
+        let stats = self.inner.stats();
+        HashMap::from([
+            ("allocations", stats.allocations),
+            ("capacity", stats.capacity),
+            ("used_bytes", stats.used_bytes),
+            ("raw_bytes", stats.raw_bytes),
+            ("stored_bytes", stats.stored_bytes),
+            ("dedup_saved", stats.dedup_saved),
+            ("free_bytes", self.inner.free_bytes()),
+        ])
+    }
+
+    /// Returns the dump text that `MemoryManager::dump` prints.
+    fn dump(&self) -> String {
+        self.inner.render_dump(false)
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.allocations.len()
+    }
+
+    fn __contains__(&self, id: BlockId) -> bool {
+        self.inner.allocations.contains_key(&id)
+    }
+}
+
+/// Maps a `MemoryError` to the closest built-in Python exception.
+fn to_py_err(error: MemoryError) -> PyErr {
+    let message = error.to_string();
+    match error {
+        MemoryError::NotFound { .. } | MemoryError::DuplicateId { .. } => PyKeyError::new_err(message),
+        MemoryError::OutOfSpace { .. } => PyMemoryError::new_err(message),
+        _ => PyValueError::new_err(message),
+    }
+}
+
+#[pymodule]
+fn project2(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyMemoryManager>()?;
+    Ok(())
+}
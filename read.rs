@@ -1,25 +1,31 @@
-use crate::memory_manager::MemoryManager; // Import the MemoryManager module
+use log::{info, warn};
 
-// Reads and prints the data associated with the given ID from the memory manager.
+use crate::error::MemoryError;
+use crate::memory_manager::{BlockId, MemoryManager}; // Import the MemoryManager module
+
+// Reads and logs the data associated with the given ID from the memory manager.
 //
 // Parameters:
 // - `manager`: A reference to the MemoryManager instance.
-// - `id`: The ID (usize) of the memory block to read.
+// - `id`: The ID of the memory block to read.
 //
-// Behavior:
-// - If the ID exists, the function prints the data as a UTF-8 string.
-// - If the ID is not found, it prints an error message.
-pub fn read(manager: &MemoryManager, id: usize) {
-    // Attempt to read the data associated with the ID (cast to u16)
-    if let Some(data) = manager.read(id as u16) {
-        // Print the successfully read data as a UTF-8 string
-        println!(
+// Returns:
+// - `Ok(data)` if the ID exists; the data is logged at info level as a UTF-8 string.
+// - `Err(reason)` otherwise (e.g. ID not found, data corrupted), logged as a warning.
+pub fn read(manager: &MemoryManager, id: BlockId) -> Result<Vec<u8>, MemoryError> {
+    // Attempt to read the data associated with the ID
+    if let Some(data) = manager.read(id) {
+        // Log the successfully read data as a UTF-8 string
+        info!(
             "Read successful for ID {}: {}",
             id,
             String::from_utf8_lossy(&data)
         );
+        Ok(data)
     } else {
-        // Print an error message if the ID is not found
-        println!("Read failed for ID {}: ID not found", id);
+        // Log an error message explaining the failure
+        let reason = manager.read_failure(id);
+        warn!("Read failed for ID {}: {}", id, reason);
+        Err(reason)
     }
 }
@@ -0,0 +1,109 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::memory_manager::MemoryManager;
+use crate::operation::{decode_hex, OpResult, Operation};
+
+/// The outcome of replaying an operation log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub operations: usize,       // Number of operations replayed
+    pub expected: u64,           // State hash recorded alongside the log
+    pub actual: u64,             // State hash of the freshly replayed manager
+    pub diverged: Option<usize>, // Line of the first operation whose result differed from the trace
+}
+
+impl ReplayReport {
+    /// Returns `true` if the replayed state matches the recorded one, and
+    /// every operation had the recorded result.
+    pub fn matches(&self) -> bool {
+        self.expected == self.actual && self.diverged.is_none()
+    }
+}
+
+/// Replays `ops` in order against a fresh `MemoryManager`.
+///
+/// Failed operations (e.g. inserting a duplicate ID) are skipped, exactly as
+/// they would have failed when the log was recorded.
+pub fn replay(ops: &[Operation]) -> MemoryManager {
+    let mut manager = MemoryManager::new();
+    manager.apply(ops);
+    manager
+}
+
+/// Replays the operation log at `path` and compares the final state hash
+/// against `expected`.
+///
+/// # Returns
+///
+/// - `Ok(report)` describing both hashes; check `report.matches()`.
+/// - `Err(e)` if the log cannot be read or parsed.
+pub fn verify_replay<P: AsRef<Path>>(path: P, expected: u64) -> io::Result<ReplayReport> {
+    let ops = Operation::parse_all(&fs::read_to_string(path)?)?;
+    let manager = replay(&ops);
+    Ok(ReplayReport { operations: ops.len(), expected, actual: manager.state_hash(), diverged: None })
+}
+
+/// Re-executes a trace recorded with `start_trace` against a fresh
+/// `MemoryManager`, checking every result and the final state hash.
+///
+/// # Returns
+///
+/// - `Ok(report)` comparing the replay with the recording; check
+///   `report.matches()`, and `report.diverged` for where it went wrong.
+/// - `Err(e)` if the trace cannot be read, has a malformed line, or has no
+///   `END` line because recording was never stopped.
+///
+/// # Behavior
+///
+/// The replayed manager has the default configuration, so traces of
+/// managers built otherwise (another capacity, compression, ...) diverge.
+pub fn replay_trace<P: AsRef<Path>>(path: P) -> io::Result<ReplayReport> {
+    let mut manager = MemoryManager::new();
+    let mut report = ReplayReport { operations: 0, expected: 0, actual: 0, diverged: None };
+    let mut expected = None;
+
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("line {}: bad trace line: {}", number + 1, line));
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(hash) = line.strip_prefix("END ") {
+            expected = Some(u64::from_str_radix(hash, 16).map_err(|_| invalid())?);
+            continue;
+        }
+        match line {
+            "BEGIN" => {
+                manager.begin_transaction();
+            }
+            "COMMIT" => {
+                manager.commit();
+            }
+            "ROLLBACK" => {
+                manager.rollback();
+            }
+            _ => {
+                let (op, result) = line.split_once(" -> ").ok_or_else(invalid)?;
+                let op = Operation::parse(op)?.ok_or_else(invalid)?;
+                let recorded = match result.split_once(' ') {
+                    // An empty read loses its trailing space to `trim`
+                    None if result == "OK" && matches!(op, Operation::Read { .. }) => OpResult::Data(Vec::new()),
+                    None if result == "OK" => OpResult::Done,
+                    None if result == "ERR" => OpResult::Failed,
+                    Some(("OK", hex)) => OpResult::Data(decode_hex(hex).ok_or_else(invalid)?),
+                    _ => return Err(invalid()),
+                };
+                report.operations += 1;
+                if manager.apply_one(&op) != recorded && report.diverged.is_none() {
+                    report.diverged = Some(number + 1);
+                }
+            }
+        }
+    }
+
+    report.expected = expected.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "trace has no END line"))?;
+    report.actual = manager.state_hash();
+    Ok(report)
+}
@@ -0,0 +1,60 @@
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// A private key able to produce detached signatures.
+///
+/// Implement this for the signing key of your signature scheme of choice
+/// (e.g. an Ed25519 key) to seal blocks.
+pub trait SigningKey {
+    /// Signs `message`, returning the detached signature bytes.
+    fn sign(&self, message: &[u8]) -> Vec<u8>;
+}
+
+/// A public key able to check detached signatures made by a `SigningKey`.
+pub trait VerifyingKey {
+    /// Returns `true` if `signature` is a valid signature of `message`.
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool;
+}
+
+impl MemoryManager {
+    /// Seals a block by storing a detached signature over its contents.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The block to seal.
+    /// - `signing_key`: The key to sign the block's data with.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the block was sealed. Any previous seal is replaced.
+    /// - `None` if the block does not exist or cannot be read.
+    ///
+    /// # Behavior
+    ///
+    /// The signature covers the data as returned by `read()`, so it stays
+    /// valid across compaction or encryption changes, but not across updates.
+    pub fn seal(&mut self, id: BlockId, signing_key: &dyn SigningKey) -> Option<()> {
+        let data = self.peek(id)?;
+        self.seals.insert(id, signing_key.sign(&data));
+        Some(())
+    }
+
+    /// Checks a block's contents against its seal.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(true)` if the block is unchanged since it was sealed.
+    /// - `Some(false)` if it was modified, corrupted, or sealed by another key.
+    /// - `None` if the block does not exist or was never sealed.
+    pub fn verify_seal(&self, id: BlockId, public_key: &dyn VerifyingKey) -> Option<bool> {
+        let signature = self.seals.get(&id)?;
+        if !self.exists(id) {
+            return None;
+        }
+        Some(self.peek(id).is_some_and(|data| public_key.verify(&data, signature)))
+    }
+
+    /// Returns the detached signature stored for a block, if it is sealed.
+    pub fn seal_of(&self, id: BlockId) -> Option<&[u8]> {
+        self.seals.get(&id).map(Vec::as_slice)
+    }
+}
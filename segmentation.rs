@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use crate::error::MemoryError;
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::paging::ProcessId;
+
+/// The segments every simulated process has.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SegmentType {
+    Code,  // The program, read-only once loaded
+    Data,  // Globals and heap, zeroed at spawn
+    Stack, // The stack, zeroed at spawn
+}
+
+impl fmt::Display for SegmentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SegmentType::Code => "code",
+            SegmentType::Data => "data",
+            SegmentType::Stack => "stack",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// The base and limit registers of one segment, as returned by `segment`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub block: BlockId, // Block holding the segment
+    pub base: usize,    // Physical address of offset 0
+    pub limit: usize,   // Size in bytes; valid offsets are below it
+    pub writable: bool, // False for code segments
+}
+
+/// Why a segmented access failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SegmentError {
+    /// No process has this ID.
+    NoSuchProcess { pid: ProcessId },
+    /// The access runs past the segment limit.
+    LimitExceeded { pid: ProcessId, segment: SegmentType, offset: usize, len: usize, limit: usize },
+    /// The segment does not allow writes.
+    ReadOnly { pid: ProcessId, segment: SegmentType },
+    /// The underlying manager refused the operation.
+    Memory(MemoryError),
+}
+
+impl fmt::Display for SegmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SegmentError::NoSuchProcess { pid } => write!(f, "no process {}", pid),
+            SegmentError::LimitExceeded { pid, segment, offset, len, limit } => write!(
+                f,
+                "segmentation fault in process {}: {} bytes at offset {:#x} exceed the {} segment limit of {:#x}",
+                pid, len, offset, segment, limit
+            ),
+            SegmentError::ReadOnly { pid, segment } => write!(f, "process {} wrote to its read-only {} segment", pid, segment),
+            SegmentError::Memory(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for SegmentError {}
+
+/// A segmentation simulation on top of a `MemoryManager`.
+///
+/// Every process gets a code, a data, and a stack segment, each one block
+/// in the manager. Accesses name a segment and an offset into it; the
+/// offset is checked against the segment's limit and translated by adding
+/// its base, the way base and limit registers would. Code segments reject
+/// writes. Segments are ordinary blocks, so they may be moved by
+/// compaction; `segment` always reports the current base.
+pub struct Segmentation {
+    manager: MemoryManager,                                       // Physical memory; one block per segment
+    processes: HashMap<ProcessId, HashMap<SegmentType, BlockId>>, // pid -> its segments
+    next_pid: ProcessId,                                          // ID of the next process created
+}
+
+impl Segmentation {
+    /// Wraps `manager`, which should start out empty.
+    pub fn new(manager: MemoryManager) -> Self {
+        Self { manager, processes: HashMap::new(), next_pid: 1 }
+    }
+
+    /// Returns the underlying manager.
+    pub fn manager(&self) -> &MemoryManager {
+        &self.manager
+    }
+
+    /// Unwraps the manager, leaving every segment in it as a plain block.
+    pub fn into_inner(self) -> MemoryManager {
+        self.manager
+    }
+
+    /// Creates a process running `code`, with zeroed data and stack segments
+    /// of `data_size` and `stack_size` bytes.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(pid)` with the new process's ID.
+    /// - `Err(SegmentError::Memory(e))` if a segment does not fit. No
+    ///   segments are left behind.
+    ///
+    /// # Behavior
+    ///
+    /// Segments are reserved with `reserve`, so they are never inline and
+    /// always exactly as large as requested. They take the lowest unused
+    /// block IDs.
+    pub fn spawn(&mut self, code: &[u8], data_size: usize, stack_size: usize) -> Result<ProcessId, SegmentError> {
+        let mut segments = HashMap::new();
+        let sizes = [(SegmentType::Code, code.len()), (SegmentType::Data, data_size), (SegmentType::Stack, stack_size)];
+        for (segment, size) in sizes {
+            let id = (0..=BlockId::MAX).find(|&candidate| !self.manager.exists(candidate));
+            let reserved = id.and_then(|id| self.manager.reserve(id, size).map(|_| id));
+            let filled = match reserved {
+                Some(id) if segment == SegmentType::Code => self.manager.update(id, code.to_vec()).map(|_| id),
+                reserved => reserved,
+            };
+            match filled {
+                Some(id) => {
+                    segments.insert(segment, id);
+                }
+                None => {
+                    let reason = match reserved {
+                        Some(id) => self.manager.update_failure(id, size),
+                        None => self.manager.insert_failure(id.unwrap_or_default(), size),
+                    };
+                    reserved.into_iter().chain(segments.into_values()).for_each(|id| {
+                        self.manager.delete(id);
+                    });
+                    return Err(SegmentError::Memory(reason));
+                }
+            }
+        }
+
+        let pid = self.next_pid;
+        self.next_pid += 1;
+        self.processes.insert(pid, segments);
+        Ok(pid)
+    }
+
+    /// Ends a process, freeing its segments. Returns `None` if it does not exist.
+    pub fn exit(&mut self, pid: ProcessId) -> Option<()> {
+        for id in self.processes.remove(&pid)?.into_values() {
+            self.manager.delete(id);
+        }
+        Some(())
+    }
+
+    /// Returns the base and limit of one of a process's segments.
+    ///
+    /// Returns `None` if the process does not exist, or its segment is
+    /// swapped out and so has no base.
+    pub fn segment(&self, pid: ProcessId, segment: SegmentType) -> Option<Segment> {
+        let id = *self.processes.get(&pid)?.get(&segment)?;
+        let block = self.manager.allocations.get(&id)?;
+        Some(Segment { block: id, base: block.start, limit: block.size, writable: segment != SegmentType::Code })
+    }
+
+    /// Translates `offset` in a segment to a physical address.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(paddr)`: The segment's base plus `offset`.
+    /// - `Err(SegmentError::LimitExceeded)` if `offset` is not below the limit.
+    /// - `Err(SegmentError::NoSuchProcess)` if the process does not exist.
+    /// - `Err(SegmentError::Memory(e))` if the segment cannot be found in memory.
+    pub fn translate(&self, pid: ProcessId, segment: SegmentType, offset: usize) -> Result<usize, SegmentError> {
+        let id = self.block(pid, segment)?;
+        let found = self.segment(pid, segment).ok_or_else(|| SegmentError::Memory(self.manager.read_failure(id)))?;
+        check_limit(pid, segment, offset, 1, found.limit)?;
+        Ok(found.base + offset)
+    }
+
+    /// Reads `len` bytes at `offset` in a segment.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(data)` if the whole range lies below the segment limit.
+    /// - `Err(SegmentError::LimitExceeded)` otherwise, naming the segment,
+    ///   the range, and the limit.
+    /// - `Err(SegmentError::NoSuchProcess)` or `Err(SegmentError::Memory(e))`
+    ///   if the process or its segment cannot be found.
+    pub fn read(&self, pid: ProcessId, segment: SegmentType, offset: usize, len: usize) -> Result<Vec<u8>, SegmentError> {
+        let id = self.block(pid, segment)?;
+        let data = self.manager.peek(id).ok_or_else(|| SegmentError::Memory(self.manager.read_failure(id)))?;
+        check_limit(pid, segment, offset, len, data.len())?;
+        Ok(data[offset..offset + len].to_vec())
+    }
+
+    /// Writes `data` at `offset` in a segment.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if the whole range lies below the segment limit.
+    /// - `Err(SegmentError::ReadOnly)` for the code segment.
+    /// - `Err(SegmentError::LimitExceeded)` if the range runs past the limit.
+    ///   Nothing is written.
+    /// - `Err(SegmentError::NoSuchProcess)` or `Err(SegmentError::Memory(e))`
+    ///   if the process or its segment cannot be found or updated.
+    pub fn write(&mut self, pid: ProcessId, segment: SegmentType, offset: usize, data: &[u8]) -> Result<(), SegmentError> {
+        let id = self.block(pid, segment)?;
+        if segment == SegmentType::Code {
+            return Err(SegmentError::ReadOnly { pid, segment });
+        }
+        let mut contents = self.manager.peek(id).ok_or_else(|| SegmentError::Memory(self.manager.read_failure(id)))?;
+        check_limit(pid, segment, offset, data.len(), contents.len())?;
+        contents[offset..offset + data.len()].copy_from_slice(data);
+        let size = contents.len();
+        self.manager.update(id, contents).ok_or_else(|| SegmentError::Memory(self.manager.update_failure(id, size)))
+    }
+
+    /// Returns the block holding one of a process's segments.
+    fn block(&self, pid: ProcessId, segment: SegmentType) -> Result<BlockId, SegmentError> {
+        let segments = self.processes.get(&pid).ok_or(SegmentError::NoSuchProcess { pid })?;
+        Ok(segments[&segment])
+    }
+}
+
+/// Faults unless `len` bytes from `offset` fit below `limit`.
+fn check_limit(pid: ProcessId, segment: SegmentType, offset: usize, len: usize, limit: usize) -> Result<(), SegmentError> {
+    if offset.checked_add(len).is_none_or(|end| end > limit) {
+        return Err(SegmentError::LimitExceeded { pid, segment, offset, len, limit });
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::memory_manager::{BlockId, MemoryManager};
+
+const CAPACITY: usize = 4096; // Size of the managers exercised by the self-test
+const BLOCK: usize = 64; // Size of each block written while filling memory
+
+/// The outcome of one self-test stage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StageResult {
+    pub stage: &'static str,        // Name of the stage, e.g. "fill"
+    pub result: Result<(), String>, // What went wrong, if the stage failed
+}
+
+impl StageResult {
+    /// Returns `true` if the stage passed.
+    pub fn passed(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Runs a battery of allocator exercises against temporary managers.
+///
+/// # Returns
+///
+/// One `StageResult` per stage, in the order they ran:
+///
+/// - `fill`: Fills a heap manager with equal-sized blocks until it is full.
+/// - `fragment`: Deletes every other block and checks the holes cannot hold
+///   a block larger than one hole.
+/// - `compact`: Compacts the fragmented memory and checks the free space is
+///   merged into one region that holds a block of that size.
+/// - `persist`: Writes blocks to a memory-mapped file in the temp directory.
+/// - `reload`: Reopens the file and checks the allocation table came back.
+/// - `verify`: Reads every reloaded block back and checks checksums and invariants.
+///
+/// A stage that depends on a failed one fails too. The temporary file is
+/// removed afterwards.
+pub fn run_selftest() -> Vec<StageResult> {
+    let mut results = Vec::new();
+    let mut record = |stage: &'static str, result: Result<(), String>| {
+        let passed = result.is_ok();
+        results.push(StageResult { stage, result });
+        passed
+    };
+
+    let mut manager = MemoryManager::with_capacity(CAPACITY);
+    let filled = record("fill", fill(&mut manager));
+    let fragmented = record("fragment", if filled { fragment(&mut manager) } else { skipped("fill") });
+    record("compact", if fragmented { compact(&mut manager) } else { skipped("fragment") });
+
+    let path = std::env::temp_dir().join(format!("selftest_{}.bin", std::process::id()));
+    let persisted = record("persist", persist(&path));
+    let reloaded = if persisted {
+        MemoryManager::open_mmap(&path, CAPACITY).map_err(|e| e.to_string())
+    } else {
+        skipped("persist")
+    };
+    match reloaded {
+        Ok(manager) => {
+            let loaded = record("reload", reload(&manager));
+            record("verify", if loaded { verify(&manager) } else { skipped("reload") });
+        }
+        Err(e) => {
+            record("reload", Err(e));
+            record("verify", skipped("reload"));
+        }
+    }
+    remove_files(&path);
+
+    results
+}
+
+fn skipped<T>(stage: &str) -> Result<T, String> {
+    Err(format!("skipped because {} failed", stage))
+}
+
+/// The data written to block `id`: distinct per block so misplaced reads show up.
+fn pattern(id: BlockId) -> Vec<u8> {
+    (0..BLOCK).map(|i| (id as usize * 31 + i) as u8).collect()
+}
+
+fn fill(manager: &mut MemoryManager) -> Result<(), String> {
+    let mut id = 0;
+    while manager.insert(id, pattern(id)).is_some() {
+        id += 1;
+    }
+    if id as usize != CAPACITY / BLOCK {
+        return Err(format!("stored {} blocks of {} bytes, expected {}", id, BLOCK, CAPACITY / BLOCK));
+    }
+    if manager.free_bytes() != 0 {
+        return Err(format!("{} bytes still free after filling", manager.free_bytes()));
+    }
+    manager.check_invariants()
+}
+
+fn fragment(manager: &mut MemoryManager) -> Result<(), String> {
+    for id in (0..(CAPACITY / BLOCK) as BlockId).step_by(2) {
+        manager.delete(id).ok_or(format!("failed to delete block {}", id))?;
+    }
+    if manager.free_bytes() != CAPACITY / 2 {
+        return Err(format!("{} bytes free after deleting half, expected {}", manager.free_bytes(), CAPACITY / 2));
+    }
+    if manager.insert(1000, vec![0; BLOCK + 1]).is_some() {
+        return Err("a block larger than any hole was stored".to_string());
+    }
+    manager.check_invariants()
+}
+
+fn compact(manager: &mut MemoryManager) -> Result<(), String> {
+    let report = manager.compact();
+    if report.largest_free != CAPACITY / 2 {
+        return Err(format!("largest free region is {} bytes after compaction, expected {}", report.largest_free, CAPACITY / 2));
+    }
+    for id in (1..(CAPACITY / BLOCK) as BlockId).step_by(2) {
+        if manager.read(id) != Some(pattern(id)) {
+            return Err(format!("block {} did not survive compaction", id));
+        }
+    }
+    manager.insert(1000, vec![0xab; CAPACITY / 2]).ok_or("merged free space did not hold a larger block")?;
+    manager.check_invariants()
+}
+
+fn persist(path: &Path) -> Result<(), String> {
+    remove_files(path);
+    let mut manager = MemoryManager::open_mmap(path, CAPACITY).map_err(|e| e.to_string())?;
+    for id in 0..8 {
+        manager.insert(id, pattern(id)).ok_or(format!("failed to insert block {}", id))?;
+    }
+    manager.delete(3).ok_or("failed to delete block 3")?;
+    manager.check_invariants()
+}
+
+fn reload(manager: &MemoryManager) -> Result<(), String> {
+    let ids: Vec<BlockId> = manager.allocations.keys().copied().collect();
+    if ids != [0, 1, 2, 4, 5, 6, 7] {
+        return Err(format!("reloaded IDs {:?}, expected [0, 1, 2, 4, 5, 6, 7]", ids));
+    }
+    manager.check_invariants()
+}
+
+fn verify(manager: &MemoryManager) -> Result<(), String> {
+    for &id in manager.allocations.keys() {
+        if manager.read(id) != Some(pattern(id)) {
+            return Err(format!("block {} did not read back its data", id));
+        }
+    }
+    let corrupted = manager.verify_all();
+    if !corrupted.is_empty() {
+        return Err(format!("checksum mismatch in blocks {:?}", corrupted));
+    }
+    manager.check_invariants()
+}
+
+fn remove_files(path: &Path) {
+    let mut index = path.as_os_str().to_owned();
+    index.push(".idx");
+    let _ = fs::remove_file(path);
+    let _ = fs::remove_file(PathBuf::from(index));
+}
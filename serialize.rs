@@ -0,0 +1,216 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::allocator::Allocator;
+use crate::bitmap::BitmapAllocator;
+use crate::buffer::Buffer;
+use crate::builder::Strategy;
+use crate::content::ContentEntry;
+use crate::erase::ErasePolicy;
+use crate::free_list::FreeList;
+use crate::memory_manager::{Block, BlockId, MemoryManager};
+use crate::throttle::Backpressure;
+
+/// Everything `Serialize` writes for a `MemoryManager`.
+///
+/// Borrowed when serializing, so the memory block is not copied first.
+#[derive(Serialize, Deserialize)]
+struct State<'a> {
+    #[serde(with = "bytes")]
+    memory: Cow<'a, [u8]>,                                       // The whole memory block
+    allocations: Cow<'a, BTreeMap<BlockId, Block>>,              // The allocation table
+    content: Cow<'a, HashMap<u64, ContentEntry>>,                // Content-addressed blocks
+    strategy: Strategy,                                          // Which allocator places blocks
+    free: Vec<(usize, usize)>,                                   // The allocator's free regions
+    tags: Cow<'a, HashMap<BlockId, BTreeMap<String, String>>>,   // id -> key/value tags
+    seals: Cow<'a, HashMap<BlockId, Vec<u8>>>,                   // id -> detached signature
+    pinned: Cow<'a, HashSet<BlockId>>,                           // IDs compaction must not move
+    versions: Cow<'a, HashMap<BlockId, VecDeque<Vec<u8>>>>,      // id -> previous data, newest first
+    config: Config,                                              // Options the manager was set up with
+    sequence: u64,                                               // Number of changes applied so far
+    next_nonce: u64,                                             // Next encryption nonce counter
+}
+
+/// The options of a `MemoryManager`, as `Serialize` writes them.
+#[derive(Serialize, Deserialize)]
+struct Config {
+    compression: bool,
+    inline_threshold: usize,
+    erase_policy: ErasePolicy,
+    soft_limit: usize,
+    backpressure: Backpressure,
+    dedup: bool,
+    history_depth: usize,
+    version_depth: usize,
+    canaries: bool,
+}
+
+/// Writes the memory block as one byte string instead of a sequence of
+/// numbers, which binary formats store far more compactly.
+mod bytes {
+    use std::borrow::Cow;
+    use std::fmt;
+
+    use serde::de::{SeqAccess, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub(super) fn deserialize<'de, 'a, D: Deserializer<'de>>(deserializer: D) -> Result<Cow<'a, [u8]>, D::Error> {
+        deserializer.deserialize_byte_buf(BytesVisitor).map(Cow::Owned)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a byte string")
+        }
+
+        fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+            Ok(bytes.to_vec())
+        }
+
+        fn visit_byte_buf<E: serde::de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+            Ok(bytes)
+        }
+
+        // Self-describing text formats such as JSON write bytes as a sequence
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            Ok(bytes)
+        }
+    }
+}
+
+/// Writes the memory block, the allocation and content tables, the free
+/// list, tags, seals, pins, previous versions, and configuration.
+///
+/// # Behavior
+///
+/// The encryption key is never written, so serialized state stays as safe
+/// to store as the encrypted memory itself; supply it again with
+/// `set_encryption_key` after deserializing. Undo history, named snapshots,
+/// subscribers, listeners, the journal, handles, and metrics belong to the
+/// running manager and are left out. An open transaction's changes are
+/// written as they currently stand. Fails for managers using a custom
+/// `Allocator`, which has no serialized form, and while blocks are swapped
+/// out; bring them back with `swap_in` first.
+impl Serialize for MemoryManager {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let strategy = self.allocator.strategy().ok_or_else(|| S::Error::custom("custom allocators cannot be serialized"))?;
+        if self.swap_stats().swapped > 0 {
+            return Err(S::Error::custom("swapped-out blocks cannot be serialized"));
+        }
+        State {
+            memory: Cow::Borrowed(&self.memory),
+            allocations: Cow::Borrowed(&self.allocations),
+            content: Cow::Borrowed(&self.content),
+            strategy,
+            free: self.allocator.regions(),
+            tags: Cow::Borrowed(&self.tags),
+            seals: Cow::Borrowed(&self.seals),
+            pinned: Cow::Borrowed(&self.pinned),
+            versions: Cow::Borrowed(&self.versions),
+            config: Config {
+                compression: self.compression,
+                inline_threshold: self.inline_threshold,
+                erase_policy: self.erase_policy,
+                soft_limit: self.soft_limit,
+                backpressure: self.backpressure,
+                dedup: self.dedup,
+                history_depth: self.history.depth,
+                version_depth: self.version_depth,
+                canaries: self.canaries,
+            },
+            sequence: self.sequence,
+            next_nonce: self.next_nonce,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Rebuilds a heap-backed manager from what `Serialize` wrote.
+///
+/// # Behavior
+///
+/// Fails if the free list does not match the space the blocks leave free,
+/// or if the result breaks any of the invariants `check_invariants` checks
+/// (blocks outside memory or overlapping, bad checksums), so a damaged or
+/// hand-edited state is refused rather than loaded. The manager starts with
+/// no encryption key; see `Serialize`.
+impl<'de> Deserialize<'de> for MemoryManager {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = State::deserialize(deserializer)?;
+        let capacity = state.memory.len();
+        let allocator: Box<dyn Allocator> = match state.strategy {
+            Strategy::FreeList => Box::new(FreeList::new(capacity)),
+            Strategy::Bitmap { cell: 0 } => return Err(D::Error::custom("bitmap cell size must be at least 1 byte")),
+            Strategy::Bitmap { cell } => Box::new(BitmapAllocator::new(capacity, cell)),
+        };
+
+        let mut manager = MemoryManager::with_allocator(allocator);
+        manager.memory = Buffer::Heap(state.memory.into_owned());
+        manager.allocations = state.allocations.into_owned();
+        manager.content = state.content.into_owned();
+        manager.canaries = state.config.canaries;
+
+        let blocks = manager.allocations.values().chain(manager.content.values().map(|entry| &entry.block));
+        let blocks: Vec<(usize, usize)> =
+            blocks.filter(|block| block.inline.is_none()).map(|block| (block.start, block.size)).collect();
+        let outside = |&(start, size): &(usize, usize)| {
+            let guard = manager.guard_len(size);
+            start < guard || start.checked_add(size + guard).is_none_or(|end| end > capacity)
+        };
+        if blocks.iter().any(outside) {
+            return Err(D::Error::custom("a block or its guard bytes lie outside memory"));
+        }
+        let used = blocks.into_iter().map(|(start, size)| manager.extent(start, size)).collect();
+        manager.allocator.rebuild(used);
+        if manager.allocator.regions() != state.free {
+            return Err(D::Error::custom("the free list does not match the allocation table"));
+        }
+        manager.rebuild_refcounts();
+        manager.check_invariants().map_err(D::Error::custom)?;
+
+        manager.tags = state.tags.into_owned();
+        manager.seals = state.seals.into_owned();
+        manager.pinned = state.pinned.into_owned();
+        manager.versions = state.versions.into_owned();
+        let config = state.config;
+        manager.compression = config.compression;
+        manager.inline_threshold = config.inline_threshold;
+        manager.erase_policy = config.erase_policy;
+        manager.soft_limit = config.soft_limit;
+        manager.backpressure = config.backpressure;
+        manager.dedup = config.dedup;
+        manager.set_history_depth(config.history_depth);
+        manager.version_depth = config.version_depth;
+        manager.sequence = state.sequence;
+        manager.next_nonce = state.next_nonce;
+        Ok(manager)
+    }
+}
+
+impl MemoryManager {
+    /// Sets the key used to encrypt and decrypt block contents.
+    ///
+    /// # Behavior
+    ///
+    /// Meant for a manager rebuilt by `Deserialize`, which never carries the
+    /// key. Blocks encrypted under a different key read back as garbage.
+    pub fn set_encryption_key(&mut self, key: [u8; 32]) {
+        self.encryption_key = Some(key);
+    }
+}
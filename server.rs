@@ -0,0 +1,153 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::thread;
+
+use crate::memory_manager::MemoryManager;
+use crate::operation::{encode_hex, OpResult, Operation};
+use crate::shared::SharedMemoryManager;
+
+impl MemoryManager {
+    /// Runs one command of the text protocol and returns the response line.
+    ///
+    /// # Protocol
+    ///
+    /// Commands are the `Operation` text format (`INSERT 1 48656c6c6f`,
+    /// `READ 1`, `UPDATE`, `DELETE`, `COPY`, `RENAME`, `RESERVE`,
+    /// `INSERT_AT`) plus `DUMP`, `UNDO`, and `REDO`. Each gets exactly one
+    /// response line, without the trailing newline:
+    ///
+    /// - `OK` for a successful change, undo, or redo.
+    /// - `OK <hex>` for a successful `READ`.
+    /// - `OK seq=<n> <id>:<start>:<size>:<hex> ...` for `DUMP`, blocks in ID
+    ///   order, with the stored bytes as they appear in memory.
+    /// - `ERR <reason>` on failure.
+    ///
+    /// # Returns
+    ///
+    /// `None` for blank lines and `#` comments, which get no response.
+    pub fn execute(&mut self, line: &str) -> Option<String> {
+        if let Some(response) = self.execute_read(line) {
+            return Some(response);
+        }
+        // Undo and redo only work with history on, see `set_history_depth`
+        let done = match line.trim().to_ascii_uppercase().as_str() {
+            "UNDO" => Some(self.undo().ok_or("nothing to undo")),
+            "REDO" => Some(self.redo().ok_or("nothing to redo")),
+            _ => None,
+        };
+        if let Some(done) = done {
+            return Some(match done {
+                Ok(()) => "OK".to_string(),
+                Err(reason) => format!("ERR {}", reason),
+            });
+        }
+
+        let op = match Operation::parse(line) {
+            Ok(Some(op)) => op,
+            Ok(None) => return None,
+            Err(e) => return Some(format!("ERR {}", e)),
+        };
+        let response = match self.apply_one(&op) {
+            OpResult::Done => "OK".to_string(),
+            OpResult::Data(data) => format!("OK {}", encode_hex(&data)),
+            OpResult::Failed => {
+                let reason = match &op {
+                    Operation::Insert { id, data } => self.insert_failure(*id, data.len()),
+                    Operation::Read { id } => self.read_failure(*id),
+                    Operation::Update { id, data } => self.update_failure(*id, data.len()),
+                    Operation::Delete { id } => self.delete_failure(*id),
+                    Operation::Reserve { id, size } => self.insert_failure(*id, *size),
+                    Operation::InsertAt { id, start, data } => self.insert_at_failure(*id, *start, data.len()),
+                    Operation::InsertAligned { alignment, .. } if !alignment.is_power_of_two() => {
+                        return Some("ERR alignment must be a power of two".to_string())
+                    }
+                    Operation::InsertAligned { id, data, .. } => self.insert_failure(*id, data.len()),
+                    Operation::Copy { .. } => return Some("ERR copy failed".to_string()),
+                    Operation::Rename { .. } => return Some("ERR rename failed".to_string()),
+                    Operation::Split { .. } => return Some("ERR split failed".to_string()),
+                    Operation::Merge { .. } => return Some("ERR merge failed".to_string()),
+                    Operation::Pin { id } => self.read_failure(*id),
+                    Operation::Unpin { .. } => return Some("ERR block is not pinned".to_string()),
+                    Operation::Compact => return Some("ERR compact failed".to_string()),
+                    Operation::InsertContent { .. } => return Some("ERR content insert failed".to_string()),
+                    Operation::DeleteContent { .. } => return Some("ERR no content has this hash".to_string()),
+                };
+                format!("ERR {}", reason)
+            }
+        };
+        Some(response)
+    }
+
+    /// Runs `line` if it is a read-only command (`READ` or `DUMP`), which
+    /// only needs shared access.
+    ///
+    /// # Returns
+    ///
+    /// The response line as `execute` would give it, or `None` if `line` is
+    /// anything else and has to go through `execute`.
+    pub fn execute_read(&self, line: &str) -> Option<String> {
+        if line.trim().eq_ignore_ascii_case("DUMP") {
+            let mut response = format!("OK seq={}", self.sequence);
+            for (id, block) in &self.allocations {
+                response.push_str(&format!(" {}:{}:{}:{}", id, block.start, block.size, encode_hex(self.stored(block))));
+            }
+            return Some(response);
+        }
+
+        let Ok(Some(Operation::Read { id })) = Operation::parse(line) else {
+            return None;
+        };
+        Some(match self.read(id) {
+            Some(data) => format!("OK {}", encode_hex(&data)),
+            None => format!("ERR {}", self.read_failure(id)),
+        })
+    }
+}
+
+/// Listens on `addr` and serves the text protocol to every client.
+///
+/// # Returns
+///
+/// Only returns if the address cannot be bound or accepting fails.
+///
+/// # Behavior
+///
+/// See `MemoryManager::execute` for the protocol. Each connection is served
+/// on its own thread against the same shared manager, so clients in
+/// different processes see each other's changes. `READ` and `DUMP` only take
+/// the manager's shared lock, so reads from many clients run in parallel.
+/// `QUIT` closes the connection.
+pub fn serve<A: ToSocketAddrs>(addr: A, manager: SharedMemoryManager) -> io::Result<()> {
+    serve_listener(TcpListener::bind(addr)?, manager)
+}
+
+/// Serves the text protocol on an already bound listener.
+pub fn serve_listener(listener: TcpListener, manager: SharedMemoryManager) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let manager = manager.clone();
+        thread::spawn(move || {
+            // A client that disconnects mid-command is not an error worth reporting
+            let _ = handle_client(stream, &manager);
+        });
+    }
+    Ok(())
+}
+
+fn handle_client(stream: TcpStream, manager: &SharedMemoryManager) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().eq_ignore_ascii_case("QUIT") {
+            break;
+        }
+        let response = match manager.with_shared(|manager| manager.execute_read(&line)) {
+            Some(response) => Some(response),
+            None => manager.with(|manager| manager.execute(&line)),
+        };
+        if let Some(response) = response {
+            writeln!(writer, "{}", response)?;
+        }
+    }
+    Ok(())
+}
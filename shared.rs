@@ -0,0 +1,183 @@
+use std::collections::BTreeSet;
+use std::mem;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use log::info;
+
+use crate::handle::Handle;
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::subscriptions::Subscription;
+
+/// A `MemoryManager` that can be shared between threads.
+///
+/// Cloning a `SharedMemoryManager` gives another handle to the same manager.
+/// Each call locks the manager for its whole duration, so every operation
+/// sees and leaves a consistent state. Reads only take the lock shared, so
+/// any number of them run in parallel; writes wait for them and run alone.
+/// The allocation table is not split into separately locked shards, since
+/// most writes also touch the allocator, history, and journal.
+#[derive(Clone)]
+pub struct SharedMemoryManager {
+    inner: Arc<RwLock<MemoryManager>>,
+}
+
+impl SharedMemoryManager {
+    /// Wraps `manager` for shared use.
+    pub fn new(manager: MemoryManager) -> Self {
+        Self { inner: Arc::new(RwLock::new(manager)) }
+    }
+
+    /// Locks the manager for writing. A panic in another thread does not
+    /// poison it for good, since every operation leaves the manager in a
+    /// consistent state.
+    fn lock(&self) -> RwLockWriteGuard<'_, MemoryManager> {
+        self.inner.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Locks the manager for reading, alongside any other readers.
+    fn lock_shared(&self) -> RwLockReadGuard<'_, MemoryManager> {
+        self.inner.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Runs `f` with exclusive access to the manager.
+    ///
+    /// Use this to group several operations so no other thread can observe
+    /// the state between them.
+    pub fn with<R>(&self, f: impl FnOnce(&mut MemoryManager) -> R) -> R {
+        f(&mut self.lock())
+    }
+
+    /// Runs `f` with shared access to the manager.
+    ///
+    /// Other readers run alongside `f`; writers wait until it returns. Use
+    /// this for several reads that must see the same state, or to borrow
+    /// data with `read_ref` instead of copying it.
+    pub fn with_shared<R>(&self, f: impl FnOnce(&MemoryManager) -> R) -> R {
+        f(&self.lock_shared())
+    }
+
+    /// See [`MemoryManager::insert`].
+    pub fn insert(&self, id: BlockId, data: Vec<u8>) -> Option<Handle> {
+        self.lock().insert(id, data)
+    }
+
+    /// See [`MemoryManager::read`].
+    pub fn read(&self, id: BlockId) -> Option<Vec<u8>> {
+        self.lock_shared().read(id)
+    }
+
+    /// See [`MemoryManager::update`].
+    pub fn update(&self, id: BlockId, data: Vec<u8>) -> Option<()> {
+        self.lock().update(id, data)
+    }
+
+    /// See [`MemoryManager::delete`].
+    pub fn delete(&self, id: BlockId) -> Option<()> {
+        self.lock().delete(id)
+    }
+
+    /// Returns the sequence number of the current state.
+    pub fn sequence(&self) -> u64 {
+        self.lock_shared().sequence()
+    }
+
+    /// Renders a dump of a single point in time.
+    ///
+    /// # Returns
+    ///
+    /// The dump text, whose header carries the sequence number it reflects.
+    /// The manager is locked only while the text is built, so a concurrent
+    /// writer can never leave a dump half old and half new.
+    pub fn dump_snapshot(&self) -> String {
+        self.lock_shared().render_dump(false)
+    }
+
+    /// Logs a consistent dump, as built by `dump_snapshot()`, at info level.
+    pub fn dump(&self) {
+        info!("{}", self.dump_snapshot().trim_end());
+    }
+
+    /// Moves every block into `target` and makes it the shared manager.
+    ///
+    /// # Parameters
+    ///
+    /// - `target`: An empty manager, typically with a larger capacity or a
+    ///   different configuration (compression, encryption, inline threshold).
+    ///
+    /// # Returns
+    ///
+    /// - `Some(old)`: The manager that was replaced, left as it was.
+    /// - `None` if a block cannot be read or does not fit in `target`, or a
+    ///   transaction is open. The shared manager is unchanged and `target`
+    ///   is dropped.
+    ///
+    /// # Behavior
+    ///
+    /// Blocks are copied one at a time, locking the manager only for each
+    /// copy and only for reading, so other threads keep reading and writing
+    /// throughout. Blocks changed meanwhile are copied again in a final pass,
+    /// which also copies tags, seals, pins, and content-addressed blocks,
+    /// then swaps the managers under the same lock. Subscribers move to the new manager. Undo history
+    /// and named snapshots describe the old layout and are not carried over.
+    /// Contents changed by restoring a snapshot during the migration are not
+    /// reported to subscribers and so are not picked up.
+    pub fn migrate_to(&self, mut target: MemoryManager) -> Option<MemoryManager> {
+        let changes = {
+            let mut source = self.lock();
+            if source.in_transaction() {
+                return None;
+            }
+            source.subscribe(Subscription::All)
+        };
+
+        let ids: Vec<BlockId> = {
+            let source = self.lock_shared();
+            source.allocations.keys().copied().chain(source.swapped_ids()).collect()
+        };
+        for id in ids {
+            migrate_block(&self.lock_shared(), &mut target, id)?;
+        }
+
+        let mut source = self.lock();
+        if source.in_transaction() {
+            return None;
+        }
+
+        // Catch up on blocks changed during the first pass, plus any whose
+        // presence differs without a change having been reported
+        let mut changed: BTreeSet<BlockId> = changes.try_iter().map(|change| change.id()).collect();
+        changed.extend(source.allocations.keys().filter(|&&id| !target.exists(id)));
+        changed.extend(target.allocations.keys().filter(|&&id| !source.exists(id)));
+        drop(changes);
+        for id in changed {
+            migrate_block(&source, &mut target, id)?;
+        }
+
+        for (&hash, entry) in &source.content {
+            let data = source.read_content(hash)?;
+            for _ in 0..entry.refs {
+                target.insert_content(data.clone())?;
+            }
+        }
+
+        target.tags = source.tags.clone();
+        target.versions = source.versions.clone();
+        target.seals = source.seals.clone();
+        target.pinned = source.pinned.clone();
+        target.history.clear();
+        target.sequence = source.sequence;
+        target.subscribers = mem::take(&mut source.subscribers);
+        Some(mem::replace(&mut *source, target))
+    }
+}
+
+/// Makes `target`'s copy of block `id` match `source`, copying or deleting as needed.
+fn migrate_block(source: &MemoryManager, target: &mut MemoryManager, id: BlockId) -> Option<()> {
+    if target.exists(id) {
+        target.delete(id)?;
+    }
+    if source.exists(id) {
+        target.insert(id, source.peek(id)?)?;
+    }
+    Some(())
+}
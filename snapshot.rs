@@ -0,0 +1,115 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::content::ContentEntry;
+use crate::allocator::Allocator;
+use crate::memory_manager::{Block, BlockId, MemoryManager};
+
+/// A full copy of a manager's memory and allocation tables.
+#[derive(Clone)]
+pub(crate) struct Snapshot {
+    memory: Vec<u8>,
+    allocations: BTreeMap<BlockId, Block>,
+    allocator: Box<dyn Allocator>,
+    content: HashMap<u64, ContentEntry>,
+    tags: HashMap<BlockId, BTreeMap<String, String>>,
+    seals: HashMap<BlockId, Vec<u8>>,
+    pinned: HashSet<BlockId>,
+    versions: HashMap<BlockId, VecDeque<Vec<u8>>>,
+    canaries: bool, // The guard layout the blocks were placed with
+    swapped: Vec<(BlockId, Block)>, // Swapped-out blocks, with their stored bytes inline
+}
+
+impl MemoryManager {
+    /// Saves the current state under `name`.
+    ///
+    /// # Behavior
+    ///
+    /// Captures the full memory contents and allocation tables in memory,
+    /// along with a copy of every block swapped out to disk.
+    /// An existing snapshot with the same name is replaced.
+    pub fn snapshot(&mut self, name: &str) {
+        let snapshot = self.capture();
+        self.snapshots.insert(name.to_string(), snapshot);
+    }
+
+    /// Returns the manager to the state saved by `snapshot(name)`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the state was restored.
+    /// - `None` if there is no snapshot with that name, or the manager is
+    ///   journaling (a restore cannot be replayed from the journal).
+    ///
+    /// # Behavior
+    ///
+    /// The snapshot is kept, so it can be restored again later.
+    pub fn restore(&mut self, name: &str) -> Option<()> {
+        if self.journal.is_some() {
+            return None;
+        }
+        let snapshot = self.snapshots.get(name)?.clone();
+        self.restore_snapshot(&snapshot);
+        Some(())
+    }
+
+    /// Returns the names of all saved snapshots in alphabetical order.
+    pub fn list_snapshots(&self) -> Vec<String> {
+        self.snapshots.keys().cloned().collect()
+    }
+
+    /// Discards the snapshot saved under `name`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the snapshot existed.
+    /// - `None` otherwise.
+    pub fn delete_snapshot(&mut self, name: &str) -> Option<()> {
+        self.snapshots.remove(name).map(|_| ())
+    }
+
+    /// Copies the current memory contents and allocation table.
+    pub(crate) fn capture(&self) -> Snapshot {
+        Snapshot {
+            memory: self.memory.to_vec(),
+            allocations: self.allocations.clone(),
+            allocator: self.allocator.clone(),
+            content: self.content.clone(),
+            tags: self.tags.clone(),
+            seals: self.seals.clone(),
+            pinned: self.pinned.clone(),
+            versions: self.versions.clone(),
+            canaries: self.canaries,
+            swapped: self.swapped_blocks(),
+        }
+    }
+
+    /// Puts the memory contents and allocation table back as they were in `snapshot`.
+    ///
+    /// Undo history is cleared, since it describes operations on the state
+    /// being replaced. Handles to blocks the snapshot does not have are
+    /// retired, so they stay stale if the ID is used again.
+    pub(crate) fn restore_snapshot(&mut self, snapshot: &Snapshot) {
+        let in_snapshot = |id: &BlockId| {
+            snapshot.allocations.contains_key(id) || snapshot.swapped.iter().any(|(swapped, _)| swapped == id)
+        };
+        let dropped: Vec<BlockId> = self.allocations.keys().copied().chain(self.swapped_ids()).filter(|id| !in_snapshot(id)).collect();
+        for id in dropped {
+            self.retire_handles(id);
+        }
+
+        self.memory.copy_from_slice(&snapshot.memory);
+        self.allocations = snapshot.allocations.clone();
+        self.allocator = snapshot.allocator.clone();
+        self.content = snapshot.content.clone();
+        self.tags = snapshot.tags.clone();
+        self.seals = snapshot.seals.clone();
+        self.pinned = snapshot.pinned.clone();
+        self.versions = snapshot.versions.clone();
+        self.canaries = snapshot.canaries;
+        self.restore_swapped(&snapshot.swapped);
+        self.rebuild_refcounts();
+        self.history.clear();
+        self.sequence += 1;
+        self.save_index();
+    }
+}
@@ -0,0 +1,191 @@
+use crate::checksum::crc32;
+use crate::history::HistoryEntry;
+use crate::memory_manager::{Block, BlockId, MemoryManager};
+use crate::operation::Operation;
+use crate::subscriptions::Change;
+
+impl MemoryManager {
+    /// Divides a block into two adjacent blocks with new IDs.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The block to divide.
+    /// - `offset`: Where the second block starts, relative to the first.
+    ///
+    /// # Returns
+    ///
+    /// - `Some((first, second))` with the IDs of the blocks holding the bytes
+    ///   before and after `offset`. These are the two lowest unused IDs.
+    /// - `None` if the block cannot be split (see `split_into`).
+    pub fn split(&mut self, id: BlockId, offset: usize) -> Option<(BlockId, BlockId)> {
+        let mut free = (0..=BlockId::MAX).filter(|&candidate| candidate != id && !self.exists(candidate));
+        let (first, second) = (free.next()?, free.next()?);
+        self.split_into(id, offset, first, second)?;
+        Some((first, second))
+    }
+
+    /// Divides a block into two adjacent blocks stored under `first` and `second`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the block was split.
+    /// - `None` with canaries on, or if the block does not exist; is inline,
+    ///   compressed, encrypted, sealed, corrupted, or shares its region through
+    ///   dedup; if `offset` is not strictly inside the block or not a multiple of the
+    ///   allocator's unit; or if `first` or `second` is already taken.
+    ///
+    /// # Behavior
+    ///
+    /// No bytes move: the region is simply recorded as two blocks. Both
+    /// halves get the original block's tags and pin, but no previous
+    /// versions. Handles to `id` become stale. Subscribers see `id` deleted
+    /// and both halves inserted.
+    pub fn split_into(&mut self, id: BlockId, offset: usize, first: BlockId, second: BlockId) -> Option<()> {
+        if self.is_swapped(id) {
+            self.swap_in(id)?;
+        }
+        let block = self.allocations.get(&id)?;
+        if !self.is_plain(id, block) || offset == 0 || offset >= block.size {
+            return None;
+        }
+        if !offset.is_multiple_of(self.allocator.granularity()) {
+            return None;
+        }
+        let taken = |other: BlockId| other != id && self.exists(other);
+        if first == second || taken(first) || taken(second) {
+            return None;
+        }
+        if !self.journal_operation(|| Operation::Split { id, offset, first, second }) {
+            return None;
+        }
+
+        let block = self.allocations.remove(&id)?;
+        let pinned = self.pinned.remove(&id);
+        let tags = self.tags.remove(&id);
+        self.versions.remove(&id);
+        self.retire_handles(id);
+        for (part, start, size) in [(first, block.start, offset), (second, block.start + offset, block.size - offset)] {
+            self.allocations.insert(part, self.plain_block(start, size));
+            if pinned {
+                self.pinned.insert(part);
+            }
+            if let Some(tags) = &tags {
+                self.tags.insert(part, tags.clone());
+            }
+        }
+        self.save_index();
+        self.history.record(HistoryEntry::Split { id, offset, first, second });
+        self.sequence += 1;
+        self.notify(Change::Deleted { id });
+        self.notify(Change::Inserted { id: first, size: offset });
+        self.notify(Change::Inserted { id: second, size: block.size - offset });
+
+        Some(())
+    }
+
+    /// Joins two adjacent blocks into one stored under `first`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the blocks were merged.
+    /// - `None` if they cannot be merged (see `merge_into`).
+    pub fn merge(&mut self, first: BlockId, second: BlockId) -> Option<()> {
+        self.merge_into(first, second, first)
+    }
+
+    /// Joins two adjacent blocks into one stored under `into`.
+    ///
+    /// # Parameters
+    ///
+    /// - `first`: The block whose region comes first.
+    /// - `second`: The block whose region starts right where `first` ends.
+    /// - `into`: The ID of the merged block: `first`, `second`, or an unused ID.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the blocks were merged.
+    /// - `None` with canaries on, or if either block does not exist; is inline,
+    ///   compressed, encrypted, sealed, corrupted, or shares its region through
+    ///   dedup; if the two are not adjacent in that order; or if `into` is already taken.
+    ///
+    /// # Behavior
+    ///
+    /// No bytes move. The merged block keeps the tags of `first`, is pinned
+    /// if either block was, and starts with no previous versions. Handles to
+    /// both blocks become stale. Subscribers see both blocks deleted and the
+    /// merged block inserted.
+    pub fn merge_into(&mut self, first: BlockId, second: BlockId, into: BlockId) -> Option<()> {
+        for id in [first, second] {
+            if self.is_swapped(id) {
+                self.swap_in(id)?;
+            }
+        }
+        let a = self.allocations.get(&first)?;
+        let b = self.allocations.get(&second)?;
+        if first == second || !self.is_plain(first, a) || !self.is_plain(second, b) || a.start + a.size != b.start {
+            return None;
+        }
+        if into != first && into != second && self.exists(into) {
+            return None;
+        }
+        if !self.journal_operation(|| Operation::Merge { first, second, into }) {
+            return None;
+        }
+
+        let a = self.allocations.remove(&first)?;
+        let b = self.allocations.remove(&second)?;
+        let pinned = self.pinned.remove(&first) | self.pinned.remove(&second);
+        let tags = self.tags.remove(&first);
+        self.tags.remove(&second);
+        self.versions.remove(&first);
+        self.versions.remove(&second);
+        self.retire_handles(first);
+        self.retire_handles(second);
+        let size = a.size + b.size;
+        self.allocations.insert(into, self.plain_block(a.start, size));
+        if pinned {
+            self.pinned.insert(into);
+        }
+        if let Some(tags) = tags {
+            self.tags.insert(into, tags);
+        }
+        self.save_index();
+        self.history.record(HistoryEntry::Merged { first, second, into, offset: a.size });
+        self.sequence += 1;
+        self.notify(Change::Deleted { id: first });
+        self.notify(Change::Deleted { id: second });
+        self.notify(Change::Inserted { id: into, size });
+
+        Some(())
+    }
+
+    /// Returns `true` if the block's bytes in memory are exactly its data, so
+    /// its region can be cut or joined without rewriting anything. Never
+    /// true with canaries on, since the pieces would need guards of their own,
+    /// nor for a corrupted block, whose pieces would get fresh checksums over
+    /// the corrupted bytes.
+    fn is_plain(&self, id: BlockId, block: &Block) -> bool {
+        !self.canaries
+            && block.inline.is_none()
+            && !block.compressed
+            && block.nonce.is_none()
+            && block.size > 0
+            && !self.is_shared(id)
+            && !self.seals.contains_key(&id)
+            && crc32(self.stored(block)) == block.checksum
+    }
+
+    /// Describes the plain bytes currently at `start..start + size`.
+    fn plain_block(&self, start: usize, size: usize) -> Block {
+        Block {
+            start,
+            size,
+            checksum: crc32(&self.memory[start..start + size]),
+            raw_size: size,
+            stored_size: size,
+            compressed: false,
+            nonce: None,
+            inline: None,
+        }
+    }
+}
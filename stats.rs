@@ -0,0 +1,37 @@
+use crate::memory_manager::MemoryManager;
+
+/// A summary of how memory is being used.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub allocations: usize,  // Number of live blocks, including content-addressed ones
+    pub capacity: usize,     // Total size of the memory block
+    pub used_bytes: usize,   // Bytes of memory reserved by live blocks, counting shared regions once
+    pub raw_bytes: usize,    // Bytes callers asked to store
+    pub stored_bytes: usize, // Bytes those payloads occupy after compression
+    pub dedup_saved: usize,  // Bytes not stored because blocks share identical regions
+}
+
+impl MemoryManager {
+    /// Collects usage statistics for the current allocations.
+    ///
+    /// # Returns
+    ///
+    /// A `Stats` value. `raw_bytes` and `stored_bytes` only differ when
+    /// compression is enabled and has been able to shrink some payloads.
+    /// `dedup_saved` is only non-zero when dedup has shared some regions.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats { capacity: self.memory.len(), ..Stats::default() };
+
+        let content = self.content.values().map(|entry| &entry.block);
+        for block in self.allocations.values().chain(content) {
+            stats.allocations += 1;
+            stats.used_bytes += block.size;
+            stats.raw_bytes += block.raw_size;
+            stats.stored_bytes += block.stored_size;
+        }
+        stats.dedup_saved = self.dedup_saved_bytes();
+        stats.used_bytes -= stats.dedup_saved;
+
+        stats
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::mpsc::{channel, Receiver};
+
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// Selects which changes a subscriber receives.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Subscription {
+    /// Changes to a single ID.
+    Id(BlockId),
+    /// Changes to any block carrying the tag `key` with the given value.
+    Tag(String, String),
+    /// Every change made to the manager.
+    All,
+}
+
+/// A change made to an allocation, delivered to matching subscribers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Inserted { id: BlockId, size: usize },
+    Updated { id: BlockId, size: usize },
+    Deleted { id: BlockId },
+}
+
+impl Change {
+    /// Returns the ID of the block this change applies to.
+    pub fn id(&self) -> BlockId {
+        match *self {
+            Change::Inserted { id, .. } | Change::Updated { id, .. } | Change::Deleted { id } => id,
+        }
+    }
+}
+
+
+impl MemoryManager {
+    /// Subscribes to changes on the manager.
+    ///
+    /// # Parameters
+    ///
+    /// - `subscription`: Which changes to receive.
+    ///
+    /// # Returns
+    ///
+    /// A `Receiver` that yields a `Change` after every matching insert,
+    /// update, or delete. Dropping the receiver ends the subscription.
+    pub fn subscribe(&mut self, subscription: Subscription) -> Receiver<Change> {
+        let (sender, receiver) = channel();
+        self.subscribers.push((subscription, sender));
+        receiver
+    }
+
+    /// Delivers `change` to every matching subscriber, forgetting any whose
+    /// receiver has been dropped.
+    ///
+    /// Tag subscriptions match against the block's tags at the time of the
+    /// change, so a delete is delivered before its tags are dropped. This is
+    /// also where `on_event` callbacks run, where `metrics()` counts changes,
+    /// where the audit trail records them, and, with the `access-times`
+    /// feature, where write times are recorded.
+    pub(crate) fn notify(&mut self, change: Change) {
+        #[cfg(feature = "access-times")]
+        self.record_write(&change);
+        self.counters.change(&change);
+        self.audit_change(&change);
+        self.swap_change(&change);
+        self.count_change(&change);
+        self.emit_change(&change);
+        self.watch_change(&change);
+        self.trace_change();
+        self.check_pressure();
+
+        let tags = self.tags.get(&change.id());
+        self.subscribers.retain(|(subscription, sender)| {
+            let matches = match subscription {
+                Subscription::Id(id) => *id == change.id(),
+                Subscription::Tag(key, value) => tags.and_then(|tags| tags.get(key)) == Some(value),
+                Subscription::All => true,
+            };
+            !matches || sender.send(change.clone()).is_ok()
+        });
+    }
+}
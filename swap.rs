@@ -0,0 +1,311 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use log::warn;
+
+use crate::checksum::crc32;
+use crate::free_list::FreeList;
+use crate::memory_manager::{Block, BlockId, MemoryManager};
+use crate::subscriptions::Change;
+
+/// Swap activity, as returned by `swap_stats()`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapStats {
+    pub swap_outs: u64,       // Blocks moved from memory to the swap file
+    pub swap_ins: u64,        // Times a block's bytes were read back from the swap file
+    pub swapped: usize,       // Blocks currently in the swap file
+    pub swapped_bytes: usize, // Memory those blocks take once swapped back in
+}
+
+/// The swap file and the blocks held in it.
+pub(crate) struct Swap {
+    file: Mutex<File>,                        // Locked so `read(&self)` can seek without racing
+    space: FreeList<Vec<(usize, usize)>>,     // Which bytes of the file are free
+    slots: BTreeMap<BlockId, (Block, usize)>, // id -> block as it was in memory, and its offset in the file
+    used: HashMap<BlockId, AtomicU64>,        // id -> tick of its last access, to find cold blocks
+    clock: AtomicU64,                         // Ticks once per access
+    swap_outs: u64,                           // Blocks written to the file so far
+    swap_ins: AtomicU64,                      // Blocks read back from the file so far
+}
+
+impl Swap {
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    fn read_slot(&self, offset: usize, size: usize) -> io::Result<Vec<u8>> {
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut bytes = vec![0; size];
+        file.seek(SeekFrom::Start(offset as u64))?;
+        file.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn write_slot(&self, offset: usize, bytes: &[u8]) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        file.seek(SeekFrom::Start(offset as u64))?;
+        file.write_all(bytes)
+    }
+}
+
+impl MemoryManager {
+    /// Creates a new `MemoryManager` that swaps cold blocks out to a file
+    /// at `path` holding up to `capacity` bytes. See `set_swap`.
+    pub fn with_swap<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        let mut manager = Self::new();
+        manager.set_swap(path, capacity)?;
+        Ok(manager)
+    }
+
+    /// Turns on swapping to a file at `path` holding up to `capacity` bytes.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` once the swap file is created. An existing file is truncated.
+    /// - `Err` if the file cannot be created, blocks are swapped out to a
+    ///   previous swap file, or the manager is file-backed, since the index
+    ///   file does not record swapped blocks.
+    ///
+    /// # Behavior
+    ///
+    /// When an insert or reserve finds no room, the least recently used
+    /// blocks are moved to the swap file one at a time until it fits, so the
+    /// insert only fails once nothing more can be swapped out. Inline,
+    /// pinned, and deduplicated blocks stay in memory.
+    ///
+    /// Swapped blocks keep their ID, tags, seals, and versions. Reads are
+    /// served from the swap file, since `read` cannot change the manager;
+    /// anything that changes a swapped block (an update, copy, rename, pin,
+    /// split, or merge) moves it back into memory first, swapping out others
+    /// if needed. Deleting a swapped block just frees its slot. `swap_in`
+    /// moves a block back explicitly. Swapped blocks take no memory, so they
+    /// do not show up in `dump()`, `stats()`, or the memory map, and a
+    /// manager with swapped blocks cannot be serialized.
+    pub fn set_swap<P: AsRef<Path>>(&mut self, path: P, capacity: usize) -> io::Result<()> {
+        if self.index_path.is_some() {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "file-backed managers cannot swap"));
+        }
+        if self.swap.as_ref().is_some_and(|swap| !swap.slots.is_empty()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "blocks are still swapped out"));
+        }
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len(capacity as u64)?;
+        self.swap = Some(Swap {
+            file: Mutex::new(file),
+            space: FreeList::new(capacity),
+            slots: BTreeMap::new(),
+            used: self.allocations.keys().map(|&id| (id, AtomicU64::new(0))).collect(),
+            clock: AtomicU64::new(0),
+            swap_outs: 0,
+            swap_ins: AtomicU64::new(0),
+        });
+        Ok(())
+    }
+
+    /// Returns `true` if block `id` is currently in the swap file.
+    pub fn is_swapped(&self, id: BlockId) -> bool {
+        self.swap.as_ref().is_some_and(|swap| swap.slots.contains_key(&id))
+    }
+
+    /// Returns the swap activity so far. All zeros with swapping off.
+    pub fn swap_stats(&self) -> SwapStats {
+        let Some(swap) = &self.swap else {
+            return SwapStats::default();
+        };
+        SwapStats {
+            swap_outs: swap.swap_outs,
+            swap_ins: swap.swap_ins.load(Ordering::Relaxed),
+            swapped: swap.slots.len(),
+            swapped_bytes: swap.slots.values().map(|(block, _)| block.size).sum(),
+        }
+    }
+
+    /// Moves block `id` from memory to the swap file.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the block was swapped out.
+    /// - `None` if swapping is off, the block does not exist or is already
+    ///   swapped out, is inline, empty, pinned, or shares its region through
+    ///   dedup, its guards were overwritten, or the swap file is full or
+    ///   cannot be written (see `take_io_error()`).
+    pub fn swap_out(&mut self, id: BlockId) -> Option<()> {
+        let block = self.allocations.get(&id)?.clone();
+        if block.inline.is_some() || block.size == 0 || self.pinned.contains(&id) || self.is_shared(id) {
+            return None;
+        }
+        if !self.guards_intact(id) {
+            return None;
+        }
+        let swap = self.swap.as_mut()?;
+        let offset = swap.space.allocate(block.size, 1)?;
+        if let Err(e) = swap.write_slot(offset, &self.memory[block.start..block.start + block.size]) {
+            swap.space.free(offset, block.size);
+            self.io_error = Some(io::Error::new(e.kind(), format!("failed to write swap file: {}", e)));
+            return None;
+        }
+
+        swap.swap_outs += 1;
+        swap.slots.insert(id, (block.clone(), offset));
+        self.allocations.remove(&id);
+        self.release_region(block.start, block.size, self.erase_policy);
+        Some(())
+    }
+
+    /// Moves block `id` from the swap file back into memory, swapping out
+    /// other blocks if there is no room.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the block is back in memory.
+    /// - `None` if it was not swapped out, or cannot be brought back.
+    pub fn swap_in(&mut self, id: BlockId) -> Option<()> {
+        let (block, offset) = self.swap.as_ref()?.slots.get(&id)?.clone();
+        let start = self.allocate_swapping(block.size, 1)?;
+        let swap = self.swap.as_mut()?;
+        let bytes = match swap.read_slot(offset, block.size) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                self.free_region(start, block.size);
+                self.io_error = Some(io::Error::new(e.kind(), format!("failed to read swap file: {}", e)));
+                return None;
+            }
+        };
+
+        swap.swap_ins.fetch_add(1, Ordering::Relaxed);
+        swap.slots.remove(&id);
+        swap.space.free(offset, block.size);
+        self.memory[start..start + block.size].copy_from_slice(&bytes);
+        self.allocations.insert(id, Block { start, ..block });
+        Some(())
+    }
+
+    /// Returns `true` if a block is stored under `id`, in memory or swapped out.
+    pub(crate) fn exists(&self, id: BlockId) -> bool {
+        self.allocations.contains_key(&id) || self.is_swapped(id)
+    }
+
+    /// Returns the IDs of all swapped-out blocks, in ID order.
+    pub(crate) fn swapped_ids(&self) -> Vec<BlockId> {
+        self.swap.as_ref().map_or_else(Vec::new, |swap| swap.slots.keys().copied().collect())
+    }
+
+    /// Returns swapped-out block `id` as it was in memory.
+    pub(crate) fn swapped_block(&self, id: BlockId) -> Option<&Block> {
+        self.swap.as_ref()?.slots.get(&id).map(|(block, _)| block)
+    }
+
+    /// Reserves memory like `allocate_aligned`, swapping out the least
+    /// recently used blocks while there is no room.
+    pub(crate) fn allocate_swapping(&mut self, size: usize, alignment: usize) -> Option<usize> {
+        loop {
+            if let Some(start) = self.allocate_aligned(size, alignment) {
+                return Some(start);
+            }
+            let swap = self.swap.as_ref()?;
+            let last_use = |id: &BlockId| swap.used.get(id).map_or(0, |tick| tick.load(Ordering::Relaxed));
+            let mut candidates: Vec<BlockId> = self.allocations.keys().copied().collect();
+            candidates.sort_by_key(|id| (last_use(id), *id));
+            candidates.into_iter().find_map(|id| self.swap_out(id))?;
+        }
+    }
+
+    /// Reads and decodes a swapped-out block, checking it against its checksum.
+    pub(crate) fn read_swapped(&self, id: BlockId) -> Option<Vec<u8>> {
+        let swap = self.swap.as_ref()?;
+        let (block, offset) = swap.slots.get(&id)?;
+        let bytes = match swap.read_slot(*offset, block.size) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("failed to read block {} from the swap file: {}", id, e);
+                return None;
+            }
+        };
+        swap.swap_ins.fetch_add(1, Ordering::Relaxed);
+        if crc32(&bytes) != block.checksum {
+            return None;
+        }
+        self.decode_region(block, &bytes).map(|data| data.into_owned())
+    }
+
+    /// Removes block `id` from the swap file, returning it with its stored
+    /// bytes kept inline. The slot is zeroed before it is freed.
+    pub(crate) fn take_swapped(&mut self, id: BlockId) -> Option<Block> {
+        let swap = self.swap.as_mut()?;
+        let (block, offset) = swap.slots.remove(&id)?;
+        let bytes = swap.read_slot(offset, block.size).unwrap_or_default();
+        if let Err(e) = swap.write_slot(offset, &vec![0; block.size]) {
+            warn!("failed to erase block {} from the swap file: {}", id, e);
+        }
+        swap.space.free(offset, block.size);
+        Some(Block { inline: Some(bytes), ..block })
+    }
+
+    /// Returns every swapped-out block with its stored bytes kept inline,
+    /// for snapshots.
+    pub(crate) fn swapped_blocks(&self) -> Vec<(BlockId, Block)> {
+        let Some(swap) = &self.swap else {
+            return Vec::new();
+        };
+        swap.slots
+            .iter()
+            .filter_map(|(&id, (block, offset))| {
+                let bytes = swap.read_slot(*offset, block.size).ok()?;
+                Some((id, Block { inline: Some(bytes), ..block.clone() }))
+            })
+            .collect()
+    }
+
+    /// Replaces the contents of the swap file with `blocks`, as returned by
+    /// `swapped_blocks`.
+    pub(crate) fn restore_swapped(&mut self, blocks: &[(BlockId, Block)]) {
+        let Some(swap) = &mut self.swap else {
+            return;
+        };
+        for (_, (block, offset)) in std::mem::take(&mut swap.slots) {
+            swap.space.free(offset, block.size);
+        }
+        for (id, block) in blocks {
+            let bytes = block.inline.as_deref().unwrap_or_default();
+            let Some(offset) = swap.space.allocate(block.size, 1) else {
+                warn!("no room in the swap file for block {}, which is lost", id);
+                continue;
+            };
+            if let Err(e) = swap.write_slot(offset, bytes) {
+                swap.space.free(offset, block.size);
+                warn!("failed to write block {} back to the swap file, so it is lost: {}", id, e);
+                continue;
+            }
+            swap.slots.insert(*id, (Block { inline: None, ..block.clone() }, offset));
+        }
+    }
+
+    /// Marks block `id` as just used, so it is swapped out last.
+    pub(crate) fn swap_touch(&self, id: BlockId) {
+        if let Some(swap) = &self.swap {
+            if let Some(tick) = swap.used.get(&id) {
+                tick.store(swap.tick(), Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Keeps the last-use ticks in step with `change`.
+    pub(crate) fn swap_change(&mut self, change: &Change) {
+        let Some(swap) = &mut self.swap else {
+            return;
+        };
+        match *change {
+            Change::Inserted { id, .. } | Change::Updated { id, .. } => {
+                let tick = swap.tick();
+                swap.used.insert(id, AtomicU64::new(tick));
+            }
+            Change::Deleted { id } => {
+                swap.used.remove(&id);
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+
+impl MemoryManager {
+    /// Attaches a key/value tag to a block.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The block to tag.
+    /// - `key`: The tag name, e.g. `"type"`.
+    /// - `value`: The tag value, e.g. `"string"`. Replaces any previous value.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the tag was set.
+    /// - `None` if the block does not exist.
+    pub fn set_tag(&mut self, id: BlockId, key: &str, value: &str) -> Option<()> {
+        if !self.exists(id) {
+            return None;
+        }
+        self.tags.entry(id).or_default().insert(key.to_string(), value.to_string());
+        Some(())
+    }
+
+    /// Returns the value of a block's tag, if set.
+    pub fn tag(&self, id: BlockId, key: &str) -> Option<&str> {
+        self.tags.get(&id)?.get(key).map(String::as_str)
+    }
+
+    /// Returns all tags of a block, ordered by key. Empty if it has none.
+    pub fn tags(&self, id: BlockId) -> BTreeMap<String, String> {
+        self.tags.get(&id).cloned().unwrap_or_default()
+    }
+
+    /// Removes a tag from a block.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(value)` with the removed value.
+    /// - `None` if the block did not have that tag.
+    pub fn remove_tag(&mut self, id: BlockId, key: &str) -> Option<String> {
+        let tags = self.tags.get_mut(&id)?;
+        let value = tags.remove(key);
+        if tags.is_empty() {
+            self.tags.remove(&id);
+        }
+        value
+    }
+
+    /// Finds every block tagged with `key` set to `value`.
+    ///
+    /// # Returns
+    ///
+    /// The matching IDs in ascending order.
+    pub fn find_by_tag(&self, key: &str, value: &str) -> Vec<BlockId> {
+        let mut ids: Vec<BlockId> = self
+            .tags
+            .iter()
+            .filter(|(_, tags)| tags.get(key).is_some_and(|v| v == value))
+            .map(|(&id, _)| id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Formats a block's tags for `dump()`, e.g. ` Tags: {type=string}`.
+    /// Returns an empty string for untagged blocks.
+    pub(crate) fn format_tags(&self, id: BlockId) -> String {
+        match self.tags.get(&id) {
+            Some(tags) if !tags.is_empty() => {
+                let pairs: Vec<String> = tags.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+                format!(" Tags: {{{}}}", pairs.join(", "))
+            }
+            _ => String::new(),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use crate::memory_manager::MemoryManager;
+
+/// What `insert` does once memory use is above the soft limit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Backpressure {
+    /// Insert normally; only the hard capacity limit applies.
+    #[default]
+    Off,
+    /// Sleep for this long before each insert, slowing bursty producers down.
+    /// Ignored on wasm32, where blocking is not allowed.
+    Delay(Duration),
+    /// Truncate data that would not fit in the remaining space, instead of
+    /// rejecting the insert outright.
+    Shrink,
+}
+
+impl MemoryManager {
+    /// Sets a soft memory limit and how inserts behave above it.
+    ///
+    /// # Parameters
+    ///
+    /// - `limit`: Number of used bytes above which `policy` kicks in.
+    /// - `policy`: The backpressure to apply. `Backpressure::Off` disables throttling.
+    pub fn set_soft_limit(&mut self, limit: usize, policy: Backpressure) {
+        self.soft_limit = limit;
+        self.backpressure = policy;
+    }
+
+    /// Applies the backpressure policy to data about to be inserted.
+    ///
+    /// Returns the data to insert, which is shorter than `data` only when the
+    /// `Shrink` policy had to truncate it.
+    pub(crate) fn throttle(&self, mut data: Vec<u8>) -> Vec<u8> {
+        let free = self.free_bytes();
+        let used = self.memory.len() - free;
+        if used + data.len() <= self.soft_limit {
+            return data;
+        }
+
+        match self.backpressure {
+            Backpressure::Off => {}
+            #[cfg(not(target_arch = "wasm32"))]
+            Backpressure::Delay(delay) => std::thread::sleep(delay),
+            // The browser's main thread cannot block, so delays are skipped there
+            #[cfg(target_arch = "wasm32")]
+            Backpressure::Delay(_) => {}
+            Backpressure::Shrink => data.truncate(free),
+        }
+        data
+    }
+}
@@ -0,0 +1,130 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::operation::encode_hex;
+
+/// A trace being recorded, see `start_trace`.
+///
+/// Behind a mutex so `read(&self)` can record reads too.
+pub(crate) struct Trace {
+    state: Mutex<TraceState>,
+}
+
+struct TraceState {
+    file: File,
+    pending: Option<String>,  // Last operation, written once its result is known
+    changed: bool,            // Whether a change was applied since `pending` was recorded
+    operations: usize,        // Operations recorded so far
+    error: Option<io::Error>, // First write failure, reported by `stop_trace`
+}
+
+impl TraceState {
+    /// Writes the pending operation with its result.
+    fn resolve(&mut self) {
+        if let Some(line) = self.pending.take() {
+            let result = if self.changed { "OK" } else { "ERR" };
+            self.write(&format!("{} -> {}", line, result));
+        }
+    }
+
+    fn write(&mut self, line: &str) {
+        if let Err(e) = writeln!(self.file, "{}", line) {
+            self.error.get_or_insert(e);
+        }
+    }
+}
+
+impl MemoryManager {
+    /// Starts recording every operation and its result to `path`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if recording started. The file is replaced.
+    /// - `Err(e)` if the manager already holds blocks or is already
+    ///   recording, or the file cannot be created.
+    ///
+    /// # Behavior
+    ///
+    /// Each operation that has a text form (see `Operation`) is written as
+    /// one line followed by ` -> OK`, ` -> OK <hex>` for reads, or ` -> ERR`,
+    /// as soon as its result is known. Transactions are recorded as `BEGIN`,
+    /// `COMMIT`, and `ROLLBACK` lines. `stop_trace` ends the file with the
+    /// final state hash, and `replay::replay_trace` checks it. Lines are
+    /// written unbuffered, so a trace cut short by a crash keeps everything
+    /// up to the crash.
+    pub fn start_trace<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        if self.trace.is_some() {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "a trace is already being recorded"));
+        }
+        // Replays start from a fresh manager, so the trace must too
+        if !self.allocations.is_empty() || !self.swapped_ids().is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "traces must start from an empty manager"));
+        }
+        let file = File::create(path)?;
+        let state = TraceState { file, pending: None, changed: false, operations: 0, error: None };
+        self.trace = Some(Trace { state: Mutex::new(state) });
+        Ok(())
+    }
+
+    /// Stops recording, ending the trace with `END <state hash>`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(count)` with the number of operations recorded.
+    /// - `Err(e)` if no trace is being recorded, or writing any line failed.
+    pub fn stop_trace(&mut self) -> io::Result<usize> {
+        let trace = self.trace.take().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no trace is being recorded"))?;
+        let mut state = trace.state.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.resolve();
+        state.write(&format!("END {:016x}", self.state_hash()));
+        match state.error.take() {
+            Some(e) => Err(e),
+            None => state.file.sync_data().map(|_| state.operations),
+        }
+    }
+
+    /// Returns `true` while a trace is being recorded.
+    pub fn is_tracing(&self) -> bool {
+        self.trace.is_some()
+    }
+
+    /// Records an operation or transaction line about to be applied.
+    pub(crate) fn trace_line(&self, line: &str) {
+        self.with_trace(|state| {
+            state.resolve();
+            if matches!(line, "BEGIN" | "COMMIT" | "ROLLBACK") {
+                state.write(line);
+            } else {
+                state.pending = Some(line.to_string());
+                state.changed = false;
+                state.operations += 1;
+            }
+        });
+    }
+
+    /// Notes that the pending operation succeeded.
+    pub(crate) fn trace_change(&self) {
+        self.with_trace(|state| state.changed = true);
+    }
+
+    /// Records a read of block `id` and what it returned.
+    pub(crate) fn trace_read(&self, id: BlockId, data: Option<&[u8]>) {
+        self.with_trace(|state| {
+            state.resolve();
+            state.operations += 1;
+            match data {
+                Some(data) => state.write(&format!("READ {} -> OK {}", id, encode_hex(data))),
+                None => state.write(&format!("READ {} -> ERR", id)),
+            }
+        });
+    }
+
+    fn with_trace(&self, f: impl FnOnce(&mut TraceState)) {
+        if let Some(trace) = &self.trace {
+            f(&mut trace.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner()));
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::memory_manager::MemoryManager;
+
+impl MemoryManager {
+    /// Starts a transaction.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the transaction was started.
+    /// - `None` if a transaction is already open. Transactions do not nest.
+    ///
+    /// # Behavior
+    ///
+    /// Takes a copy of memory and the allocation table. Inserts, updates, and
+    /// deletes made afterwards apply immediately, but can be discarded as a
+    /// group with `rollback()` or kept with `commit()`.
+    pub fn begin_transaction(&mut self) -> Option<()> {
+        if self.transaction.is_some() || !self.journal_append("BEGIN") {
+            return None;
+        }
+        self.transaction = Some(self.capture());
+        Some(())
+    }
+
+    /// Keeps every change made since `begin_transaction()`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if a transaction was committed.
+    /// - `None` if no transaction is open.
+    pub fn commit(&mut self) -> Option<()> {
+        if self.transaction.is_none() || !self.journal_append("COMMIT") {
+            return None;
+        }
+        self.transaction = None;
+        Some(())
+    }
+
+    /// Discards every change made since `begin_transaction()`.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(())` if the manager was rolled back.
+    /// - `None` if no transaction is open.
+    ///
+    /// # Behavior
+    ///
+    /// Change notifications already sent to subscribers are not retracted.
+    pub fn rollback(&mut self) -> Option<()> {
+        if self.transaction.is_none() || !self.journal_append("ROLLBACK") {
+            return None;
+        }
+        let snapshot = self.transaction.take()?;
+        self.restore_snapshot(&snapshot);
+        Some(())
+    }
+
+    /// Returns `true` while a transaction is open.
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.is_some()
+    }
+}
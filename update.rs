@@ -1,23 +1,31 @@
-use crate::memory_manager::MemoryManager; // Import the MemoryManager module
+use log::{info, warn};
+
+use crate::error::MemoryError;
+use crate::memory_manager::{BlockId, MemoryManager}; // Import the MemoryManager module
 
 // Updates an existing block of memory with new data for the given ID.
 //
 // Parameters:
 // - `manager`: A mutable reference to the MemoryManager instance.
-// - `id`: The ID (usize) of the memory block to update.
+// - `id`: The ID of the memory block to update.
 // - `data`: The new data (Vec<u8>) to write into the memory block.
 //
-// Behavior:
-// - If the ID exists and the new data fits within the originally allocated size,
-//   the memory is updated and a success message is printed.
-// - If the ID is not found or the new data is too large, a failure message is printed.
-pub fn update(manager: &mut MemoryManager, id: usize, data: Vec<u8>) {
+// Returns:
+// - `Ok(())` if the ID exists and the new data fits within the originally allocated size;
+//   a success message is logged at info level.
+// - `Err(reason)` if the ID is not found or the new data is too large; the reason,
+//   including the requested size and the block's capacity, is logged as a warning.
+pub fn update(manager: &mut MemoryManager, id: BlockId, data: Vec<u8>) -> Result<(), MemoryError> {
     // Attempt to update the memory block with the new data
-    if manager.update(id as u16, data).is_some() {
+    let requested = data.len();
+    if manager.update(id, data).is_some() {
         // Update was successful
-        println!("Update successful for ID {}", id);
+        info!("Update successful for ID {}", id);
+        Ok(())
     } else {
         // Update failed due to size overflow or missing ID
-        println!("Update failed for ID {}: Not enough space or ID not found", id);
+        let reason = manager.update_failure(id, requested);
+        warn!("Update failed for ID {}: {}", id, reason);
+        Err(reason)
     }
 }
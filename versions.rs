@@ -0,0 +1,69 @@
+use crate::memory_manager::{BlockId, MemoryManager};
+
+impl MemoryManager {
+    /// Creates a new `MemoryManager` that keeps the previous `depth` versions
+    /// of every block. See `set_version_depth`.
+    pub fn with_versions(depth: usize) -> Self {
+        Self { version_depth: depth, ..Self::new() }
+    }
+
+    /// Sets how many previous versions of each block `update` keeps.
+    ///
+    /// # Behavior
+    ///
+    /// Zero (the default) turns versioning off and discards every kept
+    /// version; shrinking the depth discards the oldest ones. Versions are
+    /// kept beside the allocation table, not in the memory block, so they
+    /// never take space from new blocks. Renames carry them along; deletes,
+    /// splits, and merges discard them.
+    pub fn set_version_depth(&mut self, depth: usize) {
+        self.version_depth = depth;
+        for versions in self.versions.values_mut() {
+            versions.truncate(depth);
+        }
+        self.versions.retain(|_, versions| !versions.is_empty());
+    }
+
+    /// Reads a version of a block.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The block to read.
+    /// - `n`: How many updates back to go. 0 is the current data, as `read`
+    ///   returns it, 1 the data before the latest update, and so on.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(data)` if the block exists and that version is still kept.
+    /// - `None` otherwise.
+    pub fn read_version(&self, id: BlockId, n: usize) -> Option<Vec<u8>> {
+        match n {
+            0 => self.read(id),
+            _ => self.versions.get(&id)?.get(n - 1).cloned(),
+        }
+    }
+
+    /// Returns every kept version of a block, newest first.
+    ///
+    /// # Returns
+    ///
+    /// - `Some(versions)`, where `versions[n]` is `read_version(id, n)`: the
+    ///   current data followed by up to `set_version_depth` previous versions.
+    /// - `None` if the block does not exist or cannot be read.
+    pub fn history(&self, id: BlockId) -> Option<Vec<Vec<u8>>> {
+        let mut versions = vec![self.read(id)?];
+        versions.extend(self.versions.get(&id).into_iter().flatten().cloned());
+        Some(versions)
+    }
+
+    /// Keeps `data` as the newest previous version of block `id`, dropping
+    /// the oldest past the depth. Does nothing with versioning off.
+    pub(crate) fn keep_version(&mut self, id: BlockId, data: Vec<u8>) {
+        if self.version_depth == 0 {
+            return;
+        }
+        let versions = self.versions.entry(id).or_default();
+        versions.push_front(data);
+        versions.truncate(self.version_depth);
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+
+use crate::error::MemoryError;
+use crate::memory_manager::{BlockId, MemoryManager};
+
+/// Virtual bases are multiples of this many bytes, and consecutive mappings
+/// are separated by at least this much unmapped space.
+pub const PAGE_SIZE: usize = 0x1000;
+
+/// Why a virtual memory access failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VmError {
+    /// No mapped block holds the byte at this virtual address.
+    SegmentationFault { vaddr: usize },
+    /// The block is stored inline, compressed, or encrypted, so its bytes
+    /// have no fixed physical address to translate to.
+    NotAddressable { id: BlockId },
+    /// The block is already mapped at this virtual base.
+    AlreadyMapped { id: BlockId, base: usize },
+    /// The underlying manager refused the operation.
+    Memory(MemoryError),
+}
+
+impl fmt::Display for VmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VmError::SegmentationFault { vaddr } => write!(f, "segmentation fault at virtual address {:#x}", vaddr),
+            VmError::NotAddressable { id } => write!(f, "block {} has no physical address", id),
+            VmError::AlreadyMapped { id, base } => write!(f, "block {} is already mapped at {:#x}", id, base),
+            VmError::Memory(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for VmError {}
+
+/// A front-end that gives each block a virtual base address.
+///
+/// Virtual addresses are translated through a table of mappings to a
+/// block and an offset into it, then through the allocation table to a
+/// physical address in memory, the way a page table and an MMU would.
+/// Any access that does not land inside a mapped block raises
+/// `VmError::SegmentationFault`. Address 0 is never mapped, and an
+/// unmapped page follows every mapping, so null pointers and overruns
+/// both fault.
+pub struct VirtualMemory {
+    manager: MemoryManager,
+    mappings: BTreeMap<usize, BlockId>, // virtual base -> block mapped there
+    bases: HashMap<BlockId, usize>,     // block -> its virtual base
+    next_base: usize,                   // Where the next mapping goes
+}
+
+impl VirtualMemory {
+    /// Wraps `manager`. None of its blocks are mapped yet; see `map`.
+    pub fn new(manager: MemoryManager) -> Self {
+        Self { manager, mappings: BTreeMap::new(), bases: HashMap::new(), next_base: PAGE_SIZE }
+    }
+
+    /// Returns the underlying manager.
+    pub fn manager(&self) -> &MemoryManager {
+        &self.manager
+    }
+
+    /// Returns the underlying manager for changes outside the virtual view.
+    /// Mappings of blocks deleted this way fault until they are unmapped.
+    pub fn manager_mut(&mut self) -> &mut MemoryManager {
+        &mut self.manager
+    }
+
+    /// Unwraps the manager, discarding all mappings.
+    pub fn into_inner(self) -> MemoryManager {
+        self.manager
+    }
+
+    /// Stores `data` under `id` and maps it.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(base)`: The block's virtual base address.
+    /// - `Err(VmError::Memory(e))` if the manager refused the insert.
+    pub fn insert(&mut self, id: BlockId, data: Vec<u8>) -> Result<usize, VmError> {
+        let size = data.len();
+        if self.manager.insert(id, data).is_none() {
+            return Err(VmError::Memory(self.manager.insert_failure(id, size)));
+        }
+        self.map(id)
+    }
+
+    /// Gives an existing block a virtual base address.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(base)`: The block's virtual base address. Bases are handed out
+    ///   in increasing order and never reused.
+    /// - `Err(VmError::AlreadyMapped)` if the block already has one.
+    /// - `Err(VmError::Memory(MemoryError::NotFound))` if the block does not exist.
+    pub fn map(&mut self, id: BlockId) -> Result<usize, VmError> {
+        if let Some(&base) = self.bases.get(&id) {
+            return Err(VmError::AlreadyMapped { id, base });
+        }
+        let block = self.manager.allocations.get(&id).ok_or(VmError::Memory(MemoryError::NotFound { id }))?;
+        // Leave room for inline values to grow to the threshold
+        let reach = if block.inline.is_some() { self.manager.inline_threshold } else { block.size };
+        let reach = reach.max(block.raw_size);
+
+        let base = self.next_base;
+        self.next_base = base + reach.next_multiple_of(PAGE_SIZE) + PAGE_SIZE;
+        self.mappings.insert(base, id);
+        self.bases.insert(id, base);
+        Ok(base)
+    }
+
+    /// Removes a block's mapping, leaving the block itself in place.
+    /// Returns `None` if it was not mapped.
+    pub fn unmap(&mut self, id: BlockId) -> Option<()> {
+        let base = self.bases.remove(&id)?;
+        self.mappings.remove(&base);
+        Some(())
+    }
+
+    /// Returns the virtual base address of block `id`, if it is mapped.
+    pub fn base_of(&self, id: BlockId) -> Option<usize> {
+        self.bases.get(&id).copied()
+    }
+
+    /// Translates a virtual address to a physical address in memory.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(paddr)`: The index in memory holding the byte at `vaddr`.
+    /// - `Err(VmError::SegmentationFault)` if no mapped block holds that byte.
+    /// - `Err(VmError::NotAddressable)` if the block's bytes are not stored
+    ///   as-is in memory.
+    pub fn translate(&self, vaddr: usize) -> Result<usize, VmError> {
+        let (id, offset) = self.resolve(vaddr)?;
+        let block = &self.manager.allocations[&id];
+        if block.inline.is_some() || block.compressed || block.nonce.is_some() {
+            return Err(VmError::NotAddressable { id });
+        }
+        if offset >= block.size {
+            return Err(VmError::SegmentationFault { vaddr });
+        }
+        Ok(block.start + offset)
+    }
+
+    /// Reads `len` bytes starting at virtual address `vaddr`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(data)` if every byte lies inside the same mapped block.
+    /// - `Err(VmError::SegmentationFault)` at the first byte that does not.
+    /// - `Err(VmError::Memory(e))` if the block cannot be read.
+    ///
+    /// # Behavior
+    ///
+    /// Works for blocks of any encoding, since it reads through the manager.
+    pub fn vread(&self, vaddr: usize, len: usize) -> Result<Vec<u8>, VmError> {
+        let (id, offset) = self.resolve(vaddr)?;
+        let data = self.manager.read_cow(id).ok_or_else(|| VmError::Memory(self.manager.read_failure(id)))?;
+        check_range(vaddr, offset, len, data.len())?;
+        Ok(data[offset..offset + len].to_vec())
+    }
+
+    /// Writes `data` starting at virtual address `vaddr`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(())` if every byte lies inside the same mapped block.
+    /// - `Err(VmError::SegmentationFault)` at the first byte that does not.
+    ///   Nothing is written.
+    /// - `Err(VmError::Memory(e))` if the block cannot be read or updated.
+    ///
+    /// # Behavior
+    ///
+    /// The block is rewritten through `update`, so the write is journaled,
+    /// undoable, and re-encoded like any other update.
+    pub fn vwrite(&mut self, vaddr: usize, data: &[u8]) -> Result<(), VmError> {
+        let (id, offset) = self.resolve(vaddr)?;
+        let mut contents = self.manager.peek(id).ok_or_else(|| VmError::Memory(self.manager.read_failure(id)))?;
+        check_range(vaddr, offset, data.len(), contents.len())?;
+        contents[offset..offset + data.len()].copy_from_slice(data);
+        let size = contents.len();
+        self.manager.update(id, contents).ok_or_else(|| VmError::Memory(self.manager.update_failure(id, size)))
+    }
+
+    /// Finds the mapped block whose range may hold `vaddr`, and the offset into it.
+    fn resolve(&self, vaddr: usize) -> Result<(BlockId, usize), VmError> {
+        let fault = VmError::SegmentationFault { vaddr };
+        let (&base, &id) = self.mappings.range(..=vaddr).next_back().ok_or(fault.clone())?;
+        if !self.manager.allocations.contains_key(&id) {
+            return Err(fault);
+        }
+        Ok((id, vaddr - base))
+    }
+}
+
+/// Faults unless `len` bytes from `offset` fit in a block of `size` bytes.
+fn check_range(vaddr: usize, offset: usize, len: usize, size: usize) -> Result<(), VmError> {
+    if offset.checked_add(len).is_none_or(|end| end > size) {
+        return Err(VmError::SegmentationFault { vaddr: vaddr + size.saturating_sub(offset) });
+    }
+    Ok(())
+}
@@ -0,0 +1,76 @@
+//! WebAssembly bindings, built with the `wasm` feature.
+//!
+//! Build with
+//! `cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib`,
+//! then `wasm-bindgen --target web --out-dir web/pkg target/wasm32-unknown-unknown/release/project2.wasm`;
+//! the page in `web/index.html` loads the result from `web/pkg` and draws the memory layout.
+
+use wasm_bindgen::prelude::*;
+
+use crate::memory_manager::{BlockId, MemoryManager, SortKey};
+
+/// A `MemoryManager` exported to JavaScript as `MemoryManager`.
+///
+/// Every method returns its result instead of printing, since a browser has
+/// no standard output.
+#[wasm_bindgen(js_name = MemoryManager)]
+pub struct WasmMemoryManager {
+    inner: MemoryManager,
+}
+
+#[wasm_bindgen(js_class = MemoryManager)]
+impl WasmMemoryManager {
+    /// Creates a manager with `capacity` bytes of memory.
+    #[wasm_bindgen(constructor)]
+    pub fn new(capacity: usize) -> Self {
+        Self { inner: MemoryManager::with_capacity(capacity) }
+    }
+
+    /// Stores `data` under `id`. Returns `false` if it could not be stored.
+    pub fn insert(&mut self, id: BlockId, data: &[u8]) -> bool {
+        self.inner.insert(id, data.to_vec()).is_some()
+    }
+
+    /// Returns the data stored under `id`, or `undefined`.
+    pub fn read(&self, id: BlockId) -> Option<Vec<u8>> {
+        self.inner.read(id)
+    }
+
+    /// Replaces the data stored under `id`. Returns `false` on failure.
+    pub fn update(&mut self, id: BlockId, data: &[u8]) -> bool {
+        self.inner.update(id, data.to_vec()).is_some()
+    }
+
+    /// Deletes the data stored under `id`. Returns `false` if it did not exist.
+    pub fn delete(&mut self, id: BlockId) -> bool {
+        self.inner.delete(id).is_some()
+    }
+
+    /// Explains why inserting `requested` bytes under `id` failed.
+    #[wasm_bindgen(js_name = insertFailure)]
+    pub fn insert_failure(&self, id: BlockId, requested: usize) -> String {
+        self.inner.insert_failure(id, requested).to_string()
+    }
+
+    /// Returns the dump text, blocks ordered by address.
+    pub fn dump(&self) -> String {
+        self.inner.dump_text_sorted_by(SortKey::Address)
+    }
+
+    /// Returns the allocation table and statistics as JSON, as `export_json` does.
+    #[wasm_bindgen(js_name = exportJson)]
+    pub fn export_json(&self) -> String {
+        self.inner.export_json()
+    }
+
+    /// Returns the free regions flattened as `[start, size, start, size, ...]`.
+    #[wasm_bindgen(js_name = freeRegions)]
+    pub fn free_regions(&self) -> Vec<usize> {
+        self.inner.free_regions().flat_map(|(start, size)| [start, size]).collect()
+    }
+
+    /// Returns the total size of memory in bytes.
+    pub fn capacity(&self) -> usize {
+        self.inner.stats().capacity
+    }
+}
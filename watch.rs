@@ -0,0 +1,126 @@
+use crate::events::Event;
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::subscriptions::Change;
+
+/// What a watchpoint watches.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Watchpoint {
+    Block(BlockId),                      // The data of one block, wherever it lives
+    Range { start: usize, len: usize }, // The bytes of memory at `start..start + len`
+}
+
+/// A watchpoint and the bytes it covered when last checked.
+pub(crate) struct Watch {
+    point: Watchpoint,
+    last: Option<Vec<u8>>, // None while a watched block does not exist
+}
+
+impl MemoryManager {
+    /// Watches block `id`, like a debugger watchpoint.
+    ///
+    /// # Behavior
+    ///
+    /// Every successful read of the block emits `Event::WatchRead` with the
+    /// data read, and every insert, update, or delete under `id` emits
+    /// `Event::WatchWrite` with the data before and after. The block does
+    /// not need to exist yet. Watching it again has no effect. Events go to
+    /// the `on_event` callbacks.
+    pub fn watch(&mut self, id: BlockId) -> Watchpoint {
+        self.add_watch(Watchpoint::Block(id))
+    }
+
+    /// Watches the bytes of memory at `start..start + len`.
+    ///
+    /// # Behavior
+    ///
+    /// Reading any block that overlaps the range emits `Event::WatchRead`
+    /// with the range's bytes. Whenever a change or a compaction leaves
+    /// different bytes in the range, `Event::WatchWrite` is emitted with the
+    /// bytes before and after, so writes of identical bytes go unreported.
+    /// Bytes past the end of memory are never watched.
+    pub fn watch_range(&mut self, start: usize, len: usize) -> Watchpoint {
+        self.add_watch(Watchpoint::Range { start, len })
+    }
+
+    /// Removes a watchpoint. Returns `None` if it was not set.
+    pub fn unwatch(&mut self, point: Watchpoint) -> Option<()> {
+        let index = self.watches.iter().position(|watch| watch.point == point)?;
+        self.watches.remove(index);
+        Some(())
+    }
+
+    /// Returns every watchpoint, in the order they were set.
+    pub fn watchpoints(&self) -> Vec<Watchpoint> {
+        self.watches.iter().map(|watch| watch.point).collect()
+    }
+
+    /// Emits `Event::WatchRead` for every watchpoint covering the read of
+    /// block `id`, which returned `data`.
+    pub(crate) fn watch_read(&self, id: BlockId, data: &[u8]) {
+        for watch in &self.watches {
+            let covered = match watch.point {
+                Watchpoint::Block(watched) if watched == id => data.to_vec(),
+                Watchpoint::Range { start, len } => {
+                    let Some(block) = self.allocations.get(&id).filter(|block| block.inline.is_none()) else {
+                        continue;
+                    };
+                    if block.start >= start.saturating_add(len) || start >= block.start + block.size {
+                        continue;
+                    }
+                    self.watched_bytes(start, len)
+                }
+                Watchpoint::Block(_) => continue,
+            };
+            self.emit(Event::WatchRead { point: watch.point, id, data: covered });
+        }
+    }
+
+    /// Emits `Event::WatchWrite` for every watchpoint `change` affected.
+    pub(crate) fn watch_change(&mut self, change: &Change) {
+        self.check_watches(Some(change.id()));
+    }
+
+    /// Compares every watchpoint with what it covers now, emitting
+    /// `Event::WatchWrite` for those that changed. `cause` is the block
+    /// whose change is being checked, if any; watched blocks only report
+    /// changes they caused.
+    pub(crate) fn check_watches(&mut self, cause: Option<BlockId>) {
+        if self.watches.is_empty() {
+            return;
+        }
+        let mut watches = std::mem::take(&mut self.watches);
+        for watch in &mut watches {
+            let now = match watch.point {
+                Watchpoint::Block(id) if cause == Some(id) => self.peek(id),
+                Watchpoint::Block(_) => continue,
+                Watchpoint::Range { start, len } => {
+                    let now = Some(self.watched_bytes(start, len));
+                    if now == watch.last {
+                        continue;
+                    }
+                    now
+                }
+            };
+            let old = std::mem::replace(&mut watch.last, now.clone());
+            self.emit(Event::WatchWrite { point: watch.point, id: cause, old, new: now });
+        }
+        self.watches = watches;
+    }
+
+    fn add_watch(&mut self, point: Watchpoint) -> Watchpoint {
+        if !self.watches.iter().any(|watch| watch.point == point) {
+            let last = match point {
+                Watchpoint::Block(id) => self.peek(id),
+                Watchpoint::Range { start, len } => Some(self.watched_bytes(start, len)),
+            };
+            self.watches.push(Watch { point, last });
+        }
+        point
+    }
+
+    /// Returns the bytes of memory at `start..start + len`, cut short at the end of memory.
+    fn watched_bytes(&self, start: usize, len: usize) -> Vec<u8> {
+        let end = start.saturating_add(len).min(self.memory.len());
+        self.memory.get(start.min(end)..end).unwrap_or_default().to_vec()
+    }
+}
@@ -0,0 +1,191 @@
+use crate::erase::splitmix64;
+use crate::memory_manager::{BlockId, MemoryManager};
+use crate::operation::{OpResult, Operation};
+
+/// How the sizes of generated blocks are chosen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SizeDistribution {
+    /// Every block has this many bytes.
+    Fixed(usize),
+    /// Sizes are spread evenly over `min..=max`.
+    Uniform { min: usize, max: usize },
+    /// Mostly small sizes with a long tail of large ones, averaging about
+    /// `mean` and never above `max`, like many real heaps.
+    Exponential { mean: usize, max: usize },
+}
+
+impl SizeDistribution {
+    /// Draws one size.
+    fn sample(&self, rng: &mut u64) -> usize {
+        match *self {
+            SizeDistribution::Fixed(size) => size,
+            SizeDistribution::Uniform { min, max } => {
+                let span = max.saturating_sub(min) as u64 + 1;
+                min + (splitmix64(rng) % span) as usize
+            }
+            SizeDistribution::Exponential { mean, max } => {
+                let uniform = unit(rng);
+                // Inverse transform sampling; 1 - u is never 0
+                let size = -(1.0 - uniform).ln() * mean as f64;
+                (size as usize).clamp(1, max.max(1))
+            }
+        }
+    }
+}
+
+/// Settings for a randomized workload, turned into operations by `generate`.
+///
+/// Each operation deletes a random live block with probability `churn`,
+/// rewrites one with a freshly drawn size, capped at the size it was
+/// inserted with since blocks cannot grow, with probability `updates`, reads
+/// one with probability `reads`, and otherwise inserts a new block, or
+/// deletes one instead once `live_blocks` blocks are alive. The live set
+/// grows to `live_blocks` as long as inserts outweigh `churn`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Workload {
+    pub seed: u64,               // Same seed, same operations
+    pub operations: usize,       // Number of operations to generate
+    pub live_blocks: usize,      // Blocks kept alive at steady state
+    pub sizes: SizeDistribution, // Sizes of inserted and updated data
+    pub churn: f64,              // Probability an operation is a delete
+    pub updates: f64,            // Probability an operation is an update
+    pub reads: f64,              // Probability an operation is a read
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            operations: 10_000,
+            live_blocks: 100,
+            sizes: SizeDistribution::Uniform { min: 8, max: 256 },
+            churn: 0.3,
+            updates: 0.2,
+            reads: 0.2,
+        }
+    }
+}
+
+impl Workload {
+    /// Generates the operations, deterministically from `seed`.
+    ///
+    /// # Returns
+    ///
+    /// `operations` operations that only read, update, and delete blocks
+    /// inserted earlier in the sequence, so they all succeed against a
+    /// manager with enough free space. Block IDs are never reused, and each
+    /// block's data is filled with its ID's low byte so reads can be checked.
+    pub fn generate(&self) -> Vec<Operation> {
+        let mut rng = self.seed;
+        let mut live: Vec<(BlockId, usize)> = Vec::new(); // Live blocks and the sizes they were inserted with
+        let mut next_id: BlockId = 0;
+        let mut ops = Vec::with_capacity(self.operations);
+
+        while ops.len() < self.operations {
+            let roll = unit(&mut rng);
+            let pick = |rng: &mut u64, live: &[(BlockId, usize)]| (splitmix64(rng) % live.len() as u64) as usize;
+            let op = if live.is_empty() {
+                None
+            } else if roll < self.churn || roll >= self.churn + self.updates + self.reads && live.len() >= self.live_blocks {
+                Some(Operation::Delete { id: live.swap_remove(pick(&mut rng, &live)).0 })
+            } else if roll < self.churn + self.updates {
+                // Blocks never grow past the size they were inserted with
+                let (id, size) = live[pick(&mut rng, &live)];
+                Some(Operation::Update { id, data: vec![id as u8; self.sizes.sample(&mut rng).min(size)] })
+            } else if roll < self.churn + self.updates + self.reads {
+                Some(Operation::Read { id: live[pick(&mut rng, &live)].0 })
+            } else {
+                None
+            };
+            ops.push(op.unwrap_or_else(|| {
+                let id = next_id;
+                next_id += 1;
+                let size = self.sizes.sample(&mut rng);
+                live.push((id, size));
+                Operation::Insert { id, data: vec![id as u8; size] }
+            }));
+        }
+        ops
+    }
+}
+
+/// A snapshot of how fragmented memory is, taken while a workload runs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FragmentationSample {
+    pub operation: usize,    // Operations applied before the sample was taken
+    pub free_bytes: usize,   // Total free bytes
+    pub largest_free: usize, // Size of the largest free region
+    pub free_regions: usize, // Number of separate free regions
+    pub fragmentation: f64,  // See `MemoryManager::fragmentation`
+}
+
+/// The outcome of `run_workload`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkloadReport {
+    pub operations: usize,                 // Operations applied
+    pub failed: usize,                     // Operations that failed, usually for lack of space
+    pub samples: Vec<FragmentationSample>, // Fragmentation over time
+}
+
+impl WorkloadReport {
+    /// Returns the mean fragmentation over all samples, or 0.0 without samples.
+    pub fn mean_fragmentation(&self) -> f64 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().map(|sample| sample.fragmentation).sum::<f64>() / self.samples.len() as f64
+    }
+}
+
+impl MemoryManager {
+    /// Returns how fragmented the free space is, from 0.0 to 1.0.
+    ///
+    /// Computed as `1 - largest_free / free_bytes`: 0.0 when all free
+    /// space is one region (or there is none), approaching 1.0 as it is
+    /// scattered over many small holes.
+    pub fn fragmentation(&self) -> f64 {
+        let free = self.allocator.free_bytes();
+        if free == 0 {
+            return 0.0;
+        }
+        1.0 - self.allocator.largest() as f64 / free as f64
+    }
+
+    /// Applies `ops` in order, sampling fragmentation along the way.
+    ///
+    /// # Returns
+    ///
+    /// A `WorkloadReport`. A sample is taken before the first operation,
+    /// after every `sample_every` operations (never, if 0), and after the
+    /// last one. Failed operations are counted and do not stop the run.
+    pub fn run_workload(&mut self, ops: &[Operation], sample_every: usize) -> WorkloadReport {
+        let mut report = WorkloadReport { operations: ops.len(), failed: 0, samples: vec![self.fragmentation_sample(0)] };
+        for (i, op) in ops.iter().enumerate() {
+            if self.apply_one(op) == OpResult::Failed {
+                report.failed += 1;
+            }
+            if sample_every > 0 && (i + 1) % sample_every == 0 && i + 1 < ops.len() {
+                report.samples.push(self.fragmentation_sample(i + 1));
+            }
+        }
+        if !ops.is_empty() {
+            report.samples.push(self.fragmentation_sample(ops.len()));
+        }
+        report
+    }
+
+    fn fragmentation_sample(&self, operation: usize) -> FragmentationSample {
+        FragmentationSample {
+            operation,
+            free_bytes: self.allocator.free_bytes(),
+            largest_free: self.allocator.largest(),
+            free_regions: self.allocator.regions().len(),
+            fragmentation: self.fragmentation(),
+        }
+    }
+}
+
+/// Returns a value in `0.0..1.0`.
+fn unit(rng: &mut u64) -> f64 {
+    (splitmix64(rng) >> 11) as f64 / (1u64 << 53) as f64
+}